@@ -1,16 +1,11 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use std::fs::File;
-use std::io::Write;
-
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct FloatTestCase {
-    pub f64_value: f64,
-}
+pub use zorsh_gen::fixtures::FloatTestCase;
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::f64;
+    use std::fs::File;
+    use std::io::Write;
 
     fn write_test_data(data: &FloatTestCase, name: &str) -> Result<(), std::io::Error> {
         let serialized = borsh::to_vec(&data).unwrap();
@@ -25,17 +20,47 @@ mod tests {
 
         // Test cases for special values
         let test_cases = vec![
-            ("infinity", FloatTestCase { f64_value: f64::INFINITY }),
-            ("neg_infinity", FloatTestCase { f64_value: f64::NEG_INFINITY }),
-            ("max", FloatTestCase { f64_value: f64::MAX }),
-            ("min", FloatTestCase { f64_value: f64::MIN }),
+            (
+                "infinity",
+                FloatTestCase {
+                    f64_value: f64::INFINITY,
+                },
+            ),
+            (
+                "neg_infinity",
+                FloatTestCase {
+                    f64_value: f64::NEG_INFINITY,
+                },
+            ),
+            (
+                "max",
+                FloatTestCase {
+                    f64_value: f64::MAX,
+                },
+            ),
+            (
+                "min",
+                FloatTestCase {
+                    f64_value: f64::MIN,
+                },
+            ),
             // Note: Rust's MIN_POSITIVE is equivalent to JavaScript's MIN_VALUE
-            ("min_value", FloatTestCase { f64_value: f64::MIN_POSITIVE }),
+            (
+                "min_value",
+                FloatTestCase {
+                    f64_value: f64::MIN_POSITIVE,
+                },
+            ),
             ("zero", FloatTestCase { f64_value: 0.0 }),
             ("neg_zero", FloatTestCase { f64_value: -0.0 }),
             ("one", FloatTestCase { f64_value: 1.0 }),
             ("neg_one", FloatTestCase { f64_value: -1.0 }),
-            ("pi", FloatTestCase { f64_value: std::f64::consts::PI }),
+            (
+                "pi",
+                FloatTestCase {
+                    f64_value: std::f64::consts::PI,
+                },
+            ),
         ];
 
         for (name, test_case) in test_cases {
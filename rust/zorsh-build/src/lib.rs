@@ -0,0 +1,188 @@
+// src/lib.rs
+//! [`ZorshBuild`]: regenerating zorsh schemas from a build script, so that a frontend's
+//! generated module is rewritten whenever the contract's types change.
+use borsh::schema::BorshSchemaContainer;
+use borsh::{BorshDeserialize, BorshSchema};
+use std::path::PathBuf;
+use zorsh_gen::{
+    merge_containers, GenerationReport, GeneratorOptions, ZorshGenError, ZorshGenerator,
+};
+
+/// The generator and its options, for [`ZorshBuild::generator`], without a dependency of
+/// its own.
+pub use zorsh_gen;
+
+/// Generates a schema module from `build.rs`:
+///
+/// ```no_run
+/// // build.rs of a crate with the contract and zorsh-build as build dependencies
+/// use zorsh_build::ZorshBuild;
+///
+/// fn main() {
+///     ZorshBuild::new("../frontend/src/generated.ts")
+///         .schema_type::<borsh_test::GameState>()
+///         .schema_file("schemas/events.bin")
+///         .run()
+///         .expect("generating zorsh schemas");
+/// }
+/// ```
+///
+/// Cargo is told to run the script again when a schema file or a path given to
+/// [`ZorshBuild::rerun_if_changed`] changes, rather than when any file of the package
+/// does. Types come from build dependencies, which Cargo already reruns build scripts for.
+/// The output is only written when its contents change, so that a bundler watching it
+/// isn't triggered by every build.
+#[derive(Clone, Debug)]
+pub struct ZorshBuild {
+    output: PathBuf,
+    generator: ZorshGenerator,
+    inputs: Vec<Input>,
+    watched: Vec<PathBuf>,
+}
+
+/// What schemas are generated from.
+#[derive(Clone, Debug)]
+enum Input {
+    Type(BorshSchemaContainer),
+    File(PathBuf),
+}
+
+impl ZorshBuild {
+    /// Writes to `output`, relative to the package's directory, which build scripts run in.
+    pub fn new(output: impl Into<PathBuf>) -> Self {
+        Self {
+            output: output.into(),
+            generator: ZorshGenerator::default(),
+            inputs: Vec::new(),
+            watched: Vec::new(),
+        }
+    }
+
+    pub fn generator(mut self, generator: ZorshGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    pub fn options(self, options: GeneratorOptions) -> Self {
+        self.generator(options.into())
+    }
+
+    /// Generates the schemas of `T` and the types it refers to. The first type or file
+    /// given is the root, as for [`merge_containers`].
+    pub fn schema_type<T: BorshSchema + ?Sized>(mut self) -> Self {
+        self.inputs
+            .push(Input::Type(BorshSchemaContainer::for_type::<T>()));
+        self
+    }
+
+    /// Generates the schemas of a borsh-serialized `BorshSchemaContainer`, such as one
+    /// exported by the contract's build, rerunning when it changes.
+    pub fn schema_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.inputs.push(Input::File(path.into()));
+        self
+    }
+
+    /// Also reruns the build script when `path`, a file or directory, changes.
+    pub fn rerun_if_changed(mut self, path: impl Into<PathBuf>) -> Self {
+        self.watched.push(path.into());
+        self
+    }
+
+    /// Prints the `cargo:rerun-if-changed` directives and writes the output, returning the
+    /// report on generating it.
+    pub fn run(self) -> Result<GenerationReport, ZorshGenError> {
+        for directive in self.directives() {
+            println!("{}", directive);
+        }
+        self.generate()
+    }
+
+    /// The lines telling Cargo when to run the build script again.
+    fn directives(&self) -> Vec<String> {
+        let files = self.files().map(|path| path.display().to_string());
+        let watched = self.watched.iter().map(|path| path.display().to_string());
+        std::iter::once("build.rs".to_string())
+            .chain(files)
+            .chain(watched)
+            .map(|path| format!("cargo:rerun-if-changed={}", path))
+            .collect()
+    }
+
+    fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.inputs.iter().filter_map(|input| match input {
+            Input::File(path) => Some(path),
+            Input::Type(_) => None,
+        })
+    }
+
+    fn generate(&self) -> Result<GenerationReport, ZorshGenError> {
+        let mut containers = Vec::new();
+        for input in &self.inputs {
+            containers.push(match input {
+                Input::Type(container) => container.clone(),
+                Input::File(path) => {
+                    let bytes = std::fs::read(path)?;
+                    BorshSchemaContainer::try_from_slice(&bytes).map_err(|source| {
+                        ZorshGenError::Schema {
+                            input: path.display().to_string(),
+                            source,
+                        }
+                    })?
+                }
+            });
+        }
+        let container = merge_containers(&containers)?;
+        let mut options = self.generator.options().clone();
+        if let Some(banner) = &mut options.banner {
+            let names: Vec<String> = self
+                .files()
+                .map(|path| {
+                    let name = path.file_name().unwrap_or(path.as_os_str());
+                    name.to_string_lossy().into_owned()
+                })
+                .collect();
+            if !names.is_empty() {
+                banner.source.get_or_insert_with(|| names.join(", "));
+            }
+        }
+        if let Some(parent) = self.output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output = self.output.to_string_lossy();
+        ZorshGenerator::from(options).generate_container(&container, &output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh_test::{GameEvent, GameState};
+
+    #[test]
+    fn regenerates_from_a_build_script() {
+        let dir = std::env::temp_dir().join(format!("zorsh_build_{}", std::process::id()));
+        let schema = dir.join("events.bin");
+        let output = dir.join("generated/schemas.ts");
+        std::fs::create_dir_all(&dir).unwrap();
+        let container = BorshSchemaContainer::for_type::<GameEvent>();
+        std::fs::write(&schema, borsh::to_vec(&container).unwrap()).unwrap();
+
+        let build = ZorshBuild::new(&output)
+            .schema_type::<GameState>()
+            .schema_file(&schema)
+            .rerun_if_changed("src/state.rs");
+        assert_eq!(
+            build.directives(),
+            [
+                "cargo:rerun-if-changed=build.rs".to_string(),
+                format!("cargo:rerun-if-changed={}", schema.display()),
+                "cargo:rerun-if-changed=src/state.rs".to_string(),
+            ]
+        );
+        build.generate().unwrap();
+        let generated = std::fs::read_to_string(&output).unwrap();
+        assert!(generated.contains("export const GameStateSchema = b.struct({\n"));
+        assert!(generated.contains("export const GameEventSchema = b.enum({\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+// src/bin/cargo-zorsh/extract.rs
+//! Writing the schema containers of a crate's types, by building and running a program
+//! that depends on the crate, under the crate's `target` directory.
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `[package.metadata.zorsh]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Settings {
+    /// Paths of the types to generate schemas for, relative to the crate root.
+    pub types: Vec<String>,
+    /// The features to build the crate with, by default `schema` if it has it.
+    pub features: Option<Vec<String>>,
+    /// Where to write the TypeScript, relative to the package.
+    pub output: Option<PathBuf>,
+    /// Generate one module for all the types.
+    pub merge: bool,
+}
+
+/// A package, as `cargo metadata` describes it.
+#[derive(Debug)]
+pub struct Package {
+    pub name: String,
+    pub manifest_path: PathBuf,
+    pub target_directory: PathBuf,
+    workspace_root: PathBuf,
+    /// The features the package declares.
+    features: BTreeSet<String>,
+    pub settings: Settings,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<MetadataPackage>,
+    target_directory: PathBuf,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct MetadataPackage {
+    name: String,
+    manifest_path: PathBuf,
+    features: BTreeMap<String, Vec<String>>,
+    metadata: Option<serde_json::Value>,
+}
+
+/// Returns the cargo that ran this subcommand.
+fn cargo() -> Command {
+    Command::new(std::env::var_os("CARGO").unwrap_or("cargo".into()))
+}
+
+/// Runs `command`, returning its standard output, or what it printed to standard error
+/// if it failed.
+fn output(mut command: Command, what: &str) -> Result<Vec<u8>, String> {
+    let output = command
+        .output()
+        .map_err(|error| format!("couldn't run cargo to {}: {}", what, error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "couldn't {}:\n{}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+impl Package {
+    /// Finds the package `spec` names, or the one whose manifest is `manifest_path` or
+    /// the nearest `Cargo.toml`.
+    pub fn find(manifest_path: Option<&Path>, spec: Option<&str>) -> Result<Self, String> {
+        let mut command = cargo();
+        command.args(["metadata", "--format-version", "1", "--no-deps"]);
+        if let Some(path) = manifest_path {
+            command.arg("--manifest-path").arg(path);
+        }
+        let json = output(command, "read the crate's metadata")?;
+        let metadata: Metadata = serde_json::from_slice(&json)
+            .map_err(|error| format!("couldn't read `cargo metadata`: {}", error))?;
+
+        let manifest = match manifest_path {
+            Some(path) => Some(
+                path.canonicalize()
+                    .map_err(|e| format!("{}: {}", path.display(), e))?,
+            ),
+            None => {
+                let mut command = cargo();
+                command.args(["locate-project", "--message-format", "plain"]);
+                let path = output(command, "find Cargo.toml")?;
+                Some(PathBuf::from(String::from_utf8_lossy(&path).trim()))
+            }
+        };
+        Self::select(metadata, manifest.as_deref(), spec)
+    }
+
+    fn select(
+        metadata: Metadata,
+        manifest: Option<&Path>,
+        spec: Option<&str>,
+    ) -> Result<Self, String> {
+        let names: Vec<&str> = metadata.packages.iter().map(|p| p.name.as_str()).collect();
+        let names = names.join(", ");
+        let count = metadata.packages.len();
+        let package = metadata.packages.into_iter().find(|package| match spec {
+            Some(spec) => package.name == spec,
+            None => count == 1 || manifest == Some(package.manifest_path.as_path()),
+        });
+        let Some(package) = package else {
+            return Err(match spec {
+                Some(spec) => format!("no package `{}` in the workspace, only {}", spec, names),
+                None => format!(
+                    "the workspace has several packages; choose one of {} with `--package`",
+                    names
+                ),
+            });
+        };
+        let settings = match package
+            .metadata
+            .and_then(|mut m| m.get_mut("zorsh").map(|z| z.take()))
+        {
+            Some(settings) => serde_json::from_value(settings).map_err(|error| {
+                format!(
+                    "{}: `[package.metadata.zorsh]`: {}",
+                    package.manifest_path.display(),
+                    error
+                )
+            })?,
+            None => Settings::default(),
+        };
+        Ok(Self {
+            name: package.name,
+            manifest_path: package.manifest_path,
+            target_directory: metadata.target_directory,
+            workspace_root: metadata.workspace_root,
+            features: package.features.into_keys().collect(),
+            settings,
+        })
+    }
+
+    /// The directory of the package.
+    pub fn directory(&self) -> PathBuf {
+        self.manifest_path
+            .parent()
+            .map_or(PathBuf::from("."), Path::to_path_buf)
+    }
+
+    /// Builds and runs the program writing the containers of the `settings` types to
+    /// `schemas`, in `work`, returning the containers written.
+    pub fn extract(
+        &self,
+        settings: &Settings,
+        work: &Path,
+        schemas: &Path,
+    ) -> Result<Vec<PathBuf>, String> {
+        let types = named_types(&settings.types)?;
+        let features = match &settings.features {
+            Some(features) => features.clone(),
+            None if self.features.contains("schema") => vec!["schema".to_string()],
+            None => Vec::new(),
+        };
+        let failed = |path: &Path, error: std::io::Error| format!("{}: {}", path.display(), error);
+        let source = work.join("src");
+        std::fs::create_dir_all(&source).map_err(|error| failed(&source, error))?;
+        std::fs::create_dir_all(schemas).map_err(|error| failed(schemas, error))?;
+        let files = [
+            (
+                work.join("Cargo.toml"),
+                manifest(&self.name, &self.directory(), &features),
+            ),
+            (source.join("main.rs"), program(&types)),
+        ];
+        for (path, contents) in files {
+            std::fs::write(&path, contents).map_err(|error| failed(&path, error))?;
+        }
+        // Start from the workspace's lock file, for the versions it already builds with
+        let lock = self.workspace_root.join("Cargo.lock");
+        if lock.is_file() {
+            let copy = work.join("Cargo.lock");
+            std::fs::copy(&lock, &copy).map_err(|error| failed(&copy, error))?;
+        }
+
+        let mut command = cargo();
+        command
+            .args(["run", "--quiet", "--manifest-path"])
+            .arg(work.join("Cargo.toml"))
+            .arg("--")
+            .arg(schemas)
+            .env("CARGO_TARGET_DIR", &self.target_directory);
+        output(
+            command,
+            &format!("build and run `{}` to extract schemas", self.name),
+        )?;
+        Ok(types
+            .iter()
+            .map(|(_, name)| schemas.join(format!("{}.bin", name)))
+            .collect())
+    }
+}
+
+/// Pairs each type path with the name of its file, the last segment of the path
+/// without generic arguments.
+fn named_types(types: &[String]) -> Result<Vec<(String, String)>, String> {
+    let mut named: Vec<(String, String)> = Vec::new();
+    for ty in types {
+        let Ok(syn::Type::Path(path)) = syn::parse_str::<syn::Type>(ty) else {
+            return Err(format!(
+                "`{}` isn't a path to a type, such as `events::Event`",
+                ty
+            ));
+        };
+        let Some(last) = path.path.segments.last() else {
+            return Err(format!("`{}` isn't a path to a type", ty));
+        };
+        let name = last.ident.to_string();
+        if let Some((other, _)) = named.iter().find(|(_, n)| *n == name) {
+            return Err(format!(
+                "`{}` and `{}` would both be written to `{}.bin`",
+                other, ty, name
+            ));
+        }
+        named.push((ty.clone(), name));
+    }
+    Ok(named)
+}
+
+/// Returns the manifest of the program, depending on the package as `extracted`.
+fn manifest(package: &str, directory: &Path, features: &[String]) -> String {
+    let features: Vec<String> = features.iter().map(|f| format!("{:?}", f)).collect();
+    format!(
+        r#"# Written by cargo-zorsh to extract schema containers; regenerated on each run.
+[package]
+name = "zorsh-extract"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+# Not part of any workspace around it
+[workspace]
+
+[dependencies]
+borsh = {{ version = "1", features = ["unstable__schema"] }}
+extracted = {{ package = {:?}, path = {:?}, features = [{}] }}
+"#,
+        package,
+        directory.to_string_lossy(),
+        features.join(", ")
+    )
+}
+
+/// Returns the program writing the container of each of `types`, given as paths and
+/// file names.
+fn program(types: &[(String, String)]) -> String {
+    let writes: Vec<String> = types
+        .iter()
+        .map(|(ty, name)| format!("    write::<extracted::{}>(&directory, {:?});\n", ty, name))
+        .collect();
+    format!(
+        r#"// Written by cargo-zorsh to extract schema containers; regenerated on each run.
+use borsh::schema::BorshSchemaContainer;
+use std::path::{{Path, PathBuf}};
+
+fn write<T: borsh::BorshSchema + ?Sized>(directory: &Path, name: &str) {{
+    let container = BorshSchemaContainer::for_type::<T>();
+    let bytes = borsh::to_vec(&container).expect("schema containers serialize");
+    std::fs::write(directory.join(format!("{{}}.bin", name)), bytes).expect("the schema is written");
+}}
+
+fn main() {{
+    let directory = PathBuf::from(std::env::args_os().nth(1).expect("a directory to write to"));
+{}}}
+"#,
+        writes.concat()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_package_metadata() {
+        let metadata: Metadata = serde_json::from_value(serde_json::json!({
+            "packages": [
+                {
+                    "name": "contract",
+                    "manifest_path": "/ws/contract/Cargo.toml",
+                    "features": { "schema": [], "abi": ["schema"] },
+                    "metadata": { "zorsh": { "types": ["Contract"], "merge": true } },
+                },
+                {
+                    "name": "tools",
+                    "manifest_path": "/ws/tools/Cargo.toml",
+                    "features": {},
+                    "metadata": null,
+                },
+            ],
+            "target_directory": "/ws/target",
+            "workspace_root": "/ws",
+        }))
+        .unwrap();
+        let package = Package::select(metadata, Some(Path::new("/ws/contract/Cargo.toml")), None);
+        let package = package.unwrap();
+        assert_eq!(package.name, "contract");
+        assert_eq!(package.directory(), Path::new("/ws/contract"));
+        assert!(package.features.contains("schema"));
+        assert_eq!(
+            package.settings,
+            Settings {
+                types: vec!["Contract".to_string()],
+                merge: true,
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn writes_the_extracting_program() {
+        let types = named_types(&["Contract".to_string(), "events::Event<u8>".to_string()]);
+        let types = types.unwrap();
+        assert_eq!(
+            types[1],
+            ("events::Event<u8>".to_string(), "Event".to_string())
+        );
+        let program = program(&types);
+        assert!(program.contains("    write::<extracted::Contract>(&directory, \"Contract\");\n"));
+        assert!(program
+            .contains("    write::<extracted::events::Event<u8>>(&directory, \"Event\");\n}\n"));
+        let manifest = manifest(
+            "my-contract",
+            Path::new("/ws/contract"),
+            &["schema".to_string()],
+        );
+        assert!(manifest.contains(
+            "extracted = { package = \"my-contract\", path = \"/ws/contract\", features = [\"schema\"] }"
+        ));
+
+        assert!(named_types(&["a::Event".to_string(), "b::Event".to_string()]).is_err());
+        assert!(named_types(&["not a type".to_string()]).is_err());
+    }
+}
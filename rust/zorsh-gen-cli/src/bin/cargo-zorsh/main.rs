@@ -0,0 +1,188 @@
+// src/bin/cargo-zorsh/main.rs
+//! `cargo zorsh`: generates zorsh schemas for the types of a crate in one step. It builds
+//! the crate, with its `schema` feature if it has one, in a small program writing the
+//! schema container of each type configured in `[package.metadata.zorsh]`, then runs
+//! `zorsh-gen` on the containers, e.g.
+//!
+//! ```toml
+//! [package.metadata.zorsh]
+//! types = ["Contract", "events::Event"]
+//! output = "../frontend/src/generated/{name}.ts"
+//! ```
+//!
+//! Type paths are relative to the crate root, and paths to the package directory, where
+//! a `zorsh.toml` can hold the other generator settings.
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+mod extract;
+
+#[derive(Parser, Debug)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    /// Generate zorsh schemas for the types of a crate.
+    #[command(version, about)]
+    Zorsh {
+        #[command(subcommand)]
+        command: ZorshCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ZorshCommand {
+    /// Build the crate, extract the schema containers of its types, and generate zorsh
+    /// schemas from them.
+    Generate(GenerateArgs),
+    /// Build the crate and write the schema containers of its types, without generating.
+    Extract(ExtractArgs),
+}
+
+#[derive(Args, Debug)]
+struct ExtractArgs {
+    /// The `Cargo.toml` of the crate, by default the one cargo finds.
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+    /// The package to extract from, in a workspace of several.
+    #[arg(short, long, value_name = "SPEC")]
+    package: Option<String>,
+    /// Types to extract, instead of `types` in `[package.metadata.zorsh]`.
+    #[arg(long = "type", value_name = "PATH")]
+    types: Vec<String>,
+    /// Features of the crate to enable, instead of `features`, or `schema` if the crate
+    /// has it.
+    #[arg(short = 'F', long, value_delimiter = ',')]
+    features: Vec<String>,
+    /// Where to write the schema containers, by default `target/zorsh/{package}/schemas`.
+    #[arg(long, value_name = "DIR")]
+    schemas: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    #[command(flatten)]
+    extract: ExtractArgs,
+    /// Where to write the TypeScript, instead of `output`. `{name}` stands for each type's
+    /// name.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Generate one module for all the types, as `merge = true` does.
+    #[arg(long)]
+    merge: bool,
+    /// Further `zorsh-gen` flags, after `--`.
+    #[arg(last = true, value_name = "ZORSH_GEN_ARGS")]
+    zorsh_gen: Vec<String>,
+}
+
+impl ExtractArgs {
+    /// Builds the crate and writes the containers, returning the package's settings and
+    /// directory, and the containers written.
+    fn run(&self) -> Result<(extract::Settings, PathBuf, Vec<PathBuf>), String> {
+        let package =
+            extract::Package::find(self.manifest_path.as_deref(), self.package.as_deref())?;
+        let mut settings = package.settings.clone();
+        if !self.types.is_empty() {
+            settings.types = self.types.clone();
+        }
+        if !self.features.is_empty() {
+            settings.features = Some(self.features.clone());
+        }
+        if settings.types.is_empty() {
+            return Err(format!(
+                "no types to extract: give `--type`, or list them as `types` in the \
+                 `[package.metadata.zorsh]` of {}",
+                package.manifest_path.display()
+            ));
+        }
+        let work = package.target_directory.join("zorsh").join(&package.name);
+        let schemas = self.schemas.clone().unwrap_or(work.join("schemas"));
+        let written = package.extract(&settings, &work, &schemas)?;
+        for path in &written {
+            eprintln!("extracted {}", path.display());
+        }
+        Ok((settings, package.directory(), written))
+    }
+}
+
+impl GenerateArgs {
+    fn run(&self) -> Result<ExitCode, String> {
+        let (settings, directory, schemas) = self.extract.run()?;
+        let mut generator = Command::new(zorsh_gen());
+        generator.current_dir(&directory).args(&schemas);
+        // `zorsh-gen` runs in the package directory, which settings are relative to
+        let output = match &self.output {
+            Some(output) => Some(std::path::absolute(output).map_err(|e| e.to_string())?),
+            None => settings.output.map(|output| directory.join(output)),
+        };
+        if let Some(output) = output {
+            generator.arg("--output").arg(output);
+        }
+        if self.merge || settings.merge {
+            generator.arg("--merge");
+        }
+        generator.args(&self.zorsh_gen);
+        let status = generator
+            .status()
+            .map_err(|error| format!("couldn't run `zorsh-gen`: {}", error))?;
+        // `zorsh-gen` reports its own errors, and its exit code says what kind they are
+        Ok(match status.code() {
+            Some(code) => ExitCode::from(u8::try_from(code).unwrap_or(1)),
+            None => ExitCode::FAILURE,
+        })
+    }
+}
+
+/// Returns the `zorsh-gen` installed next to this binary, as `cargo install` puts them, or
+/// the one on the `PATH`.
+fn zorsh_gen() -> PathBuf {
+    let name = format!("zorsh-gen{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(&name))
+        .filter(|path| path.is_file())
+        .unwrap_or(PathBuf::from(name))
+}
+
+fn main() -> ExitCode {
+    let Cargo::Zorsh { command } = Cargo::parse();
+    let result = match command {
+        ZorshCommand::Generate(args) => args.run(),
+        ZorshCommand::Extract(args) => args.run().map(|_| ExitCode::SUCCESS),
+    };
+    result.unwrap_or_else(|error| {
+        eprintln!("error: {}", error);
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn parses_cargo_subcommands() {
+        Cargo::command().debug_assert();
+        let cargo = Cargo::try_parse_from([
+            "cargo",
+            "zorsh",
+            "generate",
+            "--type",
+            "Contract",
+            "-F",
+            "abi,schema",
+            "-o",
+            "web/{name}.ts",
+            "--",
+            "--strict",
+        ]);
+        let Cargo::Zorsh { command } = cargo.unwrap();
+        let ZorshCommand::Generate(args) = command else {
+            panic!("{:?}", command);
+        };
+        assert_eq!(args.extract.types, ["Contract"]);
+        assert_eq!(args.extract.features, ["abi", "schema"]);
+        assert_eq!(args.output, Some(PathBuf::from("web/{name}.ts")));
+        assert_eq!(args.zorsh_gen, ["--strict"]);
+    }
+}
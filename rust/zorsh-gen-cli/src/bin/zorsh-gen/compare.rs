@@ -0,0 +1,66 @@
+// src/bin/zorsh-gen/compare.rs
+//! `--check` and `--diff`: comparing generated output with the files on disk.
+use similar::{ChangeTag, TextDiff};
+use std::path::{Path, PathBuf};
+
+/// How a file on disk compares with what would be generated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    UpToDate,
+    /// Holding other contents, given.
+    Stale(String),
+    Missing,
+}
+
+/// Compares the file at `path` with `contents`.
+pub fn status(path: &Path, contents: &str) -> Result<Status, String> {
+    match std::fs::read_to_string(path) {
+        Ok(existing) if existing == contents => Ok(Status::UpToDate),
+        Ok(existing) => Ok(Status::Stale(existing)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Status::Missing),
+        Err(error) => Err(format!("{}: {}", path.display(), error)),
+    }
+}
+
+/// Returns a line for each of the `rendered` files whose contents on disk differ, saying
+/// how.
+pub fn stale(rendered: &[(PathBuf, String)]) -> Result<Vec<String>, String> {
+    let mut stale = Vec::new();
+    for (path, contents) in rendered {
+        let existing = match status(path, contents)? {
+            Status::UpToDate => continue,
+            Status::Stale(existing) => existing,
+            Status::Missing => {
+                stale.push(format!("{}: missing", path.display()));
+                continue;
+            }
+        };
+        let diff = TextDiff::from_lines(&existing, contents);
+        let count = |tag| diff.iter_all_changes().filter(|c| c.tag() == tag).count();
+        stale.push(format!(
+            "{}: out of date (+{} -{})",
+            path.display(),
+            count(ChangeTag::Insert),
+            count(ChangeTag::Delete)
+        ));
+    }
+    Ok(stale)
+}
+
+/// Returns a unified diff from the files on disk to the `rendered` ones, empty if they're
+/// all up to date.
+pub fn diff(rendered: &[(PathBuf, String)]) -> Result<String, String> {
+    let mut diff = String::new();
+    for (path, contents) in rendered {
+        let (existing, old_name) = match status(path, contents)? {
+            Status::UpToDate => continue,
+            Status::Stale(existing) => (existing, path.display().to_string()),
+            Status::Missing => (String::new(), "/dev/null".to_string()),
+        };
+        let new_name = path.display().to_string();
+        let text = TextDiff::from_lines(&existing, contents);
+        let unified = text.unified_diff().header(&old_name, &new_name).to_string();
+        diff.push_str(&unified);
+    }
+    Ok(diff)
+}
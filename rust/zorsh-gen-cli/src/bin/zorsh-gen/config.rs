@@ -0,0 +1,109 @@
+// src/bin/zorsh-gen/config.rs
+//! `zorsh.toml`: generator settings committed with a project. Keys are the long names of
+//! the command line flags, which override them, e.g.
+//!
+//! ```toml
+//! inputs = ["schemas/*.bin"]
+//! output = "src/generated/{name}.ts"
+//! field-case = "camel"
+//! codec-functions = true
+//!
+//! [rename]
+//! State = "ContractState"
+//! ```
+//!
+//! Paths are relative to the directory of the file. The keys other than inputs, outputs
+//! and how to report are [`Settings`], which the library deserializes options from too.
+use super::{Cli, OutputFormat, Progress};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use zorsh_gen::Settings;
+
+/// The file looked for in the current directory and its ancestors.
+pub const FILE_NAME: &str = "zorsh.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    inputs: Option<Vec<String>>,
+    output: Option<PathBuf>,
+    merge: Option<bool>,
+    split: Option<bool>,
+    source: Option<PathBuf>,
+    output_format: Option<OutputFormat>,
+    progress: Option<Progress>,
+    /// The other keys, which are [`Settings`].
+    #[serde(flatten)]
+    settings: toml::Table,
+}
+
+impl Config {
+    /// Parses the settings in `text`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(text).map_err(|error| error.to_string())?;
+        Settings::deserialize(config.settings.clone()).map_err(|error| error.to_string())?;
+        Ok(config)
+    }
+
+    /// Reads the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+/// Returns the nearest `zorsh.toml` in `directory` or its ancestors.
+pub fn find(directory: &Path) -> Option<PathBuf> {
+    directory
+        .ancestors()
+        .map(|directory| directory.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+impl Cli {
+    /// Takes the settings of `config` that weren't given on the command line, per
+    /// `matches`, with paths relative to `base`.
+    pub fn apply(
+        &mut self,
+        config: Config,
+        base: &Path,
+        matches: &ArgMatches,
+    ) -> Result<(), String> {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(value) = config.$field {
+                    if !given(stringify!($field)) {
+                        self.$field = value.into();
+                    }
+                }
+            )*};
+        }
+        merge!(merge, split, output_format, progress);
+
+        // The file's settings, with the flags given on the command line on top
+        let mut settings = config.settings;
+        let flags = toml::Table::try_from(&self.settings).expect("settings always serialize");
+        for (key, value) in flags {
+            if given(&key.replace('-', "_")) {
+                settings.insert(key, value);
+            }
+        }
+        self.settings = Settings::deserialize(settings).map_err(|error| error.to_string())?;
+
+        let relative = |path: &str| base.join(path).to_string_lossy().into_owned();
+        if let Some(inputs) = config.inputs.filter(|_| !given("inputs")) {
+            self.inputs = inputs.iter().map(|input| relative(input)).collect();
+        }
+        if let Some(output) = config.output.filter(|_| !given("output")) {
+            self.output = Some(base.join(output));
+        }
+        if let Some(source) = config.source.filter(|_| !given("source")) {
+            self.source = Some(base.join(source));
+        }
+        Ok(())
+    }
+}
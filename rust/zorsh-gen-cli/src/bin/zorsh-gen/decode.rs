@@ -0,0 +1,154 @@
+// src/bin/zorsh-gen/decode.rs
+//! Subcommands looking into borsh payloads by their schema.
+use super::report;
+use borsh::schema::BorshSchemaContainer;
+use clap::Args;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zorsh_gen::payload::{self, Node, Segment};
+
+/// The type payloads hold.
+#[derive(Args, Debug)]
+pub struct TypeArgs {
+    /// The borsh-serialized `BorshSchemaContainer` defining the type.
+    #[arg(long, value_name = "FILE")]
+    schema: PathBuf,
+    /// The declaration of the type, e.g. `GameState`. Defaults to the schema's root.
+    #[arg(long = "type", value_name = "DECL")]
+    declaration: Option<String>,
+}
+
+impl TypeArgs {
+    /// Reads the schema, and returns it with the declaration to decode.
+    fn load(&self) -> Result<(BorshSchemaContainer, String), String> {
+        let container = read_schema(&self.schema)?;
+        let declaration = match &self.declaration {
+            Some(declaration) => declaration.clone(),
+            None => container.declaration().clone(),
+        };
+        Ok((container, declaration))
+    }
+}
+
+/// A payload and the type it holds.
+#[derive(Args, Debug)]
+pub struct PayloadArgs {
+    /// The borsh-serialized value, or `-` for standard input.
+    payload: PathBuf,
+    #[command(flatten)]
+    ty: TypeArgs,
+}
+
+impl PayloadArgs {
+    /// Reads and decodes the payload, returning its value and bytes.
+    pub fn decode(&self) -> Result<(Node, Vec<u8>), String> {
+        let (container, declaration) = self.ty.load()?;
+        let bytes = read(&self.payload)?;
+        let node = payload::decode(&container, &declaration, &bytes)
+            .map_err(|error| format!("{}: {}", self.payload.display(), error))?;
+        Ok((node, bytes))
+    }
+}
+
+/// Payloads that should all hold the same type.
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// The borsh-serialized values, or `-` for standard input.
+    #[arg(required = true)]
+    payloads: Vec<PathBuf>,
+    #[command(flatten)]
+    ty: TypeArgs,
+}
+
+/// Reads a file, or standard input for `-`.
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    let bytes = match path.to_str() {
+        Some("-") => {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .lock()
+                .read_to_end(&mut bytes)
+                .map(|_| bytes)
+        }
+        _ => std::fs::read(path),
+    };
+    bytes.map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Reads a borsh-serialized `BorshSchemaContainer`.
+pub fn read_schema(path: &Path) -> Result<BorshSchemaContainer, String> {
+    let bytes = read(path)?;
+    borsh::from_slice(&bytes).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// `inspect`: prints the decoded value.
+pub fn inspect(args: &PayloadArgs) -> Result<(), String> {
+    report::output(format_args!("{}\n", args.decode()?.0));
+    Ok(())
+}
+
+/// `hexdump`: prints the payload's bytes, with each run labelled with the field it
+/// encodes.
+pub fn hexdump(args: &PayloadArgs) -> Result<(), String> {
+    let (node, bytes) = args.decode()?;
+    report::output(annotate(&node.segments(), &bytes));
+    Ok(())
+}
+
+/// How many bytes a hexdump row shows.
+const ROW_WIDTH: usize = 16;
+
+/// Returns a hexdump of `bytes` with a row per `ROW_WIDTH` bytes of each segment, its
+/// path and label on the first.
+pub fn annotate(segments: &[Segment], bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for segment in segments {
+        let mut label = format!("{}  {}", segment.path, segment.label);
+        for start in segment.span.clone().step_by(ROW_WIDTH) {
+            let end = (start + ROW_WIDTH).min(segment.span.end);
+            let hex: Vec<String> = bytes[start..end]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            let row = format!(
+                "{:08x}  {:width$}  {}",
+                start,
+                hex.join(" "),
+                label,
+                width = ROW_WIDTH * 3 - 1
+            );
+            dump.push_str(row.trim_end());
+            dump.push('\n');
+            label.clear();
+        }
+    }
+    dump
+}
+
+/// `validate`: checks that each payload decodes exactly, with no bytes missing or left
+/// over, and reports the offset where those that don't go wrong.
+pub fn validate(args: &ValidateArgs) -> Result<(), String> {
+    let (container, declaration) = args.ty.load()?;
+    let mut failed = 0;
+    for path in &args.payloads {
+        let decoded = read(path).and_then(|bytes| {
+            payload::decode(&container, &declaration, &bytes).map_err(|error| error.to_string())
+        });
+        match decoded {
+            Ok(_) => report::output(format_args!("ok: {}\n", path.display())),
+            Err(error) => {
+                report::error(format_args!("{}: {}", path.display(), error));
+                failed += 1;
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!(
+            "{} of {} payloads aren't valid `{}`s",
+            failed,
+            args.payloads.len(),
+            declaration
+        )),
+    }
+}
@@ -0,0 +1,333 @@
+// src/bin/zorsh-gen/fetch.rs
+//! `fetch`: generates zorsh schemas for a deployed contract from the ABI it serves, for
+//! contracts whose source isn't at hand. Contracts built with `cargo near build` and ABI
+//! embedding answer the `__contract_abi` view call with their zstd-compressed JSON ABI,
+//! which carries a borsh schema for each borsh-serialized parameter and result.
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The view method contracts serve their ABI from.
+const ABI_METHOD: &str = "__contract_abi";
+
+/// How long to wait for the RPC endpoint.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Args, Debug)]
+pub struct FetchArgs {
+    /// The contract's account, e.g. `wrap.near`.
+    account_id: String,
+    /// The network whose public RPC endpoint to ask.
+    #[arg(long, value_enum, default_value_t = Network::Mainnet)]
+    network: Network,
+    /// The RPC endpoint to ask instead, e.g. a provider's with an API key.
+    #[arg(long, value_name = "URL")]
+    rpc_url: Option<String>,
+    /// Where to write the schema containers, by default `schemas/{account}`.
+    #[arg(long, value_name = "DIR")]
+    schemas: Option<PathBuf>,
+    /// Where to write the zorsh schemas, by default `{account}.ts`.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Further generator flags, after `--`.
+    #[arg(last = true, value_name = "ZORSH_GEN_ARGS")]
+    zorsh_gen: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl FetchArgs {
+    fn rpc_url(&self) -> &str {
+        match (&self.rpc_url, self.network) {
+            (Some(url), _) => url,
+            (None, Network::Mainnet) => "https://rpc.mainnet.near.org",
+            (None, Network::Testnet) => "https://rpc.testnet.near.org",
+        }
+    }
+
+    /// Returns the command line generating the schemas from the containers `inputs`.
+    pub fn generator_args(&self, inputs: &[PathBuf]) -> Vec<OsString> {
+        let output = self
+            .output
+            .clone()
+            .unwrap_or(PathBuf::from(format!("{}.ts", self.account_id)));
+        let mut args: Vec<OsString> = vec!["zorsh-gen".into(), "--merge".into()];
+        args.extend(["--output".into(), output.into_os_string()]);
+        args.extend(inputs.iter().map(|input| input.clone().into_os_string()));
+        args.extend(self.zorsh_gen.iter().map(OsString::from));
+        args
+    }
+}
+
+/// Fetches the contract's ABI and writes a schema container for each borsh parameter
+/// and result in it, returning the containers written.
+pub fn fetch(args: &FetchArgs) -> Result<Vec<PathBuf>, String> {
+    let abi = abi(args.rpc_url(), &args.account_id)?;
+    let containers = containers(&abi)?;
+    if containers.is_empty() {
+        return Err(format!(
+            "the ABI of `{}` has no borsh-serialized parameters or results; its functions all take and return JSON",
+            args.account_id
+        ));
+    }
+    let directory = match &args.schemas {
+        Some(directory) => directory.clone(),
+        None => Path::new("schemas").join(&args.account_id),
+    };
+    let failed = |path: &Path, error: std::io::Error| format!("{}: {}", path.display(), error);
+    std::fs::create_dir_all(&directory).map_err(|error| failed(&directory, error))?;
+    let mut written = Vec::new();
+    for (name, container) in containers {
+        let path = directory.join(format!("{}.bin", name));
+        let bytes = borsh::to_vec(&container).map_err(|error| failed(&path, error))?;
+        std::fs::write(&path, bytes).map_err(|error| failed(&path, error))?;
+        written.push(path);
+    }
+    eprintln!(
+        "fetched {} schemas of `{}` into {}",
+        written.len(),
+        args.account_id,
+        directory.display()
+    );
+    Ok(written)
+}
+
+/// Asks `rpc_url` for the ABI of `account_id`, and returns it decompressed.
+fn abi(rpc_url: &str, account_id: &str) -> Result<Value, String> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": "zorsh-gen",
+        "method": "query",
+        "params": {
+            "request_type": "call_function",
+            "finality": "final",
+            "account_id": account_id,
+            "method_name": ABI_METHOD,
+            "args_base64": "",
+        },
+    });
+    let response: Value = ureq::post(rpc_url)
+        .timeout(TIMEOUT)
+        .send_json(request)
+        .map_err(|error| format!("{}: {}", rpc_url, error))?
+        .into_json()
+        .map_err(|error| format!("{}: {}", rpc_url, error))?;
+    if let Some(error) = response.get("error") {
+        let cause = error["cause"]["name"].as_str();
+        let message = cause
+            .or(error["message"].as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("{}: {}", rpc_url, message));
+    }
+    let result = &response["result"];
+    if let Some(error) = result["error"].as_str() {
+        if error.contains("MethodNotFound") {
+            return Err(format!(
+                "`{}` doesn't serve its ABI: it wasn't built with `cargo near build` embedding one",
+                account_id
+            ));
+        }
+        return Err(format!(
+            "calling `{}` on `{}`: {}",
+            ABI_METHOD, account_id, error
+        ));
+    }
+    let compressed: Vec<u8> = serde_json::from_value(result["result"].clone())
+        .map_err(|_| format!("{}: the response holds no result bytes", rpc_url))?;
+    let mut json = Vec::new();
+    ruzstd::decoding::StreamingDecoder::new(compressed.as_slice())
+        .map_err(|error| error.to_string())
+        .and_then(|mut decoder| decoder.read_to_end(&mut json).map_err(|e| e.to_string()))
+        .map_err(|error| format!("couldn't decompress the ABI of `{}`: {}", account_id, error))?;
+    serde_json::from_slice(&json)
+        .map_err(|error| format!("the ABI of `{}` isn't JSON: {}", account_id, error))
+}
+
+/// Returns a container for each borsh parameter, named `{function}_{parameter}`, and
+/// each borsh result, named `{function}_result`, in the order of the ABI.
+fn containers(abi: &Value) -> Result<Vec<(String, BorshSchemaContainer)>, String> {
+    let Some(functions) = abi["body"]["functions"].as_array() else {
+        return Err("the ABI lists no functions".to_string());
+    };
+    let mut containers = Vec::new();
+    for function in functions {
+        let name = function["name"].as_str().unwrap_or_default();
+        let borsh = |types: &Value| types["serialization_type"] == "borsh";
+        if borsh(&function["params"]) {
+            for arg in function["params"]["args"].as_array().into_iter().flatten() {
+                let arg_name = arg["name"].as_str().unwrap_or_default();
+                let container = container(&arg["type_schema"])
+                    .map_err(|error| format!("`{}` parameter `{}`: {}", name, arg_name, error))?;
+                containers.push((format!("{}_{}", name, arg_name), container));
+            }
+        }
+        if borsh(&function["result"]) {
+            let container = container(&function["result"]["type_schema"])
+                .map_err(|error| format!("`{}` result: {}", name, error))?;
+            containers.push((format!("{}_result", name), container));
+        }
+    }
+    Ok(containers)
+}
+
+/// Converts a borsh schema as the ABI writes it, in the JSON form of borsh 0.9's
+/// containers, to a container of the current format. Borsh 0.9 leaves primitives
+/// undefined and calls strings `string` and the unit type `nil`; those are defined and
+/// renamed here.
+fn container(schema: &Value) -> Result<BorshSchemaContainer, String> {
+    let Some(declaration) = schema["declaration"].as_str() else {
+        return Err("the schema has no `declaration`".to_string());
+    };
+    let declaration = rename(declaration);
+    let mut definitions = BTreeMap::new();
+    let empty = serde_json::Map::new();
+    for (name, definition) in schema["definitions"].as_object().unwrap_or(&empty) {
+        definitions.insert(
+            rename(name),
+            convert(definition).map_err(|e| format!("`{}`: {}", name, e))?,
+        );
+    }
+
+    let mut referenced: Vec<Declaration> = vec![declaration.clone()];
+    for definition in definitions.values() {
+        referenced.extend(references(definition));
+    }
+    referenced.push("u8".to_string()); // the bytes of `String`s
+    for declaration in referenced {
+        if let Some(definition) = primitive(&declaration) {
+            definitions.entry(declaration).or_insert(definition);
+        }
+    }
+    Ok(BorshSchemaContainer::new(declaration, definitions))
+}
+
+/// Converts one definition: `{"Array": {"length", "elements"}}`, `{"Sequence": elements}`,
+/// `{"Tuple": [elements]}`, `{"Enum": [[variant, declaration]]}`, or `{"Struct": fields}`
+/// with fields as `[[name, declaration]]`, `[declaration]` or `null`.
+fn convert(definition: &Value) -> Result<Definition, String> {
+    let declarations = |value: &Value| -> Result<Vec<Declaration>, String> {
+        let items = value.as_array().ok_or("expected a list of declarations")?;
+        let items = items.iter().map(|item| item.as_str().map(rename));
+        items
+            .collect::<Option<_>>()
+            .ok_or("expected declarations".to_string())
+    };
+    let pairs = |value: &Value| -> Result<Vec<(String, Declaration)>, String> {
+        let items = value.as_array().ok_or("expected a list of pairs")?;
+        let pair = |item: &Value| match item.as_array().map(Vec::as_slice) {
+            Some([Value::String(name), Value::String(declaration)]) => {
+                Some((name.clone(), rename(declaration)))
+            }
+            _ => None,
+        };
+        let pairs = items.iter().map(pair);
+        pairs
+            .collect::<Option<_>>()
+            .ok_or("expected `[name, declaration]` pairs".to_string())
+    };
+    let Some((kind, value)) = definition.as_object().and_then(|o| o.iter().next()) else {
+        return Err("expected a definition".to_string());
+    };
+    Ok(match kind.as_str() {
+        "Array" => {
+            let length = value["length"].as_u64().ok_or("the array has no length")?;
+            let elements = value["elements"]
+                .as_str()
+                .ok_or("the array has no elements")?;
+            Definition::Sequence {
+                length_width: Definition::ARRAY_LENGTH_WIDTH,
+                length_range: length..=length,
+                elements: rename(elements),
+            }
+        }
+        "Sequence" => Definition::Sequence {
+            length_width: Definition::DEFAULT_LENGTH_WIDTH,
+            length_range: Definition::DEFAULT_LENGTH_RANGE,
+            elements: rename(value.as_str().ok_or("the sequence has no elements")?),
+        },
+        "Tuple" => Definition::Tuple {
+            elements: declarations(value)?,
+        },
+        "Enum" => Definition::Enum {
+            tag_width: 1,
+            variants: pairs(value)?
+                .into_iter()
+                .enumerate()
+                .map(|(index, (name, declaration))| (index as i64, name, declaration))
+                .collect(),
+        },
+        "Struct" => Definition::Struct {
+            fields: match value {
+                Value::Null => Fields::Empty,
+                Value::Array(items) if items.iter().all(Value::is_string) && !items.is_empty() => {
+                    Fields::UnnamedFields(declarations(value)?)
+                }
+                _ => Fields::NamedFields(pairs(value)?),
+            },
+        },
+        kind => return Err(format!("unknown kind of definition `{}`", kind)),
+    })
+}
+
+/// Renames borsh 0.9's `string` and `nil` in a declaration.
+fn rename(declaration: &str) -> Declaration {
+    let mut renamed = String::new();
+    let mut word = String::new();
+    for c in declaration.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        renamed.push_str(match word.as_str() {
+            "string" => "String",
+            "nil" => "()",
+            word => word,
+        });
+        word.clear();
+        renamed.push(c);
+    }
+    renamed.pop();
+    renamed
+}
+
+/// Returns the declarations a definition refers to.
+fn references(definition: &Definition) -> Vec<Declaration> {
+    match definition {
+        Definition::Primitive(_) => Vec::new(),
+        Definition::Sequence { elements, .. } => vec![elements.clone()],
+        Definition::Tuple { elements } => elements.clone(),
+        Definition::Enum { variants, .. } => variants.iter().map(|(_, _, d)| d.clone()).collect(),
+        Definition::Struct { fields } => match fields {
+            Fields::NamedFields(fields) => fields.iter().map(|(_, d)| d.clone()).collect(),
+            Fields::UnnamedFields(elements) => elements.clone(),
+            Fields::Empty => Vec::new(),
+        },
+    }
+}
+
+/// Returns the definition of a primitive borsh 0.9 leaves undefined.
+fn primitive(declaration: &str) -> Option<Definition> {
+    Some(match declaration {
+        "u8" | "i8" | "bool" => Definition::Primitive(1),
+        "u16" | "i16" => Definition::Primitive(2),
+        "u32" | "i32" | "f32" => Definition::Primitive(4),
+        "u64" | "i64" | "f64" => Definition::Primitive(8),
+        "u128" | "i128" => Definition::Primitive(16),
+        "()" => Definition::Primitive(0),
+        "String" => Definition::Sequence {
+            length_width: Definition::DEFAULT_LENGTH_WIDTH,
+            length_range: Definition::DEFAULT_LENGTH_RANGE,
+            elements: "u8".to_string(),
+        },
+        _ => return None,
+    })
+}
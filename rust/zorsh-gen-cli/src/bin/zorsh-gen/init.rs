@@ -0,0 +1,164 @@
+// src/bin/zorsh-gen/init.rs
+//! `init`: sets up a project to generate its zorsh schemas, with a `zorsh.toml`, a
+//! directory for schema containers and one for the output, a Rust snippet exporting a
+//! container, and `package.json` scripts running the generator.
+use super::config::FILE_NAME;
+use super::report;
+use clap::Args;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use zorsh_gen::RUNTIME_PACKAGE;
+
+/// The version range the zorsh runtime is added with: the first release with the
+/// `lengthWidth` option and `b.transform`, which generated code may use.
+const RUNTIME_VERSION: &str = "^0.6.0";
+
+/// Where schema containers go, relative to the project.
+const SCHEMAS: &str = "schemas";
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// The project directory, created if it doesn't exist.
+    #[arg(default_value = ".")]
+    directory: PathBuf,
+    /// Where generated schemas go, relative to the project.
+    #[arg(long, value_name = "DIR", default_value = "src/generated")]
+    output: PathBuf,
+    /// Overwrite files that already exist.
+    #[arg(long)]
+    force: bool,
+}
+
+/// Sets up the project, and returns a line per file saying what was done with it.
+pub fn init(args: &InitArgs) -> Result<Vec<String>, String> {
+    let directory = &args.directory;
+    let failed = |path: &Path, error: std::io::Error| format!("{}: {}", path.display(), error);
+    let mut done = Vec::new();
+    for path in [directory.join(SCHEMAS), directory.join(&args.output)] {
+        if !path.is_dir() {
+            std::fs::create_dir_all(&path).map_err(|error| failed(&path, error))?;
+            done.push(format!("created {}/", path.display()));
+        }
+    }
+
+    let output = args.output.join("{name}.ts");
+    let files = [
+        (FILE_NAME, config(&output.to_string_lossy())),
+        ("schemas/export_schema.rs", EXPORT_SNIPPET.to_string()),
+    ];
+    for (name, contents) in files {
+        let path = directory.join(name);
+        if path.exists() && !args.force {
+            done.push(format!("skipped {}, which exists", path.display()));
+            continue;
+        }
+        std::fs::write(&path, contents).map_err(|error| failed(&path, error))?;
+        done.push(format!("created {}", path.display()));
+    }
+
+    let path = directory.join("package.json");
+    let existing = path.exists();
+    let package = match existing {
+        true => {
+            let text = std::fs::read_to_string(&path).map_err(|error| failed(&path, error))?;
+            serde_json::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error))?
+        }
+        false => json!({ "name": project_name(directory), "private": true }),
+    };
+    let Value::Object(mut package) = package else {
+        return Err(format!("{}: isn't a JSON object", path.display()));
+    };
+    if !wire(&mut package) {
+        done.push(format!("skipped {}, which runs zorsh-gen", path.display()));
+        return Ok(done);
+    }
+    let text = serde_json::to_string_pretty(&package).expect("a package always serializes");
+    std::fs::write(&path, text + "\n").map_err(|error| failed(&path, error))?;
+    done.push(match existing {
+        true => format!("updated {}", path.display()),
+        false => format!("created {}", path.display()),
+    });
+    Ok(done)
+}
+
+/// `init`: sets up the project and says what it did.
+pub fn run(args: &InitArgs) -> Result<(), String> {
+    for line in init(args)? {
+        report::output(format_args!("{}\n", line));
+    }
+    report::output(format_args!(
+        "\nnext, export a schema container into {}/ (see {}/export_schema.rs) and run `npm run zorsh`\n",
+        SCHEMAS, SCHEMAS
+    ));
+    Ok(())
+}
+
+/// Adds the `zorsh` script and the runtime dependency to a `package.json`, keeping any
+/// that are there, and returns whether it changed.
+fn wire(package: &mut Map<String, Value>) -> bool {
+    let mut changed = false;
+    let mut add = |section: &str, key: &str, value: &str| {
+        let entries = package
+            .entry(section)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(entries) = entries {
+            if !entries.contains_key(key) {
+                entries.insert(key.to_string(), Value::from(value));
+                changed = true;
+            }
+        }
+    };
+    add("scripts", "zorsh", "zorsh-gen");
+    add("scripts", "zorsh:check", "zorsh-gen --check");
+    add("dependencies", RUNTIME_PACKAGE, RUNTIME_VERSION);
+    changed
+}
+
+/// Names a new `package.json` after the project directory.
+fn project_name(directory: &Path) -> String {
+    let directory = directory.canonicalize().unwrap_or(directory.to_path_buf());
+    match directory.file_name() {
+        Some(name) => name.to_string_lossy().to_lowercase().replace(' ', "-"),
+        None => "zorsh-schemas".to_string(),
+    }
+}
+
+/// Returns the `zorsh.toml` of a new project writing to `output`.
+fn config(output: &str) -> String {
+    format!(
+        r#"# zorsh-gen settings. Keys are the long names of its command line flags, which
+# override them; paths are relative to this file. See `zorsh-gen --help`.
+
+# The borsh-serialized `BorshSchemaContainer`s to generate from
+inputs = ["{}/*.bin"]
+# `{{name}}` stands for the file stem of each input
+output = "{}"
+runtime-module = "{}"
+banner = true
+
+# [rename]
+# State = "ContractState"
+"#,
+        SCHEMAS,
+        output.replace('\\', "/"),
+        RUNTIME_PACKAGE
+    )
+}
+
+/// A test for the contract crate writing the schema container of its state.
+const EXPORT_SNIPPET: &str = r#"// Exports the schema container zorsh-gen reads. Add this to the contract crate, with
+// `borsh` and its `unstable__schema` feature as a dependency, and run `cargo test
+// export_zorsh_schema` whenever the types change.
+
+#[test]
+fn export_zorsh_schema() {
+    use borsh::schema::BorshSchemaContainer;
+
+    // Replace `Contract` with the type to generate schemas for; the types it refers
+    // to are exported with it.
+    let container = BorshSchemaContainer::for_type::<Contract>();
+    let bytes = borsh::to_vec(&container).expect("schema containers serialize");
+    // Relative to the contract crate; point this at the project's `schemas/`
+    std::fs::write("schemas/contract.bin", bytes).expect("the schema is written");
+}
+"#;
@@ -0,0 +1,1307 @@
+// src/bin/zorsh-gen/main.rs
+//! `zorsh-gen`: generates zorsh TypeScript schemas from a serialized `BorshSchemaContainer`.
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use report::{Failure, Kind, Summary};
+use serde::Deserialize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
+use zorsh_gen::fixtures;
+use zorsh_gen::{
+    render_zorsh_files, render_zorsh_schema, write_rendered, GeneratorOptions, Settings,
+    SourceInfo, Target,
+};
+
+mod compare;
+mod config;
+mod decode;
+mod fetch;
+mod init;
+mod progress;
+mod report;
+mod roundtrip;
+mod schema;
+mod watch;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "zorsh-gen",
+    version,
+    about,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The borsh-serialized `BorshSchemaContainer`s to generate from, glob patterns such
+    /// as `schemas/*.bin`, or `-` for standard input.
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<String>,
+    /// Where to write the TypeScript, or the directory for `--split` output. With several
+    /// inputs, `{name}` stands for each one's file stem, e.g. `src/generated/{name}.ts`.
+    /// `-` writes to standard output. Defaults to each input path with a `.ts` extension,
+    /// or standard output for standard input.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Generate one output for the types of all inputs.
+    #[arg(long)]
+    merge: bool,
+    /// Keep running, and regenerate whenever an input or the `--source` file changes.
+    #[arg(short, long)]
+    watch: bool,
+    /// Write nothing, and fail if the output isn't what would be generated, such as when
+    /// committed code is out of sync with its schema.
+    #[arg(long, conflicts_with = "watch")]
+    check: bool,
+    /// Write nothing, and print a unified diff of what generating would change.
+    #[arg(long, conflicts_with_all = ["watch", "check"])]
+    diff: bool,
+    /// Generate everything but write nothing, and list the files that would be created
+    /// or updated.
+    #[arg(long, conflicts_with_all = ["watch", "check", "diff"])]
+    dry_run: bool,
+    /// Write one file per exported type, and an `index.ts`, into the output directory.
+    #[arg(long)]
+    split: bool,
+    /// Rust source to read type aliases, doc comments and modules from.
+    #[arg(long, value_name = "FILE")]
+    source: Option<PathBuf>,
+    /// Settings to use for the flags not given, instead of the nearest `zorsh.toml`.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// How to report the outcome: messages on standard error, or a JSON summary of the
+    /// files generated, warnings and errors on standard output. Either way, the exit code
+    /// is 1 for out-of-date files and other failures, 2 for bad flags or settings, 3 for
+    /// I/O errors, 4 for schema errors, and 5 when only `--strict` placeholders failed.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+    /// How to show the progress of long runs: a bar on standard error, a line per input
+    /// and file written, or nothing. `auto` shows the bar when standard error is a
+    /// terminal and the output format is text.
+    #[arg(long, value_enum, default_value_t = Progress::Auto)]
+    progress: Progress,
+
+    #[command(flatten)]
+    settings: Settings,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a completion script for a shell.
+    ///
+    /// For example, `zorsh-gen completions bash >
+    /// ~/.local/share/bash-completion/completions/zorsh-gen`.
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Decode a borsh payload by its schema and print the value.
+    Inspect(decode::PayloadArgs),
+    /// Print a hex dump of a borsh payload, labelling each run of bytes with the field,
+    /// length prefix or enum tag it encodes.
+    Hexdump(decode::PayloadArgs),
+    /// Check that payloads decode exactly as a type, with no bytes missing or left over.
+    Validate(decode::ValidateArgs),
+    /// Look into schema containers.
+    #[command(subcommand)]
+    Schema(schema::SchemaCommand),
+    /// Set up a project to generate its zorsh schemas: a `zorsh.toml`, directories for
+    /// schema containers and output, a Rust snippet exporting a container, and
+    /// `package.json` scripts.
+    Init(init::InitArgs),
+    /// Generate zorsh schemas for a deployed contract from the ABI it serves, fetched over
+    /// NEAR RPC. Contracts serve their ABI when built with `cargo near build` embedding one;
+    /// schemas are generated for the parameters and results they serialize with borsh.
+    Fetch(fetch::FetchArgs),
+    /// Write the cross-language test corpus: a payload and schema container for each
+    /// primitive, float, collection, enum and edge case, with a `manifest.json` listing
+    /// them.
+    Fixtures {
+        /// The directory to write to, created if it doesn't exist.
+        directory: PathBuf,
+        /// Write only fixtures whose names match this glob, e.g. `float_*`.
+        #[arg(long, value_name = "PATTERN")]
+        only: Option<glob::Pattern>,
+    },
+    /// Check the TypeScript runtime against the corpus: generate a schema for each fixture,
+    /// round-trip its payload through zorsh with a JavaScript runner, and report the first
+    /// byte and field of each payload that comes back different.
+    Roundtrip(roundtrip::RoundtripArgs),
+}
+
+impl Command {
+    fn run(&self) -> Result<(), String> {
+        match self {
+            Command::Completions { shell } => {
+                let mut command = Cli::command();
+                let name = command.get_name().to_string();
+                clap_complete::generate(*shell, &mut command, name, &mut std::io::stdout());
+                Ok(())
+            }
+            Command::Inspect(args) => decode::inspect(args),
+            Command::Hexdump(args) => decode::hexdump(args),
+            Command::Validate(args) => decode::validate(args),
+            Command::Schema(command) => command.run(),
+            Command::Init(args) => init::run(args),
+            Command::Fetch(args) => {
+                let inputs = fetch::fetch(args)?;
+                let cli = Cli::parse_with_config_from(args.generator_args(&inputs))?;
+                cli.run(&mut Summary::default())
+                    .map_err(|failure| failure.to_string())
+            }
+            Command::Roundtrip(args) => roundtrip::run(args),
+            Command::Fixtures { directory, only } => {
+                let fixtures: Vec<_> = fixtures::corpus()
+                    .into_iter()
+                    .filter(|fixture| only.as_ref().is_none_or(|only| only.matches(&fixture.name)))
+                    .collect();
+                if fixtures.is_empty() {
+                    return Err("no fixtures match `--only`".to_string());
+                }
+                let reporter = progress::Reporter::new(progress::Style::auto());
+                let written = |done: usize, fixture: &fixtures::Fixture| {
+                    reporter.step(done, fixtures.len(), "fixtures", &fixture.name);
+                };
+                fixtures::write(directory, &fixtures, written)
+                    .map_err(|error| format!("{}: {}", directory.display(), error))?;
+                reporter.clear();
+                report::output(format_args!(
+                    "wrote {} fixtures to {}\n",
+                    fixtures.len(),
+                    directory.display()
+                ));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Progress {
+    Auto,
+    Bar,
+    Lines,
+    Off,
+}
+
+/// Whether an input is a glob pattern rather than a path.
+fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+impl Cli {
+    /// Parses the command line, and fills in the flags it doesn't give from the config file.
+    fn parse_with_config() -> Result<Self, String> {
+        Self::parse_with_config_from(std::env::args_os())
+    }
+
+    /// Like [`Cli::parse_with_config`], for the command line `args`.
+    fn parse_with_config_from(
+        args: impl IntoIterator<Item = impl Into<std::ffi::OsString> + Clone>,
+    ) -> Result<Self, String> {
+        let matches = Cli::command().get_matches_from(args);
+        let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+        if cli.command.is_some() {
+            return Ok(cli);
+        }
+        cli.split_stdout();
+        let directory = std::env::current_dir().map_err(|error| error.to_string())?;
+        let path = cli.config.clone().or_else(|| config::find(&directory));
+        if let Some(path) = path {
+            let settings = config::Config::load(&path)?;
+            // Paths in the file are relative to it, shown relative to the working directory
+            let base = path.parent().unwrap_or(Path::new(""));
+            let base = base.strip_prefix(&directory).unwrap_or(base);
+            cli.apply(settings, base, &matches)?;
+        }
+        cli.check()?;
+        Ok(cli)
+    }
+
+    /// Takes a second `-` after `-` as the output rather than an input, as in
+    /// `zorsh-gen - -`.
+    fn split_stdout(&mut self) {
+        let stdin = self.inputs.iter().filter(|input| *input == "-").count();
+        if stdin > 1 && self.output.is_none() && self.inputs.last().is_some_and(|i| i == "-") {
+            self.inputs.pop();
+            self.output = Some(PathBuf::from("-"));
+        }
+    }
+
+    /// Checks the flags that need others, which may come from the config file.
+    fn check(&self) -> Result<(), String> {
+        let missing = |flag: &str, needs: &str| Err(format!("`{}` needs {}", flag, needs));
+        if self.inputs.is_empty() {
+            return Err(format!(
+                "no inputs given, on the command line or as `inputs` in `{}`",
+                config::FILE_NAME
+            ));
+        }
+        if self.inputs.iter().filter(|input| *input == "-").count() > 1 {
+            return Err("standard input can only be read once".to_string());
+        }
+        if self.watch && self.inputs.iter().any(|input| input == "-") {
+            return Err("`--watch` can't watch standard input".to_string());
+        }
+        if self.merge && self.output.is_none() {
+            return missing("--merge", "`--output`");
+        }
+        if self.settings.module_files && !(self.split && self.source.is_some()) {
+            return missing("--module-files", "`--split` and `--source`");
+        }
+        if self.settings.timestamp && !self.settings.banner {
+            return missing("--timestamp", "`--banner`");
+        }
+        if self.split && self.settings.backend != Target::Zorsh {
+            return Err(
+                "`--split` writes zorsh modules; other backends write a single file".to_string(),
+            );
+        }
+        if self.output_format == OutputFormat::Json {
+            let stdout = match &self.output {
+                Some(output) => output == Path::new("-"),
+                None => self.inputs.iter().any(|input| input == "-"),
+            };
+            if self.watch || self.diff || stdout {
+                return Err(
+                    "`--output-format json` prints the summary to standard output, \
+                    so it can't be used with `--watch`, `--diff` or an output of `-`"
+                        .to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn options(&self) -> Result<GeneratorOptions, String> {
+        let source = match &self.source {
+            Some(path) => {
+                let code = std::fs::read_to_string(path)
+                    .map_err(|error| format!("{}: {}", path.display(), error))?;
+                SourceInfo::parse(&code)
+                    .map_err(|error| format!("{}: {}", path.display(), error))?
+            }
+            None => SourceInfo::default(),
+        };
+        let options =
+            GeneratorOptions::try_from(self.settings.clone()).map_err(|error| error.to_string())?;
+        Ok(GeneratorOptions { source, ..options })
+    }
+
+    /// Returns the input paths, with glob patterns expanded.
+    fn inputs(&self) -> Result<Vec<PathBuf>, String> {
+        let mut inputs = Vec::new();
+        for pattern in &self.inputs {
+            if !is_pattern(pattern) {
+                inputs.push(PathBuf::from(pattern));
+                continue;
+            }
+            let paths = glob::glob(pattern).map_err(|error| format!("`{}`: {}", pattern, error))?;
+            let count = inputs.len();
+            for path in paths {
+                inputs.push(path.map_err(|error| error.to_string())?);
+            }
+            if inputs.len() == count {
+                return Err(format!("`{}` matches no files", pattern));
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// Returns where to write the output for `input`.
+    fn output(&self, input: &Path) -> PathBuf {
+        match &self.output {
+            Some(output) => {
+                let name = input.file_stem().unwrap_or_default().to_string_lossy();
+                output.to_string_lossy().replace("{name}", &name).into()
+            }
+            None if input == Path::new("-") => input.to_path_buf(),
+            None if self.split => input.with_extension(""),
+            None if self.settings.backend == Target::JsonSchema => {
+                input.with_extension("schema.json")
+            }
+            None => input.with_extension("ts"),
+        }
+    }
+
+    /// Returns how to show progress, with `auto` decided.
+    fn progress(&self) -> progress::Style {
+        match self.progress {
+            Progress::Auto if self.output_format == OutputFormat::Text => progress::Style::auto(),
+            Progress::Auto | Progress::Off => progress::Style::Off,
+            Progress::Bar => progress::Style::Bar,
+            Progress::Lines => progress::Style::Lines,
+        }
+    }
+
+    fn run(&self, summary: &mut Summary) -> Result<(), Failure> {
+        let reporter = Arc::new(progress::Reporter::new(self.progress()));
+        let mut options = self.options()?;
+        options.progress = Some(reporter.clone());
+        let inputs = self.inputs()?;
+        if self.merge {
+            let inputs: Vec<&Path> = inputs.iter().map(PathBuf::as_path).collect();
+            let output = self.output(Path::new(""));
+            return self.generate(&inputs, &output, &options, &reporter, summary);
+        }
+        let templated = self
+            .output
+            .as_ref()
+            .is_none_or(|output| output.to_string_lossy().contains("{name}"));
+        if inputs.len() > 1 && !templated {
+            return Err(Failure::new(
+                Kind::Usage,
+                "several inputs need `{name}` in `--output`, e.g. `src/{name}.ts`, or `--merge`",
+            ));
+        }
+
+        let mut failed = Vec::new();
+        for (index, input) in inputs.iter().enumerate() {
+            reporter.line(format_args!(
+                "[{}/{}] {}",
+                index + 1,
+                inputs.len(),
+                input.display()
+            ));
+            let output = self.output(input);
+            if let Err(failure) = self.generate(&[input], &output, &options, &reporter, summary) {
+                reporter.clear();
+                if self.output_format == OutputFormat::Text {
+                    report::error(format_args!("{}: {}", input.display(), failure));
+                }
+                failed.push(failure.kind);
+            }
+        }
+        match failed.first() {
+            None => Ok(()),
+            Some(&kind) => Err(Failure::new(
+                kind,
+                format!("{} of {} inputs failed", failed.len(), inputs.len()),
+            )),
+        }
+    }
+
+    /// Generates `output` from `inputs`, or with `--check` or `--diff`, compares it with
+    /// what's on disk, recording what happened in `summary`.
+    fn generate(
+        &self,
+        inputs: &[&Path],
+        output: &Path,
+        options: &GeneratorOptions,
+        reporter: &progress::Reporter,
+        summary: &mut Summary,
+    ) -> Result<(), Failure> {
+        let inputs: Vec<_> = inputs.iter().map(|input| input.to_string_lossy()).collect();
+        let inputs: Vec<&str> = inputs.iter().map(|input| input.as_ref()).collect();
+        let input = inputs.join(", ");
+        let output = output.to_string_lossy();
+        if (self.check || self.diff) && output == "-" {
+            return Err(Failure::new(
+                Kind::Usage,
+                "`--check` and `--diff` compare with files, not standard output",
+            ));
+        }
+        let render = |options: &GeneratorOptions| match self.split {
+            true => render_zorsh_files(&inputs, &output, options),
+            false => render_zorsh_schema(&inputs, &output, options),
+        };
+        let rendered = match render(options) {
+            Ok(rendered) => rendered,
+            Err(error) => {
+                summary.generator_error(Some(&input), &error, false);
+                return Err(Failure::new(Kind::of(&error), report::describe(&error)));
+            }
+        };
+        if self.output_format == OutputFormat::Json && !options.strict {
+            // Every placeholder written is an error of strict mode
+            let strict = GeneratorOptions {
+                strict: true,
+                progress: None,
+                ..options.clone()
+            };
+            if let Err(error) = render(&strict) {
+                summary.generator_error(Some(&input), &error, true);
+            }
+        }
+
+        if !self.check && !self.diff {
+            if output == "-" {
+                if !self.dry_run {
+                    report::output(&rendered[0].1);
+                }
+                return Ok(());
+            }
+            let mut statuses = Vec::new();
+            for (path, contents) in &rendered {
+                let status = compare::status(path, contents).map_err(Failure::io)?;
+                let status = match (status, self.dry_run) {
+                    (compare::Status::UpToDate, _) => "unchanged",
+                    (compare::Status::Missing, false) => "created",
+                    (compare::Status::Stale(_), false) => "updated",
+                    (compare::Status::Missing, true) => "would-create",
+                    (compare::Status::Stale(_), true) => "would-update",
+                };
+                if self.dry_run && status != "unchanged" && self.output_format == OutputFormat::Text
+                {
+                    let status = status.replace('-', " ");
+                    report::output(format_args!("{} {}\n", status, path.display()));
+                }
+                summary.file(path, status);
+                statuses.push((path, status));
+            }
+            if self.dry_run {
+                return Ok(());
+            }
+            return match write_rendered(&rendered) {
+                Ok(_) => {
+                    for (path, status) in statuses {
+                        reporter.line(format_args!("{} {}", status, path.display()));
+                    }
+                    Ok(())
+                }
+                Err(error) => {
+                    summary.generator_error(Some(&input), &error, false);
+                    Err(Failure::new(Kind::of(&error), report::describe(&error)))
+                }
+            };
+        }
+
+        if self.diff {
+            report::output(compare::diff(&rendered).map_err(Failure::io)?);
+            return Ok(());
+        }
+        let mut stale = 0;
+        for (path, contents) in &rendered {
+            let status = match compare::status(path, contents).map_err(Failure::io)? {
+                compare::Status::UpToDate => "up-to-date",
+                compare::Status::Stale(_) => "stale",
+                compare::Status::Missing => "missing",
+            };
+            stale += usize::from(status != "up-to-date");
+            summary.file(path, status);
+        }
+        if self.output_format == OutputFormat::Text {
+            for line in compare::stale(&rendered).map_err(Failure::io)? {
+                eprintln!("{}", line);
+            }
+        }
+        match stale {
+            0 => Ok(()),
+            count => {
+                let message = format!(
+                    "{} of {} generated files are out of date",
+                    count,
+                    rendered.len()
+                );
+                summary.error(Some(&input), &message);
+                Err(Failure::from(message))
+            }
+        }
+    }
+
+    /// Regenerates whenever a file the output depends on changes.
+    fn watch(&self) -> Result<(), String> {
+        let base = std::env::current_dir().map_err(|error| error.to_string())?;
+        let (patterns, paths): (Vec<&String>, Vec<&String>) =
+            self.inputs.iter().partition(|input| is_pattern(input));
+        let mut files: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+        files.extend(self.source.clone());
+        let patterns: Vec<&str> = patterns.into_iter().map(String::as_str).collect();
+        let watched = watch::Watched::new(&base, &files, &patterns);
+
+        eprintln!("Watching for changes, press Ctrl+C to stop");
+        let regenerate = || match self.run(&mut Summary::default()) {
+            Ok(()) => eprintln!("Regenerated"),
+            Err(error) => report::error(error),
+        };
+        watch::watch(&watched, regenerate).map_err(|error| error.to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    // `RUST_LOG=debug` shows how the parser got to each type, on standard error
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
+    let cli = match Cli::parse_with_config() {
+        Ok(cli) => cli,
+        Err(error) => {
+            report::error(error);
+            return Kind::Usage.exit_code();
+        }
+    };
+    if let Some(command) = &cli.command {
+        return match command.run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                report::error(error);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let mut summary = Summary::default();
+    let generated = cli.run(&mut summary);
+    match (cli.output_format, &generated) {
+        (OutputFormat::Text, Err(failure)) => report::error(failure),
+        (OutputFormat::Text, Ok(())) => {}
+        (OutputFormat::Json, _) => {
+            if let Err(failure) = &generated {
+                if summary.errors.is_empty() {
+                    summary.error(None, failure);
+                }
+            }
+            let json = summary.to_json(generated.as_ref().err());
+            report::output(format_args!("{:#}\n", json));
+        }
+    }
+    if cli.watch {
+        // Errors in the first run are reported above, and fixed inputs picked up later
+        if let Err(error) = cli.watch() {
+            report::error(error);
+            return ExitCode::FAILURE;
+        }
+    }
+    match generated {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => failure.kind.exit_code(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use zorsh_gen::payload;
+    use zorsh_gen::{
+        Diagnostic, Diagnostics, FieldCase, Indent, TypeMapping, UnionStyle, ZorshGenError,
+    };
+
+    #[test]
+    fn parses_arguments() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "zorsh-gen",
+            "contract.bin",
+            "--union-types",
+            "tagged",
+            "--map-import",
+            "U128=U128Schema@@near/zorsh",
+            "--rename",
+            "State=ContractState",
+            "--indent",
+            "tab",
+        ])
+        .unwrap();
+        let options = cli.options().unwrap();
+        assert_eq!(
+            options.union_types,
+            Some(UnionStyle::Tagged {
+                field: "kind".to_string()
+            })
+        );
+        assert_eq!(
+            options.type_mappings["U128"],
+            TypeMapping::Import {
+                module: "@near/zorsh".to_string(),
+                name: "U128Schema".to_string()
+            }
+        );
+        assert_eq!(options.renames["State"], "ContractState");
+        assert_eq!(options.format.indent, Indent::Tabs);
+
+        assert!(Cli::try_parse_from(["zorsh-gen", "a.bin", "--rename", "State"]).is_err());
+        let cli = Cli::try_parse_from(["zorsh-gen", "a.bin", "--module-files"]).unwrap();
+        assert_eq!(
+            cli.check().unwrap_err(),
+            "`--module-files` needs `--split` and `--source`"
+        );
+        let cli = Cli::try_parse_from(["zorsh-gen", "a.bin", "b.bin", "--merge"]).unwrap();
+        assert_eq!(cli.check().unwrap_err(), "`--merge` needs `--output`");
+    }
+
+    #[test]
+    fn reads_a_config_file() {
+        let settings = config::Config::parse(
+            r#"
+            inputs = ["schemas/*.bin"]
+            output = "src/{name}.ts"
+            field-case = "camel"
+            indent = 4
+            codec-functions = true
+
+            [rename]
+            State = "ContractState"
+            "#,
+        )
+        .unwrap();
+        let arguments = ["zorsh-gen", "--field-case", "pascal", "--rename", "A=B"];
+        let matches = Cli::command().try_get_matches_from(arguments).unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        cli.apply(settings, Path::new("project"), &matches).unwrap();
+        cli.check().unwrap();
+
+        assert_eq!(cli.inputs, ["project/schemas/*.bin"]);
+        assert_eq!(
+            cli.output(Path::new("ft.bin")),
+            Path::new("project/src/ft.ts")
+        );
+        let options = cli.options().unwrap();
+        // Flags override the file
+        assert_eq!(options.field_case, FieldCase::Pascal);
+        assert_eq!(options.renames["A"], "B");
+        assert!(!options.renames.contains_key("State"));
+        assert_eq!(options.format.indent, Indent::Spaces(4));
+        assert!(options.codec_functions);
+
+        let error = config::Config::parse("field-case = \"snake\"").unwrap_err();
+        assert!(error.contains("unknown variant `snake`"), "{}", error);
+        assert!(config::Config::parse("no-such-flag = true").is_err());
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        let cli = Cli::try_parse_from(["zorsh-gen", "completions", "zsh"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Completions {
+                shell: clap_complete::Shell::Zsh
+            })
+        ));
+        assert!(Cli::try_parse_from(["zorsh-gen", "completions", "tcsh"]).is_err());
+        let cli = Cli::try_parse_from([
+            "zorsh-gen",
+            "inspect",
+            "state.bin",
+            "--schema",
+            "schema.bin",
+            "--type",
+            "GameState",
+        ]);
+        assert!(matches!(cli.unwrap().command, Some(Command::Inspect(_))));
+        assert!(Cli::try_parse_from(["zorsh-gen", "inspect", "state.bin"]).is_err());
+        let cli = Cli::try_parse_from(["zorsh-gen", "fixtures", "out", "--only", "float_*"]);
+        match cli.unwrap().command {
+            Some(Command::Fixtures { directory, only }) => {
+                assert_eq!(directory, Path::new("out"));
+                assert!(only.unwrap().matches("float_pi"));
+            }
+            command => panic!("{:?}", command),
+        }
+        // Inputs go to generation, not subcommands
+        let cli = Cli::try_parse_from(["zorsh-gen", "contract.bin"]).unwrap();
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn describes_diagnostics() {
+        let diagnostic = |message: &str, references: &[&str]| Diagnostic {
+            message: message.to_string(),
+            declaration: references.last().map(|r| r.to_string()),
+            references: references.iter().map(|r| r.to_string()).collect(),
+            placeholder: false,
+        };
+        let error = ZorshGenError::Unsupported(Diagnostics(vec![
+            diagnostic("`Item` isn't defined", &["GameState", "items", "Item"]),
+            diagnostic("`1` can't prefix exported names", &[]),
+        ]));
+        assert_eq!(Kind::of(&error), Kind::Schema);
+        let text = report::describe(&error);
+        assert_eq!(
+            anstream::adapter::strip_str(&text).to_string(),
+            "`Item` isn't defined\n  --> GameState → items → Item\nerror: `1` can't prefix exported names"
+        );
+    }
+
+    #[test]
+    fn validates_payloads() {
+        let directory = std::env::temp_dir().join(format!("zorsh_validate_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let data = concat!(env!("CARGO_MANIFEST_DIR"), "/../borsh_test/test_data");
+        let payload = std::fs::read(format!("{}/complex_game_state.bin", data)).unwrap();
+        let truncated = directory.join("truncated.bin");
+        std::fs::write(&truncated, &payload[..100]).unwrap();
+        let validate = |payload: &str| {
+            let schema = format!("{}/complex_schema.bin", data);
+            let arguments = ["zorsh-gen", "validate", payload, "--schema", &schema];
+            match Cli::try_parse_from(arguments).unwrap().command {
+                Some(Command::Validate(args)) => decode::validate(&args),
+                command => panic!("{:?}", command),
+            }
+        };
+
+        validate(&format!("{}/complex_game_state.bin", data)).unwrap();
+        assert_eq!(
+            validate(truncated.to_str().unwrap()).unwrap_err(),
+            "1 of 1 payloads aren't valid `GameState`s"
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn shows_progress() {
+        let parse = |arguments: &[&str]| {
+            let cli = Cli::try_parse_from([&["zorsh-gen", "a.bin"], arguments].concat());
+            cli.unwrap().progress()
+        };
+        assert_eq!(parse(&["--progress", "lines"]), progress::Style::Lines);
+        assert_eq!(parse(&["--progress", "bar"]), progress::Style::Bar);
+        // Never mixed into a JSON summary unless asked for
+        assert_eq!(parse(&["--output-format", "json"]), progress::Style::Off);
+
+        assert_eq!(
+            progress::render(10, 40, "declarations", "Player"),
+            "[######------------------] 10/40 declarations  Player"
+        );
+        assert_eq!(
+            progress::render(0, 0, "fixtures", ""),
+            "[########################] 0/0 fixtures  "
+        );
+    }
+
+    #[test]
+    fn fetches_contract_abis() {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        // A contract ABI as cargo-near writes it, with borsh 0.9 schemas
+        let abi = serde_json::json!({
+            "schema_version": "0.3.0",
+            "metadata": {},
+            "body": {
+                "functions": [
+                    {
+                        "name": "set_player",
+                        "kind": "call",
+                        "params": {
+                            "serialization_type": "borsh",
+                            "args": [{
+                                "name": "player",
+                                "type_schema": {
+                                    "declaration": "Player",
+                                    "definitions": {
+                                        "Player": { "Struct": [
+                                            ["name", "string"],
+                                            ["last_login", "Option<u64>"],
+                                            ["role", "Class"],
+                                        ] },
+                                        "Option<u64>": { "Enum": [["None", "nil"], ["Some", "u64"]] },
+                                        "Class": { "Enum": [["Warrior", "ClassWarrior"], ["Mage", "ClassMage"]] },
+                                        "ClassWarrior": { "Struct": null },
+                                        "ClassMage": { "Struct": null },
+                                    },
+                                },
+                            }],
+                        },
+                    },
+                    {
+                        "name": "get_round",
+                        "kind": "view",
+                        "result": {
+                            "serialization_type": "borsh",
+                            "type_schema": { "declaration": "u32", "definitions": {} },
+                        },
+                    },
+                    {
+                        "name": "get_name",
+                        "kind": "view",
+                        "result": { "serialization_type": "json", "type_schema": { "type": "string" } },
+                    },
+                ],
+                "root_schema": {},
+            },
+        });
+        let compressed = ruzstd::encoding::compress_to_vec(
+            abi.to_string().as_bytes(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let response = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": { "result": compressed, "logs": [], "block_height": 1 },
+            })
+            .to_string();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+            request
+        });
+
+        let directory = std::env::temp_dir().join(format!("zorsh_fetch_{}", std::process::id()));
+        let schemas = directory.join("schemas");
+        let output = directory.join("game.ts");
+        let cli = Cli::try_parse_from([
+            "zorsh-gen",
+            "fetch",
+            "game.near",
+            "--rpc-url",
+            &url,
+            "--schemas",
+            schemas.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .unwrap();
+        cli.command.unwrap().run().unwrap();
+
+        let request = server.join().unwrap();
+        assert_eq!(request["params"]["account_id"], "game.near");
+        assert_eq!(request["params"]["method_name"], "__contract_abi");
+        let mut written: Vec<String> = std::fs::read_dir(&schemas)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        written.sort();
+        assert_eq!(written, ["get_round_result.bin", "set_player_player.bin"]);
+        let generated = std::fs::read_to_string(&output).unwrap();
+        assert!(generated.contains(
+            "export const PlayerSchema = b.struct({\n  name: b.string(),\n  last_login: b.option(b.u64()),\n  role: ClassSchema,\n});\n"
+        ));
+        assert!(generated.contains("export const ClassSchema = b.enum({\n  Warrior: b.unit(),\n"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn annotates_hexdumps() {
+        let segments = [
+            payload::Segment {
+                span: 0..4,
+                path: "name".to_string(),
+                label: "length prefix: 18".to_string(),
+            },
+            payload::Segment {
+                span: 4..22,
+                path: "name".to_string(),
+                label: "String = \"abcdefghijklmnopqr\"".to_string(),
+            },
+        ];
+        let mut bytes = vec![18, 0, 0, 0];
+        bytes.extend(b"abcdefghijklmnopqr");
+        assert_eq!(
+            decode::annotate(&segments, &bytes),
+            [
+                "00000000  12 00 00 00                                      name  length prefix: 18",
+                "00000004  61 62 63 64 65 66 67 68 69 6a 6b 6c 6d 6e 6f 70  name  String = \"abcdefghijklmnopqr\"",
+                "00000014  71 72",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn scaffolds_projects() {
+        let directory = std::env::temp_dir().join(format!("zorsh_init_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let package = directory.join("package.json");
+        std::fs::write(
+            &package,
+            r#"{ "name": "app", "scripts": { "zorsh": "custom" } }"#,
+        )
+        .unwrap();
+        let init = |arguments: &[&str]| {
+            let mut all = vec!["zorsh-gen", "init", directory.to_str().unwrap()];
+            all.extend(arguments);
+            match Cli::try_parse_from(all).unwrap().command {
+                Some(Command::Init(args)) => init::init(&args).unwrap(),
+                command => panic!("{:?}", command),
+            }
+        };
+
+        let done = init(&["--output", "web/generated"]);
+        assert_eq!(done.len(), 5);
+        assert!(directory.join("schemas").is_dir());
+        assert!(directory.join("web/generated").is_dir());
+        assert!(directory.join("schemas/export_schema.rs").is_file());
+        let settings = config::Config::load(&directory.join(config::FILE_NAME)).unwrap();
+        let matches = Cli::command().try_get_matches_from(["zorsh-gen"]).unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        cli.apply(settings, &directory, &matches).unwrap();
+        assert_eq!(
+            cli.output(Path::new("ft.bin")),
+            directory.join("web/generated/ft.ts")
+        );
+        assert_eq!(cli.settings.runtime_module, "@zorsh/zorsh");
+
+        let package: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&package).unwrap()).unwrap();
+        assert_eq!(package["name"], "app");
+        // Scripts already there are kept
+        assert_eq!(package["scripts"]["zorsh"], "custom");
+        assert_eq!(package["scripts"]["zorsh:check"], "zorsh-gen --check");
+        assert!(package["dependencies"]["@zorsh/zorsh"].is_string());
+
+        let done = init(&["--output", "web/generated"]);
+        assert!(done.iter().all(|line| line.starts_with("skipped ")));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn dumps_schemas() {
+        let container = borsh::schema::BorshSchemaContainer::for_type::<fixtures::GameState>();
+        let tree = schema::tree(&container);
+        assert!(tree.starts_with("root: GameState\n\n"));
+        assert!(tree.contains(
+            "\nEffect: enum, 4 variants with a 1-byte tag\n  0 Damage: EffectDamage\n  1 Heal: EffectHeal\n"
+        ));
+        assert!(
+            tree.contains("\nString: sequence of 0..=4294967295 u8, with a 4-byte length prefix\n")
+        );
+        assert!(tree.contains("\nEffectHeal: tuple struct, 1 field\n  0: u32\n"));
+        assert!(tree.contains("\nStats: struct, 6 fields\n  health: u32\n"));
+
+        let json = schema::to_json(&container);
+        assert_eq!(json["declaration"], "GameState");
+        assert_eq!(
+            json["definitions"]["Option<u64>"]["variants"][1],
+            serde_json::json!({ "discriminant": 1, "name": "Some", "declaration": "u64" })
+        );
+        assert_eq!(
+            json["definitions"]["u32"],
+            serde_json::json!({ "kind": "primitive", "size": 4 })
+        );
+    }
+
+    #[test]
+    fn diffs_schemas() {
+        use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+        let container = |definitions: Vec<(&str, Definition)>| {
+            let definitions = definitions
+                .into_iter()
+                .map(|(declaration, definition)| (declaration.to_string(), definition));
+            BorshSchemaContainer::new("State".to_string(), definitions.collect())
+        };
+        let fields = |fields: &[(&str, &str)]| Definition::Struct {
+            fields: Fields::NamedFields(
+                fields
+                    .iter()
+                    .map(|(name, declaration)| (name.to_string(), declaration.to_string()))
+                    .collect(),
+            ),
+        };
+        let variants = |variants: &[(i64, &str)]| Definition::Enum {
+            tag_width: 1,
+            variants: variants
+                .iter()
+                .map(|(discriminant, name)| (*discriminant, name.to_string(), "Unit".to_string()))
+                .collect(),
+        };
+        let unit = || Definition::Struct {
+            fields: Fields::Empty,
+        };
+        let old = container(vec![
+            (
+                "State",
+                fields(&[("owner", "u32"), ("score", "u32"), ("mode", "Mode")]),
+            ),
+            (
+                "Mode",
+                variants(&[(0, "Idle"), (1, "Running"), (2, "Done")]),
+            ),
+            ("Unit", unit()),
+            ("Legacy", unit()),
+        ]);
+        let new = container(vec![
+            (
+                "State",
+                fields(&[
+                    ("score", "u64"),
+                    ("owner", "u32"),
+                    ("mode", "Mode"),
+                    ("level", "u8"),
+                ]),
+            ),
+            (
+                "Mode",
+                variants(&[(0, "Idle"), (1, "Paused"), (2, "Running")]),
+            ),
+            ("Unit", unit()),
+        ]);
+
+        assert_eq!(
+            schema::changes(&old, &new),
+            [
+                "removed Legacy: unit struct",
+                "changed Mode:",
+                "  variant 2 `Done` removed, was Unit",
+                "  variant 1 `Paused` added: Unit",
+                "  variant `Running` renumbered: 1 → 2",
+                "changed State:",
+                "  field `score`: u32 → u64",
+                "  field `level` added at position 3: u8",
+                "  fields reordered: owner, score, mode → score, owner, mode",
+            ]
+        );
+        assert!(schema::changes(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn watches_inputs_and_patterns() {
+        let base = Path::new("/project");
+        let watched = watch::Watched::new(
+            base,
+            &[PathBuf::from("contract.bin"), PathBuf::from("src/lib.rs")],
+            &["schemas/*.bin"],
+        );
+        assert!(watched.contains(Path::new("/project/contract.bin")));
+        assert!(watched.contains(Path::new("/project/src/lib.rs")));
+        assert!(watched.contains(Path::new("/project/schemas/new.bin")));
+        assert!(!watched.contains(Path::new("/project/contract.ts")));
+        assert!(!watched.contains(Path::new("/project/schemas/new.ts")));
+    }
+
+    #[test]
+    fn templates_output_paths() {
+        let cli = Cli::try_parse_from(["zorsh-gen", "schemas/ft.bin", "-o", "src/{name}.ts"]);
+        let cli = cli.unwrap();
+        assert_eq!(
+            cli.output(Path::new("schemas/ft.bin")),
+            Path::new("src/ft.ts")
+        );
+
+        let cli = Cli::try_parse_from(["zorsh-gen", "schemas/ft.bin", "--split"]).unwrap();
+        assert_eq!(
+            cli.output(Path::new("schemas/ft.bin")),
+            Path::new("schemas/ft")
+        );
+
+        let cli = Cli::try_parse_from(["zorsh-gen", "a.bin", "b.bin", "-o", "out.ts"]).unwrap();
+        assert_eq!(
+            cli.run(&mut Summary::default()).unwrap_err(),
+            Failure::new(
+                Kind::Usage,
+                "several inputs need `{name}` in `--output`, e.g. `src/{name}.ts`, or `--merge`"
+            )
+        );
+        let cli = Cli::try_parse_from(["zorsh-gen", "missing/*.bin"]).unwrap();
+        assert_eq!(
+            cli.run(&mut Summary::default()).unwrap_err().message,
+            "`missing/*.bin` matches no files"
+        );
+    }
+
+    #[test]
+    fn summarizes_runs_as_json() {
+        use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+        let directory = std::env::temp_dir().join(format!("zorsh_json_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let definitions = BTreeMap::from([(
+            "Account".to_string(),
+            Definition::Struct {
+                fields: Fields::NamedFields(vec![("id".to_string(), "AccountId".to_string())]),
+            },
+        )]);
+        let container = BorshSchemaContainer::new("Account".to_string(), definitions);
+        let input = directory.join("account.bin");
+        std::fs::write(&input, borsh::to_vec(&container).unwrap()).unwrap();
+        std::fs::write(directory.join("garbage.bin"), [1, 2, 3]).unwrap();
+        let run = |input: &Path, strict: bool| {
+            let output = directory.join("account.ts");
+            let mut arguments = vec![
+                "zorsh-gen",
+                input.to_str().unwrap(),
+                "-o",
+                output.to_str().unwrap(),
+                "--output-format",
+                "json",
+            ];
+            arguments.extend(strict.then_some("--strict"));
+            let mut summary = Summary::default();
+            let cli = Cli::try_parse_from(arguments).unwrap();
+            let failure = cli.run(&mut summary).err();
+            summary.to_json(failure.as_ref())
+        };
+
+        let json = run(&input, false);
+        assert_eq!(json["success"], true);
+        assert_eq!(json["files"][0]["status"], "created");
+        assert_eq!(json["warnings"][0]["declaration"], "AccountId");
+        assert_eq!(json["unresolved"], serde_json::json!(["AccountId"]));
+        assert_eq!(run(&input, false)["files"][0]["status"], "unchanged");
+
+        let json = run(&input, true);
+        assert_eq!(json["exit_code"], Kind::Strict as u8);
+        assert_eq!(
+            json["errors"][0]["references"],
+            serde_json::json!(["Account", "id", "AccountId"])
+        );
+        assert_eq!(
+            run(&directory.join("missing.bin"), false)["exit_code"],
+            Kind::Io as u8
+        );
+        assert_eq!(
+            run(&directory.join("garbage.bin"), false)["exit_code"],
+            Kind::Schema as u8
+        );
+
+        let cli = Cli::try_parse_from(["zorsh-gen", "-", "--output-format", "json"]).unwrap();
+        assert!(cli.check().is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn writes_nothing_on_dry_runs() {
+        let directory = std::env::temp_dir().join(format!("zorsh_dry_run_{}", std::process::id()));
+        let input = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../borsh_test/test_data/complex_schema.bin"
+        );
+        let output = directory.join("types");
+        let run = |dry_run: bool| {
+            let mut arguments = vec![
+                "zorsh-gen",
+                input,
+                "--split",
+                "-o",
+                output.to_str().unwrap(),
+            ];
+            arguments.extend(dry_run.then_some("--dry-run"));
+            let mut summary = Summary::default();
+            Cli::try_parse_from(arguments)
+                .unwrap()
+                .run(&mut summary)
+                .unwrap();
+            summary
+        };
+
+        let summary = run(true);
+        assert!(summary.files.len() > 1);
+        assert!(summary
+            .files
+            .iter()
+            .all(|file| file.status == "would-create"));
+        assert!(!directory.exists());
+
+        run(false);
+        let index = output.join("index.ts");
+        std::fs::write(&index, "").unwrap();
+        let summary = run(true);
+        let statuses: Vec<&str> = summary.files.iter().map(|file| file.status).collect();
+        assert_eq!(statuses.iter().filter(|s| **s == "would-update").count(), 1);
+        assert!(statuses
+            .iter()
+            .all(|s| ["unchanged", "would-update"].contains(s)));
+        assert_eq!(std::fs::read_to_string(&index).unwrap(), "");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn pipes_standard_streams() {
+        let mut cli = Cli::try_parse_from(["zorsh-gen", "-", "-"]).unwrap();
+        cli.split_stdout();
+        cli.check().unwrap();
+        assert_eq!(cli.inputs, ["-"]);
+        assert_eq!(cli.output(Path::new("-")), Path::new("-"));
+
+        let cli = Cli::try_parse_from(["zorsh-gen", "-"]).unwrap();
+        assert_eq!(cli.output(Path::new("-")), Path::new("-"));
+        let cli = Cli::try_parse_from(["zorsh-gen", "-", "-", "-o", "out.ts"]).unwrap();
+        assert_eq!(
+            cli.check().unwrap_err(),
+            "standard input can only be read once"
+        );
+    }
+
+    #[test]
+    fn checks_generated_files() {
+        let directory = std::env::temp_dir().join(format!("zorsh_check_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let output = directory.join("schema.ts");
+        let input = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../borsh_test/test_data/complex_schema.bin"
+        );
+        let arguments = |check: bool| {
+            let mut arguments = vec!["zorsh-gen", input, "-o", output.to_str().unwrap()];
+            arguments.extend(check.then_some("--check"));
+            Cli::try_parse_from(arguments).unwrap()
+        };
+
+        assert!(arguments(true).run(&mut Summary::default()).is_err());
+        assert!(!output.exists());
+        arguments(false).run(&mut Summary::default()).unwrap();
+        arguments(true).run(&mut Summary::default()).unwrap();
+
+        let generated = std::fs::read_to_string(&output).unwrap();
+        std::fs::write(&output, generated.replacen("export", "// export", 1)).unwrap();
+        let rendered = render_zorsh_schema(&[input], output.to_str().unwrap(), &Default::default());
+        let stale = compare::stale(&rendered.unwrap()).unwrap();
+        assert_eq!(
+            stale,
+            [format!("{}: out of date (+1 -1)", output.display())]
+        );
+        assert!(arguments(true).run(&mut Summary::default()).is_err());
+
+        let rendered = render_zorsh_schema(&[input], output.to_str().unwrap(), &Default::default());
+        let diff = compare::diff(&rendered.unwrap()).unwrap();
+        let path = output.display();
+        assert!(
+            diff.starts_with(&format!("--- {}\n+++ {}\n@@ ", path, path)),
+            "{}",
+            diff
+        );
+        assert!(diff.contains("\n-// export "), "{}", diff);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn reports_roundtrip_mismatches() {
+        let fixture = fixtures::corpus()
+            .into_iter()
+            .find(|fixture| fixture.name == "vec_u32")
+            .unwrap();
+        assert_eq!(roundtrip::mismatch(&fixture, &fixture.payload), None);
+
+        // The second element's low byte
+        let mut changed = fixture.payload.clone();
+        changed[8] = 9;
+        let report = roundtrip::mismatch(&fixture, &changed).unwrap();
+        assert!(report.starts_with("differs at byte 8 ([1]: "), "{}", report);
+        assert!(
+            report.contains("expected 02 00 00 00 03 00 00 00, got 09 00"),
+            "{}",
+            report
+        );
+
+        let report = roundtrip::mismatch(&fixture, &fixture.payload[..12]).unwrap();
+        assert!(
+            report.contains("got the end (12 bytes instead of 16)"),
+            "{}",
+            report
+        );
+    }
+}
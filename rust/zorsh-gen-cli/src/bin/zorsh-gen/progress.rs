@@ -0,0 +1,112 @@
+// src/bin/zorsh-gen/progress.rs
+//! `--progress`: showing how far a long run is, as a bar redrawn on standard error, or as
+//! a line per file for logs.
+use std::fmt::Display;
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often the bar is redrawn at most, so that drawing doesn't slow the run down.
+const REDRAW: Duration = Duration::from_millis(50);
+
+/// The characters the bar is wide.
+const WIDTH: usize = 24;
+
+/// How progress is shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// A bar, redrawn in place.
+    Bar,
+    /// A line per input and per file.
+    Lines,
+    Off,
+}
+
+impl Style {
+    /// A bar if standard error is a terminal, otherwise nothing.
+    pub fn auto() -> Self {
+        match std::io::stderr().is_terminal() {
+            true => Style::Bar,
+            false => Style::Off,
+        }
+    }
+}
+
+/// Shows the progress of a run in its style, clearing the bar when dropped.
+pub struct Reporter {
+    style: Style,
+    /// When the bar was last drawn, if it's on screen.
+    drawn: Mutex<Option<Instant>>,
+}
+
+impl Reporter {
+    pub fn new(style: Style) -> Self {
+        Self {
+            style,
+            drawn: Mutex::new(None),
+        }
+    }
+
+    /// Shows that `done` of `total` things, the last of them `label`, are done or being
+    /// done.
+    pub fn step(&self, done: usize, total: usize, what: &str, label: impl Display) {
+        if self.style != Style::Bar {
+            return;
+        }
+        let mut drawn = self.drawn.lock().unwrap_or_else(|e| e.into_inner());
+        if drawn.is_some_and(|at| at.elapsed() < REDRAW) && done < total {
+            return;
+        }
+        let bar = render(done, total, what, label);
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{}", bar);
+        let _ = stderr.flush();
+        *drawn = Some(Instant::now());
+    }
+
+    /// Prints a line of status, such as for a file written, in the `Lines` style.
+    pub fn line(&self, text: impl Display) {
+        if self.style == Style::Lines {
+            eprintln!("{}", text);
+        }
+    }
+
+    /// Clears the bar, such as before printing an error.
+    pub fn clear(&self) {
+        let mut drawn = self.drawn.lock().unwrap_or_else(|e| e.into_inner());
+        if drawn.take().is_some() {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for Reporter {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl zorsh_gen::Progress for Reporter {
+    fn declaration(&self, done: usize, total: usize, declaration: &str) {
+        self.step(done, total, "declarations", declaration);
+    }
+}
+
+/// Returns the bar, e.g. `[##########--------------] 40/96 declarations  Player`.
+pub fn render(done: usize, total: usize, what: &str, label: impl Display) -> String {
+    let filled = (done * WIDTH)
+        .checked_div(total)
+        .unwrap_or(WIDTH)
+        .min(WIDTH);
+    format!(
+        "[{}{}] {}/{} {}  {}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        done,
+        total,
+        what,
+        label
+    )
+}
@@ -0,0 +1,221 @@
+// src/bin/zorsh-gen/report.rs
+//! Printing errors in the style of rustc: colored when standard error is a terminal, and
+//! with the chain of references from the root type to the declaration at fault. Or, with
+//! `--output-format json`, summing up a run as JSON for build systems, which also tell
+//! failures apart by the exit code.
+use anstyle::{AnsiColor, Style};
+use serde::Serialize;
+use std::fmt::{Display, Write};
+use std::io;
+use std::path::Path;
+use std::process::ExitCode;
+use zorsh_gen::ZorshGenError;
+
+const ERROR: Style = AnsiColor::Red.on_default().bold();
+const ARROW: Style = AnsiColor::Blue.on_default().bold();
+const NAME: Style = Style::new().bold();
+
+/// Prints `text` to standard output. A closed pipe, as from `| head`, isn't worth
+/// reporting.
+pub fn output(text: impl Display) {
+    use std::io::Write;
+    let _ = write!(io::stdout().lock(), "{}", text);
+}
+
+/// Prints `message` as an error.
+pub fn error(message: impl Display) {
+    anstream::eprintln!("{ERROR}error{ERROR:#}: {}", message);
+}
+
+/// Describes an error of the generator, following each of its diagnostics with the
+/// references leading to its declaration, e.g. `--> GameState → players → Player`.
+pub fn describe(error: &ZorshGenError) -> String {
+    let Some(diagnostics) = error.diagnostics() else {
+        return error.to_string();
+    };
+    let mut text = String::new();
+    for (index, diagnostic) in diagnostics.0.iter().enumerate() {
+        if index > 0 {
+            let _ = write!(text, "\n{ERROR}error{ERROR:#}: ");
+        }
+        text.push_str(&diagnostic.message);
+        if !diagnostic.references.is_empty() {
+            let references: Vec<String> = diagnostic
+                .references
+                .iter()
+                .map(|reference| format!("{NAME}{}{NAME:#}", reference))
+                .collect();
+            let _ = write!(text, "\n  {ARROW}-->{ARROW:#} {}", references.join(" → "));
+        }
+    }
+    text
+}
+
+/// What went wrong, as the exit code says.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// Anything else, such as outputs out of date with `--check`: exit code 1.
+    Other = 1,
+    /// Flags or settings that don't go together: 2, as for clap's own errors.
+    Usage = 2,
+    /// Reading inputs or writing outputs: 3.
+    Io = 3,
+    /// Schema containers that don't parse, or declarations that can't be generated: 4.
+    Schema = 4,
+    /// Only placeholders, which `--strict` makes errors: 5.
+    Strict = 5,
+}
+
+impl Kind {
+    /// Tells what went wrong from an error of the generator.
+    pub fn of(error: &ZorshGenError) -> Self {
+        match error {
+            ZorshGenError::Unresolved(_) => Kind::Strict,
+            ZorshGenError::Schema { .. }
+            | ZorshGenError::Conflict { .. }
+            | ZorshGenError::Unsupported(_)
+            | ZorshGenError::CustomRegions { .. } => Kind::Schema,
+            ZorshGenError::Options(_) => Kind::Usage,
+            ZorshGenError::Io(_) => Kind::Io,
+            _ => Kind::Other,
+        }
+    }
+
+    pub fn exit_code(self) -> ExitCode {
+        ExitCode::from(self as u8)
+    }
+}
+
+/// An error, with what kind it is.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Failure {
+    pub kind: Kind,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(kind: Kind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl Failure {
+    /// An I/O error, already described.
+    pub fn io(message: String) -> Self {
+        Self::new(Kind::Io, message)
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Self::new(Kind::Other, message)
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// What a run did, printed with `--output-format json`.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub files: Vec<FileStatus>,
+    /// The placeholders written, which `--strict` would make errors.
+    pub warnings: Vec<Issue>,
+    pub errors: Vec<Issue>,
+}
+
+/// A file generated or compared.
+#[derive(Debug, Serialize)]
+pub struct FileStatus {
+    pub path: String,
+    /// `created`, `updated` or `unchanged`; with `--dry-run`, `would-create`,
+    /// `would-update` or `unchanged`; with `--check`, `up-to-date`, `stale` or `missing`.
+    pub status: &'static str,
+}
+
+/// An error or warning, with the declaration it concerns if it does.
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    /// The input being generated from, if it's about one.
+    pub input: Option<String>,
+    pub message: String,
+    pub declaration: Option<String>,
+    pub references: Vec<String>,
+    /// Whether it's about a placeholder for a declaration that couldn't be resolved.
+    #[serde(skip)]
+    pub placeholder: bool,
+}
+
+impl Summary {
+    pub fn file(&mut self, path: &Path, status: &'static str) {
+        self.files.push(FileStatus {
+            path: path.display().to_string(),
+            status,
+        });
+    }
+
+    /// Records an error of the generator, an issue per diagnostic.
+    pub fn generator_error(&mut self, input: Option<&str>, error: &ZorshGenError, warning: bool) {
+        let issues = match warning {
+            true => &mut self.warnings,
+            false => &mut self.errors,
+        };
+        let input = input.map(str::to_string);
+        let Some(diagnostics) = error.diagnostics() else {
+            issues.push(Issue {
+                input,
+                message: error.to_string(),
+                declaration: None,
+                references: Vec::new(),
+                placeholder: false,
+            });
+            return;
+        };
+        for diagnostic in &diagnostics.0 {
+            issues.push(Issue {
+                input: input.clone(),
+                message: diagnostic.message.clone(),
+                declaration: diagnostic.declaration.clone(),
+                references: diagnostic.references.clone(),
+                placeholder: diagnostic.placeholder,
+            });
+        }
+    }
+
+    pub fn error(&mut self, input: Option<&str>, message: impl Display) {
+        self.errors.push(Issue {
+            input: input.map(str::to_string),
+            message: message.to_string(),
+            declaration: None,
+            references: Vec::new(),
+            placeholder: false,
+        });
+    }
+
+    /// Returns the summary as JSON, with how the run ended.
+    pub fn to_json(&self, failure: Option<&Failure>) -> serde_json::Value {
+        let mut unresolved: Vec<&str> = self
+            .warnings
+            .iter()
+            .chain(&self.errors)
+            .filter(|issue| issue.placeholder)
+            .filter_map(|issue| issue.declaration.as_deref())
+            .collect();
+        unresolved.sort_unstable();
+        unresolved.dedup();
+        serde_json::json!({
+            "success": failure.is_none(),
+            "exit_code": failure.map_or(0, |failure| failure.kind as u8),
+            "files": self.files,
+            "warnings": self.warnings,
+            "errors": self.errors,
+            "unresolved": unresolved,
+        })
+    }
+}
@@ -0,0 +1,240 @@
+// src/bin/zorsh-gen/roundtrip.rs
+//! `roundtrip`: checks the TypeScript runtime against the Rust one. Generates schemas for
+//! each fixture of the cross-language corpus, has a JavaScript runner deserialize and
+//! reserialize every payload through them with zorsh, and reports the first byte each
+//! result differs from the payload at, with the field that byte encodes.
+use super::report;
+use clap::Args;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process::Command;
+use zorsh_gen::fixtures::{self, Fixture};
+use zorsh_gen::payload;
+use zorsh_gen::{
+    render_zorsh_schema, write_rendered, GeneratorOptions, RuntimeImport, RUNTIME_PACKAGE,
+};
+
+/// The file of the program the runner runs.
+const HARNESS: &str = "roundtrip.mjs";
+
+/// The name each fixture's root type is exported under.
+const ROOT: &str = "Fixture";
+
+/// How many bytes around a difference a report shows.
+const WINDOW: usize = 8;
+
+#[derive(Args, Debug)]
+pub struct RoundtripArgs {
+    /// Check the fixtures a `fixtures` run wrote to this directory, instead of the
+    /// corpus built into zorsh-gen.
+    #[arg(long, value_name = "DIR")]
+    fixtures: Option<PathBuf>,
+    /// Check only fixtures whose names match this glob, e.g. `float_*`.
+    #[arg(long, value_name = "PATTERN")]
+    only: Option<glob::Pattern>,
+    /// The command running JavaScript that imports TypeScript, such as Node.js 22.18 or
+    /// later, `bun` or `npx tsx`.
+    #[arg(long, value_name = "COMMAND", default_value = "node")]
+    runner: String,
+    /// The module to import the zorsh runtime from, resolved from the working directory.
+    #[arg(long, default_value = RUNTIME_PACKAGE)]
+    runtime_module: String,
+    /// Where to write the schemas and the runner's program; created in, and resolving
+    /// modules from, the working directory.
+    #[arg(long, value_name = "DIR", default_value = ".zorsh-roundtrip")]
+    work: PathBuf,
+    /// Leave the work directory in place afterwards, for running the program by hand.
+    #[arg(long)]
+    keep: bool,
+}
+
+/// What the runner reports for a fixture: the payload reserialized, or why it couldn't be.
+#[derive(Debug, Deserialize)]
+struct Outcome {
+    name: String,
+    #[serde(default)]
+    bytes: Option<Vec<u8>>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// `roundtrip`: round-trips the fixtures and reports those that don't survive it.
+pub fn run(args: &RoundtripArgs) -> Result<(), String> {
+    let fixtures = match &args.fixtures {
+        Some(directory) => fixtures::read(directory)
+            .map_err(|error| format!("{}: {}", directory.display(), error))?,
+        None => fixtures::corpus(),
+    };
+    let fixtures: Vec<Fixture> = fixtures
+        .into_iter()
+        .filter(|fixture| {
+            args.only
+                .as_ref()
+                .is_none_or(|only| only.matches(&fixture.name))
+        })
+        .collect();
+    if fixtures.is_empty() {
+        return Err("no fixtures match `--only`".to_string());
+    }
+
+    let result = prepare(args, &fixtures).and_then(|()| execute(args));
+    if !args.keep {
+        let _ = std::fs::remove_dir_all(&args.work);
+    }
+    let outcomes = result?;
+
+    let mut failed = 0;
+    for fixture in &fixtures {
+        let problem = match outcomes.get(&fixture.name) {
+            None => Some("the runner reported nothing".to_string()),
+            Some(Outcome {
+                error: Some(error), ..
+            }) => Some(error.clone()),
+            Some(Outcome {
+                bytes: Some(bytes), ..
+            }) => mismatch(fixture, bytes),
+            Some(Outcome { .. }) => Some("the runner reported no bytes".to_string()),
+        };
+        if let Some(problem) = problem {
+            report::error(format_args!("{}: {}", fixture.name, problem));
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => {
+            report::output(format_args!(
+                "all {} fixtures round-trip through zorsh\n",
+                fixtures.len()
+            ));
+            Ok(())
+        }
+        failed => Err(format!(
+            "{} of {} fixtures don't round-trip through zorsh",
+            failed,
+            fixtures.len()
+        )),
+    }
+}
+
+/// Writes the fixtures, a schema module for each, and the runner's program.
+fn prepare(args: &RoundtripArgs, fixtures: &[Fixture]) -> Result<(), String> {
+    let work = &args.work;
+    let failed = |error: std::io::Error| format!("{}: {}", work.display(), error);
+    fixtures::write(work, fixtures, |_, _| {}).map_err(failed)?;
+    for fixture in fixtures {
+        // Roots such as `Vec<u32>` aren't TypeScript identifiers
+        let root = fixture.container.declaration().clone();
+        let options = GeneratorOptions {
+            runtime: RuntimeImport {
+                module: args.runtime_module.clone(),
+                ..RuntimeImport::default()
+            },
+            schema_registry: true,
+            renames: BTreeMap::from([(root, ROOT.to_string())]),
+            ..GeneratorOptions::default()
+        };
+        let schema = work.join(fixture.schema_file());
+        let module = work.join(format!("{}.ts", fixture.name));
+        let rendered = render_zorsh_schema(
+            &[&schema.to_string_lossy()],
+            &module.to_string_lossy(),
+            &options,
+        )
+        .map_err(|error| format!("{}: {}", fixture.name, report::describe(&error)))?;
+        write_rendered(&rendered).map_err(|error| failed(error.into()))?;
+    }
+    std::fs::write(work.join(HARNESS), HARNESS_SOURCE).map_err(failed)
+}
+
+/// Runs the program, and returns what it reported for each fixture.
+fn execute(args: &RoundtripArgs) -> Result<HashMap<String, Outcome>, String> {
+    let mut words = args.runner.split_whitespace();
+    let program = words.next().ok_or("`--runner` is empty")?;
+    let output = Command::new(program)
+        .args(words)
+        .arg(args.work.join(HARNESS))
+        .output()
+        .map_err(|error| format!("couldn't run `{}`: {}", args.runner, error))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let outcomes: HashMap<String, Outcome> = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Outcome>(line).ok())
+        .map(|outcome| (outcome.name.clone(), outcome))
+        .collect();
+    if outcomes.is_empty() {
+        return Err(format!(
+            "`{}` reported no fixtures:\n{}",
+            args.runner,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+    Ok(outcomes)
+}
+
+/// Describes where `bytes` first differ from the fixture's payload, or returns `None` if
+/// they don't.
+pub fn mismatch(fixture: &Fixture, bytes: &[u8]) -> Option<String> {
+    let expected = &fixture.payload;
+    let offset = expected
+        .iter()
+        .zip(bytes)
+        .position(|(a, b)| a != b)
+        .unwrap_or(expected.len().min(bytes.len()));
+    if offset == expected.len() && offset == bytes.len() {
+        return None;
+    }
+    let declaration = fixture.container.declaration();
+    let location = payload::decode(&fixture.container, declaration, expected)
+        .ok()
+        .and_then(|node| {
+            let segments = node.segments();
+            segments.into_iter().find(|s| s.span.contains(&offset))
+        })
+        .map(|segment| match segment.path.as_str() {
+            "" => format!(" ({})", segment.label),
+            path => format!(" ({}: {})", path, segment.label),
+        })
+        .unwrap_or_default();
+    let window = |bytes: &[u8]| match bytes.get(offset..) {
+        Some([]) | None => "the end".to_string(),
+        Some(rest) => {
+            let shown: Vec<String> = rest
+                .iter()
+                .take(WINDOW)
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            let more = if rest.len() > WINDOW { " …" } else { "" };
+            format!("{}{}", shown.join(" "), more)
+        }
+    };
+    Some(format!(
+        "differs at byte {}{}: expected {}, got {} ({} bytes instead of {})",
+        offset,
+        location,
+        window(expected),
+        window(bytes),
+        bytes.len(),
+        expected.len()
+    ))
+}
+
+/// The program the runner runs: deserializes and reserializes each payload listed in the
+/// manifest through the root schema of its module, printing a JSON line per fixture.
+const HARNESS_SOURCE: &str = r#"// Written by zorsh-gen roundtrip; round-trips each fixture's payload through zorsh.
+import { readFileSync } from "node:fs";
+
+const manifest = JSON.parse(readFileSync(new URL("./manifest.json", import.meta.url), "utf8"));
+for (const { name, declaration, payload } of manifest) {
+  let outcome;
+  try {
+    const { schemaRegistry } = await import(`./${name}.ts`);
+    const schema = schemaRegistry[declaration];
+    const bytes = new Uint8Array(readFileSync(new URL(`./${payload}`, import.meta.url)));
+    outcome = { name, bytes: Array.from(schema.serialize(schema.deserialize(bytes))) };
+  } catch (error) {
+    outcome = { name, error: String(error instanceof Error ? error.message : error) };
+  }
+  console.log(JSON.stringify(outcome));
+}
+"#;
@@ -0,0 +1,356 @@
+// src/bin/zorsh-gen/schema.rs
+//! `schema` subcommands, looking into schema containers themselves.
+use super::decode::read_schema;
+use super::report;
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, DiscriminantValue, Fields};
+use clap::{Args, Subcommand};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug)]
+pub enum SchemaCommand {
+    /// Print the declarations and definitions of a schema container.
+    Dump(DumpArgs),
+    /// Print what changed between two schema containers: declarations added and removed,
+    /// and fields or variants added, removed, reordered, renumbered or retyped.
+    Diff(DiffArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct DumpArgs {
+    /// The borsh-serialized `BorshSchemaContainer`, or `-` for standard input.
+    schema: PathBuf,
+    /// Print JSON instead of a tree.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// The schema container before the change.
+    old: PathBuf,
+    /// The schema container after the change.
+    new: PathBuf,
+}
+
+impl SchemaCommand {
+    pub fn run(&self) -> Result<(), String> {
+        match self {
+            SchemaCommand::Dump(args) => {
+                let container = read_schema(&args.schema)?;
+                match args.json {
+                    true => report::output(format_args!("{:#}\n", to_json(&container))),
+                    false => report::output(tree(&container)),
+                }
+                Ok(())
+            }
+            SchemaCommand::Diff(args) => {
+                let changes = changes(&read_schema(&args.old)?, &read_schema(&args.new)?);
+                match changes.is_empty() {
+                    true => report::output("no changes\n"),
+                    false => report::output(changes.join("\n") + "\n"),
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the container as a JSON object with its root `declaration` and its
+/// `definitions` by declaration.
+pub fn to_json(container: &BorshSchemaContainer) -> Value {
+    let definitions: serde_json::Map<String, Value> = container
+        .definitions()
+        .map(|(declaration, definition)| (declaration.clone(), definition_json(definition)))
+        .collect();
+    json!({
+        "declaration": container.declaration(),
+        "definitions": definitions,
+    })
+}
+
+fn definition_json(definition: &Definition) -> Value {
+    match definition {
+        Definition::Primitive(size) => json!({ "kind": "primitive", "size": size }),
+        Definition::Sequence {
+            length_width,
+            length_range,
+            elements,
+        } => json!({
+            "kind": "sequence",
+            "length_width": length_width,
+            "length_range": [length_range.start(), length_range.end()],
+            "elements": elements,
+        }),
+        Definition::Tuple { elements } => json!({ "kind": "tuple", "elements": elements }),
+        Definition::Enum {
+            tag_width,
+            variants,
+        } => json!({
+            "kind": "enum",
+            "tag_width": tag_width,
+            "variants": variants
+                .iter()
+                .map(|(discriminant, name, declaration)| json!({
+                    "discriminant": discriminant,
+                    "name": name,
+                    "declaration": declaration,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        Definition::Struct { fields } => match fields {
+            Fields::NamedFields(fields) => json!({
+                "kind": "struct",
+                "fields": fields
+                    .iter()
+                    .map(|(name, declaration)| json!({ "name": name, "declaration": declaration }))
+                    .collect::<Vec<_>>(),
+            }),
+            Fields::UnnamedFields(elements) => json!({ "kind": "struct", "elements": elements }),
+            Fields::Empty => json!({ "kind": "struct" }),
+        },
+    }
+}
+
+/// Returns the container as a tree, a definition per declaration with its fields or
+/// variants indented below.
+pub fn tree(container: &BorshSchemaContainer) -> String {
+    let mut tree = format!("root: {}\n", container.declaration());
+    for (declaration, definition) in container.definitions() {
+        let _ = writeln!(tree, "\n{}: {}", declaration, summary(definition));
+        for line in members(definition) {
+            let _ = writeln!(tree, "  {}", line);
+        }
+    }
+    tree
+}
+
+/// Describes a definition in a line.
+pub fn summary(definition: &Definition) -> String {
+    match definition {
+        Definition::Primitive(size) => format!("primitive, {} bytes", size),
+        Definition::Sequence {
+            length_width,
+            length_range,
+            elements,
+        } => match *length_width {
+            Definition::ARRAY_LENGTH_WIDTH if length_range.start() == length_range.end() => {
+                format!("array of {} {}", length_range.start(), elements)
+            }
+            Definition::ARRAY_LENGTH_WIDTH => format!(
+                "sequence of {}..={} {}, without a length prefix",
+                length_range.start(),
+                length_range.end(),
+                elements
+            ),
+            width => format!(
+                "sequence of {}..={} {}, with a {}-byte length prefix",
+                length_range.start(),
+                length_range.end(),
+                elements,
+                width
+            ),
+        },
+        Definition::Tuple { elements } => format!("tuple, {}", count(elements.len(), "element")),
+        Definition::Enum {
+            tag_width,
+            variants,
+        } => format!(
+            "enum, {} with a {}-byte tag",
+            count(variants.len(), "variant"),
+            tag_width
+        ),
+        Definition::Struct { fields } => match fields {
+            Fields::NamedFields(fields) => format!("struct, {}", count(fields.len(), "field")),
+            Fields::UnnamedFields(elements) => {
+                format!("tuple struct, {}", count(elements.len(), "field"))
+            }
+            Fields::Empty => "unit struct".to_string(),
+        },
+    }
+}
+
+/// Returns a line per field or variant of a definition.
+fn members(definition: &Definition) -> Vec<String> {
+    match definition {
+        Definition::Enum { variants, .. } => variants
+            .iter()
+            .map(|(discriminant, name, declaration)| {
+                format!("{} {}: {}", discriminant, name, declaration)
+            })
+            .collect(),
+        Definition::Struct {
+            fields: Fields::NamedFields(fields),
+        } => fields
+            .iter()
+            .map(|(name, declaration)| format!("{}: {}", name, declaration))
+            .collect(),
+        Definition::Tuple { elements }
+        | Definition::Struct {
+            fields: Fields::UnnamedFields(elements),
+        } => elements
+            .iter()
+            .enumerate()
+            .map(|(index, declaration)| format!("{}: {}", index, declaration))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns e.g. `1 field` or `2 fields`.
+fn count(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+/// Returns a line for each change from `old` to `new`, with the changes to a definition
+/// indented below its declaration.
+pub fn changes(old: &BorshSchemaContainer, new: &BorshSchemaContainer) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.declaration() != new.declaration() {
+        changes.push(format!(
+            "root: {} → {}",
+            old.declaration(),
+            new.declaration()
+        ));
+    }
+    for (declaration, definition) in old.definitions() {
+        if new.get_definition(declaration).is_none() {
+            changes.push(format!("removed {}: {}", declaration, summary(definition)));
+        }
+    }
+    for (declaration, definition) in new.definitions() {
+        let Some(previous) = old.get_definition(declaration) else {
+            changes.push(format!("added {}: {}", declaration, summary(definition)));
+            continue;
+        };
+        let changed = definition_changes(previous, definition);
+        if !changed.is_empty() {
+            changes.push(format!("changed {}:", declaration));
+            changes.extend(changed.into_iter().map(|change| format!("  {}", change)));
+        }
+    }
+    changes
+}
+
+/// Returns the changes from one definition of a declaration to another.
+fn definition_changes(old: &Definition, new: &Definition) -> Vec<String> {
+    if old == new {
+        return Vec::new();
+    }
+    match (old, new) {
+        (
+            Definition::Struct {
+                fields: Fields::NamedFields(old),
+            },
+            Definition::Struct {
+                fields: Fields::NamedFields(new),
+            },
+        ) => field_changes(old, new),
+        (
+            Definition::Enum {
+                tag_width: old_width,
+                variants: old,
+            },
+            Definition::Enum {
+                tag_width: new_width,
+                variants: new,
+            },
+        ) => {
+            let mut changes = Vec::new();
+            if old_width != new_width {
+                changes.push(format!("tag width: {} → {} bytes", old_width, new_width));
+            }
+            changes.extend(variant_changes(old, new));
+            changes
+        }
+        _ => vec![format!("{} → {}", summary(old), summary(new))],
+    }
+}
+
+fn field_changes(old: &[(String, String)], new: &[(String, String)]) -> Vec<String> {
+    let mut changes = Vec::new();
+    let find = |fields: &[(String, String)], name: &str| {
+        fields.iter().position(|(field, _)| field == name)
+    };
+    for (name, declaration) in old {
+        if find(new, name).is_none() {
+            changes.push(format!("field `{}` removed, was {}", name, declaration));
+        }
+    }
+    for (index, (name, declaration)) in new.iter().enumerate() {
+        match find(old, name) {
+            None => changes.push(format!(
+                "field `{}` added at position {}: {}",
+                name, index, declaration
+            )),
+            Some(previous) if old[previous].1 != *declaration => changes.push(format!(
+                "field `{}`: {} → {}",
+                name, old[previous].1, declaration
+            )),
+            Some(_) => {}
+        }
+    }
+    // Fields kept in both, in their old and new order
+    let kept = |fields: &[(String, String)], other: &[(String, String)]| {
+        fields
+            .iter()
+            .map(|(name, _)| name.clone())
+            .filter(|name| find(other, name).is_some())
+            .collect::<Vec<_>>()
+    };
+    let (before, after) = (kept(old, new), kept(new, old));
+    if before != after {
+        changes.push(format!(
+            "fields reordered: {} → {}",
+            before.join(", "),
+            after.join(", ")
+        ));
+    }
+    changes
+}
+
+type Variant = (DiscriminantValue, String, Declaration);
+
+fn variant_changes(old: &[Variant], new: &[Variant]) -> Vec<String> {
+    let mut changes = Vec::new();
+    let find = |variants: &[Variant], name: &str| {
+        variants
+            .iter()
+            .find(|(_, variant, _)| variant == name)
+            .cloned()
+    };
+    for (discriminant, name, declaration) in old {
+        if find(new, name).is_none() {
+            changes.push(format!(
+                "variant {} `{}` removed, was {}",
+                discriminant, name, declaration
+            ));
+        }
+    }
+    for (discriminant, name, declaration) in new {
+        let Some((previous, _, previous_declaration)) = find(old, name) else {
+            changes.push(format!(
+                "variant {} `{}` added: {}",
+                discriminant, name, declaration
+            ));
+            continue;
+        };
+        if previous != *discriminant {
+            changes.push(format!(
+                "variant `{}` renumbered: {} → {}",
+                name, previous, discriminant
+            ));
+        }
+        if previous_declaration != *declaration {
+            changes.push(format!(
+                "variant `{}`: {} → {}",
+                name, previous_declaration, declaration
+            ));
+        }
+    }
+    changes
+}
@@ -0,0 +1,78 @@
+// src/bin/zorsh-gen/watch.rs
+//! `--watch`: regenerating whenever an input changes.
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long to wait for an editor or build to finish writing before regenerating, since
+/// a single save often shows up as several events.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// The files that trigger regeneration.
+pub struct Watched {
+    /// Absolute paths of the inputs and other files read.
+    files: BTreeSet<PathBuf>,
+    /// Absolute glob patterns, which files created later can match.
+    patterns: Vec<glob::Pattern>,
+}
+
+impl Watched {
+    /// Watches `files` and anything matching the glob `patterns`, relative to `base`.
+    pub fn new(base: &Path, files: &[PathBuf], patterns: &[&str]) -> Self {
+        Self {
+            files: files.iter().map(|file| base.join(file)).collect(),
+            patterns: patterns
+                .iter()
+                .filter_map(|pattern| {
+                    glob::Pattern::new(&base.join(pattern).to_string_lossy()).ok()
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether a change to `path` should trigger regeneration.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path) || self.patterns.iter().any(|p| p.matches_path(path))
+    }
+
+    /// The directories to watch: those of the files, and of the patterns up to their
+    /// first wildcard. Directories are watched rather than files so that files replaced
+    /// by a rename, as many editors save them, are still followed.
+    fn directories(&self) -> BTreeSet<PathBuf> {
+        let files = self.files.iter().filter_map(|file| file.parent());
+        let patterns = self.patterns.iter().map(|pattern| {
+            let pattern = pattern.as_str();
+            let literal = &pattern[..pattern.find(['*', '?', '[']).unwrap_or(pattern.len())];
+            // The literal part ends in a directory separator or a partial file name
+            match literal.ends_with(std::path::MAIN_SEPARATOR) {
+                true => Path::new(literal),
+                false => Path::new(literal).parent().unwrap_or(Path::new(literal)),
+            }
+        });
+        files.chain(patterns).map(Path::to_path_buf).collect()
+    }
+}
+
+/// Calls `regenerate` whenever a watched file changes, until the watcher fails.
+pub fn watch(watched: &Watched, mut regenerate: impl FnMut()) -> notify::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for directory in watched.directories() {
+        watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+    }
+    while let Ok(event) = receiver.recv() {
+        let event = event?;
+        let changed = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        );
+        if !changed || !event.paths.iter().any(|path| watched.contains(path)) {
+            continue;
+        }
+        while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+        regenerate();
+    }
+    Ok(())
+}
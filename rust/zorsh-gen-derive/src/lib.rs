@@ -0,0 +1,34 @@
+// src/lib.rs
+//! `#[derive(ZorshExport)]`: registers a type with `zorsh_gen::generate_all`,
+//! which generates the schemas of every registered type in one module.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Registers the type for `zorsh_gen::generate_all`. The type must also implement
+/// `BorshSchema`, and can't be generic, since it's the schema of one instantiation that is
+/// generated; list instantiations with `zorsh_export!` instead.
+#[proc_macro_derive(ZorshExport)]
+pub fn derive_zorsh_export(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    if !input.generics.params.is_empty() {
+        return syn::Error::new_spanned(
+            &input.generics,
+            "ZorshExport can't register generic types; list their instantiations with `zorsh_export!` instead",
+        )
+        .into_compile_error()
+        .into();
+    }
+    let ident = &input.ident;
+    let name = ident.to_string();
+    quote! {
+        ::zorsh_gen::__private::inventory::submit! {
+            ::zorsh_gen::Registered {
+                name: #name,
+                module: ::core::module_path!(),
+                container: ::zorsh_gen::__private::BorshSchemaContainer::for_type::<#ident>,
+            }
+        }
+    }
+    .into()
+}
@@ -0,0 +1,153 @@
+// src/backend.rs
+//! [`Backend`], what turns the intermediate representation into code, with the built-in
+//! backends [`Target`] chooses between.
+use super::{ir, Generated, GenerationReport, GeneratorOptions, Parser, Target, ZorshGenError};
+use std::fmt;
+
+/// Generates code for a schema, such as schemas for a runtime other than zorsh:
+///
+/// ```
+/// use zorsh_gen::{ir, Backend, Generated, GeneratorOptions, ZorshGenError};
+///
+/// /// Lists the structs a schema defines.
+/// struct StructNames;
+///
+/// impl Backend for StructNames {
+///     fn generate(
+///         &self,
+///         schema: &ir::Schema,
+///         _options: &GeneratorOptions,
+///     ) -> Result<Generated, ZorshGenError> {
+///         let names: Vec<&str> = schema
+///             .types
+///             .iter()
+///             .filter(|(_, ty)| matches!(ty, ir::Type::Struct(_)))
+///             .map(|(declaration, _)| declaration.as_str())
+///             .collect();
+///         Ok(Generated {
+///             output: names.join("\n"),
+///             warnings: Vec::new(),
+///         })
+///     }
+/// }
+/// ```
+///
+/// Set as [`GeneratorOptions::backend`], a backend replaces the one `target` chooses for
+/// single-file output. Options are passed along for backends to honour as they see fit.
+pub trait Backend {
+    /// Returns the code for the root type of `schema` and the types it refers to.
+    fn generate(
+        &self,
+        schema: &ir::Schema,
+        options: &GeneratorOptions,
+    ) -> Result<Generated, ZorshGenError>;
+}
+
+impl fmt::Debug for dyn Backend + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Backend")
+    }
+}
+
+/// TypeScript zorsh schemas, for [`Target::Zorsh`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZorshBackend;
+
+impl Backend for ZorshBackend {
+    fn generate(
+        &self,
+        schema: &ir::Schema,
+        options: &GeneratorOptions,
+    ) -> Result<Generated, ZorshGenError> {
+        let mut parser = Parser::new(schema, options);
+        let output = parser.parse()?;
+        Ok(Generated {
+            output,
+            warnings: parser.warnings(),
+        })
+    }
+}
+
+/// A JSON Schema document, for [`Target::JsonSchema`].
+#[cfg(feature = "backend-json-schema")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonSchemaBackend;
+
+#[cfg(feature = "backend-json-schema")]
+impl Backend for JsonSchemaBackend {
+    fn generate(
+        &self,
+        schema: &ir::Schema,
+        options: &GeneratorOptions,
+    ) -> Result<Generated, ZorshGenError> {
+        let mut parser = Parser::new(schema, options);
+        let output = parser.json_schema()?;
+        Ok(Generated {
+            output,
+            warnings: parser.warnings(),
+        })
+    }
+}
+
+/// TypeScript Zod schemas, for [`Target::Zod`].
+#[cfg(feature = "backend-zod")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZodBackend;
+
+#[cfg(feature = "backend-zod")]
+impl Backend for ZodBackend {
+    fn generate(
+        &self,
+        schema: &ir::Schema,
+        options: &GeneratorOptions,
+    ) -> Result<Generated, ZorshGenError> {
+        let mut parser = Parser::new(schema, options);
+        let output = parser.zod_schema()?;
+        Ok(Generated {
+            output,
+            warnings: parser.warnings(),
+        })
+    }
+}
+
+/// Generates with the backend `options` choose, like [`Backend::generate`], also
+/// returning the report, which only the built-in backends fill in beyond the number of
+/// declarations.
+pub(super) fn generate_reported(
+    schema: &ir::Schema,
+    options: &GeneratorOptions,
+) -> Result<(Generated, GenerationReport), ZorshGenError> {
+    if let Some(backend) = &options.backend {
+        let report = GenerationReport {
+            declarations: schema.types.len(),
+            ..GenerationReport::default()
+        };
+        return Ok((backend.generate(schema, options)?, report));
+    }
+    let mut parser = Parser::new(schema, options);
+    let output = match options.target {
+        Target::Zorsh => parser.parse()?,
+        #[cfg(feature = "backend-json-schema")]
+        Target::JsonSchema => parser.json_schema()?,
+        #[cfg(feature = "backend-zod")]
+        Target::Zod => parser.zod_schema()?,
+    };
+    let generated = Generated {
+        output,
+        warnings: parser.warnings(),
+    };
+    Ok((generated, parser.report()))
+}
+
+impl Target {
+    /// The built-in backend generating this target.
+    pub fn backend(self) -> &'static dyn Backend {
+        match self {
+            Target::Zorsh => &ZorshBackend,
+            #[cfg(feature = "backend-json-schema")]
+            Target::JsonSchema => &JsonSchemaBackend,
+            #[cfg(feature = "backend-zod")]
+            Target::Zod => &ZodBackend,
+        }
+    }
+}
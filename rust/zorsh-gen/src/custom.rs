@@ -0,0 +1,126 @@
+// src/custom.rs
+//! Hand-written code kept in generated files between `// zorsh:begin-custom` and
+//! `// zorsh:end-custom` lines, which is carried over when the files are regenerated.
+use std::collections::HashMap;
+
+const BEGIN: &str = "// zorsh:begin-custom";
+const END: &str = "// zorsh:end-custom";
+
+/// A custom region of an existing file.
+struct Region<'a> {
+    /// The last non-blank generated line before the region; `None` at the top of the file.
+    anchor: Option<&'a str>,
+    /// The region's lines, markers included.
+    lines: &'a [&'a str],
+    /// Whether a blank line follows the region.
+    blank_after: bool,
+}
+
+/// Returns `generated` with the custom regions of `existing` carried over. Each region
+/// goes after the same generated line it followed before, or at the end of the file if
+/// that line is gone or isn't unique.
+pub(super) fn carry_over(existing: &str, generated: &str) -> Result<String, String> {
+    let lines: Vec<&str> = existing.lines().collect();
+    let mut regions = Vec::new();
+    let mut old_generated = Vec::new();
+    let mut start = None;
+    for (index, line) in lines.iter().enumerate() {
+        let marker = line.trim_start();
+        match start {
+            None if marker.starts_with(BEGIN) => start = Some(index),
+            None if marker.starts_with(END) => {
+                return Err(format!(
+                    "line {}: `{}` without a `{}` before it",
+                    index + 1,
+                    END,
+                    BEGIN
+                ));
+            }
+            None => old_generated.push(*line),
+            Some(begin) if marker.starts_with(BEGIN) => {
+                return Err(format!(
+                    "line {}: custom regions can't be nested, but the one on line {} isn't closed",
+                    index + 1,
+                    begin + 1
+                ));
+            }
+            Some(begin) if marker.starts_with(END) => {
+                regions.push(Region {
+                    anchor: lines[..begin]
+                        .iter()
+                        .rev()
+                        .find(|line| !line.trim().is_empty())
+                        .copied(),
+                    lines: &lines[begin..=index],
+                    blank_after: lines.get(index + 1).is_none_or(|l| l.trim().is_empty()),
+                });
+                start = None;
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(begin) = start {
+        return Err(format!(
+            "line {}: `{}` is never closed by `{}`",
+            begin + 1,
+            BEGIN,
+            END
+        ));
+    }
+    if regions.is_empty() {
+        return Ok(generated.to_string());
+    }
+
+    let new_lines: Vec<&str> = generated.lines().collect();
+    let unique = |lines: &[&str], line: &str| lines.iter().filter(|&&l| l == line).count() == 1;
+    let mut top = Vec::new();
+    let mut anchored: HashMap<usize, Vec<&Region>> = HashMap::new();
+    let mut trailing = Vec::new();
+    for region in &regions {
+        match region.anchor {
+            None => top.push(region),
+            Some(anchor) if unique(&old_generated, anchor) && unique(&new_lines, anchor) => {
+                // Placed before the next non-blank line, keeping the blank lines after the anchor
+                let position = new_lines.iter().position(|&l| l == anchor).unwrap();
+                let next = new_lines[position + 1..]
+                    .iter()
+                    .position(|l| !l.trim().is_empty())
+                    .map_or(new_lines.len(), |offset| position + 1 + offset);
+                anchored.entry(next).or_default().push(region);
+            }
+            Some(_) => trailing.push(region),
+        }
+    }
+
+    let mut output: Vec<&str> = Vec::new();
+    for region in top {
+        push_region(&mut output, region);
+    }
+    for (index, line) in new_lines.iter().enumerate() {
+        for region in anchored.remove(&index).into_iter().flatten() {
+            push_region(&mut output, region);
+        }
+        output.push(line);
+    }
+    for region in anchored.remove(&new_lines.len()).into_iter().flatten() {
+        push_region(&mut output, region);
+    }
+    for region in trailing {
+        if output.last().is_some_and(|l| !l.trim().is_empty()) {
+            output.push("");
+        }
+        push_region(&mut output, region);
+    }
+    while output.last().is_some_and(|l| l.is_empty()) {
+        output.pop();
+    }
+    Ok(output.join("\n") + "\n")
+}
+
+/// Appends a region, and the blank line after it if it had one.
+fn push_region<'a>(output: &mut Vec<&'a str>, region: &Region<'a>) {
+    output.extend(region.lines);
+    if region.blank_after {
+        output.push("");
+    }
+}
@@ -0,0 +1,140 @@
+// src/diagnostics.rs
+//! The problems that stop generation, and the warnings about what it wrote placeholders
+//! for, with the declarations they concern and how the root type refers to them.
+use super::{ir, is_identifier, Parser, ZorshGenError};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// A problem with the schema container or the options, or, as a warning, with what was
+/// generated for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// The declaration the problem is with, if it is with one.
+    pub declaration: Option<String>,
+    /// The named types, fields and variants leading from the root type to `declaration`,
+    /// e.g. `["GameState", "players", "Player", "inventory", "Item"]`. Just the
+    /// declaration if the root doesn't refer to it.
+    pub references: Vec<String>,
+    /// Whether this is a placeholder that `strict` made an error. Without `strict`, the
+    /// output would have held a `TODO` comment instead.
+    pub placeholder: bool,
+}
+
+impl Diagnostic {
+    pub(super) fn new(declaration: Option<&str>, message: String) -> Self {
+        Self {
+            message,
+            declaration: declaration.map(str::to_string),
+            references: Vec::new(),
+            placeholder: false,
+        }
+    }
+}
+
+/// Every problem found, as held by [`ZorshGenError::Unresolved`] and
+/// [`ZorshGenError::Unsupported`]. Displayed as one message per line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<&str> = self.0.iter().map(|d| d.message.as_str()).collect();
+        f.write_str(&messages.join("\n"))
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+impl Parser<'_> {
+    /// Records a problem with `declaration`.
+    pub(super) fn error(&mut self, declaration: &str, message: String) {
+        self.errors
+            .push(Diagnostic::new(Some(declaration), message));
+    }
+
+    /// Records a warning about what was written for `declaration`, once.
+    pub(super) fn warn(&mut self, declaration: &str, message: String) {
+        if !self.warnings.iter().any(|d| d.message == message) {
+            self.warnings
+                .push(Diagnostic::new(Some(declaration), message));
+        }
+    }
+
+    /// Returns the warnings recorded, with their references.
+    pub(super) fn warnings(&self) -> Vec<Diagnostic> {
+        self.warnings.iter().map(|d| self.located(d)).collect()
+    }
+
+    /// Returns the error for the problems recorded.
+    pub(super) fn failure(&self) -> ZorshGenError {
+        let diagnostics = self.errors.iter().map(|d| self.located(d));
+        let diagnostics = Diagnostics(diagnostics.collect());
+        match diagnostics.0.iter().all(|d| d.placeholder) {
+            true => ZorshGenError::Unresolved(diagnostics),
+            false => ZorshGenError::Unsupported(diagnostics),
+        }
+    }
+
+    fn located(&self, diagnostic: &Diagnostic) -> Diagnostic {
+        Diagnostic {
+            references: match &diagnostic.declaration {
+                Some(declaration) => self.references(declaration),
+                None => Vec::new(),
+            },
+            ..diagnostic.clone()
+        }
+    }
+
+    /// Returns the shortest chain of references from the root to `declaration`, leaving
+    /// out unnamed types such as `Vec<Player>`.
+    fn references(&self, declaration: &str) -> Vec<String> {
+        let root = self.ir.root.as_str();
+        // Each declaration reached, with the one referring to it and the field or
+        // variant doing so
+        let mut parents: HashMap<&str, (&str, Option<&str>)> = HashMap::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(current) = queue.pop_front() {
+            if current == declaration {
+                break;
+            }
+            let Some(ty) = self.ty(current) else {
+                continue;
+            };
+            let names: Vec<Option<&str>> = match ty {
+                ir::Type::Struct(ir::Fields::Named(fields)) => fields
+                    .iter()
+                    .map(|field| Some(field.name.as_str()))
+                    .collect(),
+                ir::Type::Enum { variants, .. } => variants
+                    .iter()
+                    .map(|variant| Some(variant.name.as_str()))
+                    .collect(),
+                ir::Type::Option(_) => vec![Some("Some")],
+                _ => vec![None; ty.references().len()],
+            };
+            for (name, next) in names.into_iter().zip(ty.references()) {
+                if next != root && !parents.contains_key(next.as_str()) {
+                    parents.insert(next, (current, name));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut references = vec![declaration.to_string()];
+        let mut current = declaration;
+        while let Some(&(parent, name)) = parents.get(current) {
+            references.extend(name.map(str::to_string));
+            if is_identifier(parent) {
+                references.push(parent.to_string());
+            }
+            current = parent;
+        }
+        if current != root {
+            // Unreachable from the root
+            return vec![declaration.to_string()];
+        }
+        references.reverse();
+        references
+    }
+}
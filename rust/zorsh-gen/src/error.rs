@@ -0,0 +1,75 @@
+// src/error.rs
+//! [`ZorshGenError`], what generation fails with.
+use super::Diagnostics;
+use std::io;
+use std::path::PathBuf;
+
+/// Why generation failed. Kinds of failure may be added in minor versions.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ZorshGenError {
+    /// An input that isn't a borsh-serialized `BorshSchemaContainer`. Displayed without
+    /// `input`, which messages usually start with already.
+    #[error("not a borsh-serialized schema container: {source}")]
+    Schema {
+        input: String,
+        #[source]
+        source: io::Error,
+    },
+    /// Schema containers to be merged that define a type differently.
+    #[error("`{declaration}` is defined differently in `{first}` and `{second}`")]
+    Conflict {
+        declaration: String,
+        first: String,
+        second: String,
+    },
+    /// Declarations that `strict` made errors rather than placeholders: ones that nothing
+    /// defines, maps or resolves, primitives of sizes no zorsh builder reads, and type
+    /// aliases of missing types.
+    #[error("{0}")]
+    Unresolved(Diagnostics),
+    /// Declarations that can't be generated at all, such as sequences of unsupported
+    /// length widths or names that aren't TypeScript identifiers, along with any
+    /// unresolved ones found too.
+    #[error("{0}")]
+    Unsupported(Diagnostics),
+    /// Options that don't go together, or don't go with the output asked for, such as
+    /// source maps written to standard output.
+    #[error("{0}")]
+    Options(String),
+    /// An existing output file whose custom regions can't be carried over.
+    #[error("{}: {message}", path.display())]
+    CustomRegions { path: PathBuf, message: String },
+    /// Reading inputs or writing outputs.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl ZorshGenError {
+    pub(super) fn options(message: impl Into<String>) -> Self {
+        ZorshGenError::Options(message.into())
+    }
+
+    /// The problems with declarations, for [`ZorshGenError::Unresolved`] and
+    /// [`ZorshGenError::Unsupported`].
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        match self {
+            ZorshGenError::Unresolved(diagnostics) | ZorshGenError::Unsupported(diagnostics) => {
+                Some(diagnostics)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// For callers returning `io::Result`, such as build scripts: I/O errors as they were,
+/// options as `InvalidInput`, and the rest as `InvalidData`.
+impl From<ZorshGenError> for io::Error {
+    fn from(error: ZorshGenError) -> Self {
+        match error {
+            ZorshGenError::Io(error) => error,
+            ZorshGenError::Options(_) => io::Error::new(io::ErrorKind::InvalidInput, error),
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
@@ -0,0 +1,370 @@
+// src/files.rs
+//! Output split into one file per exported type, or per Rust module, with an `index.ts`
+//! barrel.
+use super::{
+    commonjs_module, format, import_statement, module_alias, unraw, Chunk, ExportStyle,
+    ModuleFormat, OutputMode, Parser, ZorshGenError,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Where a name used by a generated file is defined.
+enum Origin<'a> {
+    /// Another generated file, by stem; `true` for type-only names.
+    Sibling(&'a str, bool),
+    /// A module imported by a type mapping.
+    Mapping(&'a str),
+    Helpers,
+}
+
+impl Parser<'_> {
+    /// Generates one file per exported type, or per Rust module with `module_files`,
+    /// `helpers.ts` if any runtime helpers are needed, an `index.ts` re-exporting every
+    /// type and, if enabled, `zorsh-map.json`, keyed by file name.
+    pub(super) fn parse_files(&mut self) -> Result<BTreeMap<String, String>, ZorshGenError> {
+        if let Some(namespace) = &self.options.namespace {
+            // Namespaces only merge across files in scripts, not modules
+            return Err(ZorshGenError::options(format!(
+                "namespace `{}` can't span one file per type; use a prefix instead",
+                namespace
+            )));
+        }
+        if self.options.export_style == ExportStyle::Object {
+            return Err(ZorshGenError::options(
+                "the object export style can't span one file per type",
+            ));
+        }
+        self.run()?;
+        let declarations = self.options.output_mode == OutputMode::Declarations;
+        let extension = if declarations { "d.ts" } else { "ts" };
+        // Declaration files only import types, which needs no CommonJS syntax
+        let format = match declarations {
+            true => ModuleFormat::Esm,
+            false => self.options.module_format,
+        };
+
+        // File stems with the chunks going in each, in the order of their first chunk
+        let mut stems: Vec<(String, Vec<&Chunk>)> = Vec::new();
+        for chunk in &self.chunks {
+            let stem = self.chunk_file(chunk);
+            match stems.iter_mut().find(|(existing, _)| *existing == stem) {
+                Some((_, chunks)) => chunks.push(chunk),
+                None => stems.push((stem, vec![chunk])),
+            }
+        }
+        let reserved = |stem: &str| stem == "index" || stem == "helpers";
+        if let Some((stem, _)) = stems
+            .iter()
+            .find(|(stem, _)| self.options.module_files && reserved(stem))
+        {
+            return Err(ZorshGenError::options(format!(
+                "module `{0}` would overwrite the generated `{0}.ts`",
+                stem
+            )));
+        }
+
+        let mut origins = HashMap::new();
+        for (stem, chunks) in &stems {
+            for chunk in chunks {
+                origins.insert(chunk.name.as_str(), Origin::Sibling(stem, true));
+                for export in &chunk.exports {
+                    origins.insert(export.as_str(), Origin::Sibling(stem, false));
+                }
+            }
+        }
+        for (module, names) in &self.imports {
+            for name in names {
+                origins.insert(name.as_str(), Origin::Mapping(module));
+            }
+        }
+        let helpers: Vec<&str> = self.helpers.iter().flat_map(|h| helper_names(h)).collect();
+        for helper in &helpers {
+            origins.insert(helper, Origin::Helpers);
+        }
+
+        let mut files = BTreeMap::new();
+        let mut value_imports: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (stem, chunks) in &stems {
+            let text: String = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+            let mut siblings: BTreeMap<&str, (BTreeSet<&str>, BTreeSet<&str>)> = BTreeMap::new();
+            let mut mappings: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+            let mut used_helpers = BTreeSet::new();
+            for token in code_tokens(&text) {
+                match origins.get(token) {
+                    Some(Origin::Sibling(file, _)) if file == stem => {}
+                    Some(Origin::Sibling(file, true)) => {
+                        siblings.entry(*file).or_default().1.insert(token);
+                    }
+                    Some(Origin::Sibling(file, false)) => {
+                        siblings.entry(*file).or_default().0.insert(token);
+                        value_imports.entry(stem).or_default().insert(*file);
+                    }
+                    Some(Origin::Mapping(module)) => {
+                        mappings.entry(*module).or_default().insert(token);
+                    }
+                    Some(Origin::Helpers) => {
+                        used_helpers.insert(token);
+                    }
+                    None => {}
+                }
+            }
+
+            let mut file = String::new();
+            if !declarations {
+                let runtime = &self.options.runtime;
+                file.push_str(&import_statement(
+                    format,
+                    &[(runtime.binding.as_str(), "b")],
+                    &[],
+                    &runtime.module,
+                ));
+            }
+            for (module, names) in &mappings {
+                let names: Vec<(&str, &str)> = names.iter().map(|&n| (n, n)).collect();
+                file.push_str(&import_statement(format, &names, &[], module));
+            }
+            for (sibling, (values, types)) in &siblings {
+                let values: Vec<(&str, &str)> = values.iter().map(|&n| (n, n)).collect();
+                let types: Vec<&str> = types.iter().copied().collect();
+                let module = relative_module(stem, sibling);
+                file.push_str(&import_statement(format, &values, &types, &module));
+            }
+            if !used_helpers.is_empty() {
+                let names: Vec<(&str, &str)> = used_helpers.iter().map(|&n| (n, n)).collect();
+                let module = relative_module(stem, "helpers");
+                file.push_str(&import_statement(format, &names, &[], &module));
+            }
+            if !file.is_empty() {
+                file.push('\n');
+            }
+            file.push_str(&match format {
+                ModuleFormat::Esm => text,
+                ModuleFormat::CommonJs => commonjs_module(&text),
+            });
+            files.insert(format!("{}.{}", stem, extension), file);
+        }
+        if let Some(cycle) = import_cycle(&value_imports) {
+            // Types are acyclic, but the modules grouping them need not be
+            return Err(ZorshGenError::options(format!(
+                "generated files would import each other's schemas in a cycle ({}), which modules can't initialize; group these types differently",
+                cycle.join(" -> ")
+            )));
+        }
+
+        if !helpers.is_empty() && !declarations {
+            let types: &[&str] = if self.helpers.iter().any(|h| h.contains("Schema<")) {
+                &["Schema"]
+            } else {
+                &[]
+            };
+            let runtime = &self.options.runtime;
+            let builders = [(runtime.binding.as_str(), "b")];
+            let values: &[(&str, &str)] = match self.helpers.iter().any(|h| h.contains(" b.")) {
+                true => &builders,
+                false => &[],
+            };
+            let imports = import_statement(format, values, types, &runtime.module);
+            let mut body = String::new();
+            for helper in &self.helpers {
+                // Every helper is a series of functions separated by blank lines
+                body.push_str(&format!(
+                    "export {}",
+                    helper.replace("\n\nfunction ", "\n\nexport function ")
+                ));
+            }
+            let mut file = format!("{}\n", imports);
+            file.push_str(&match format {
+                ModuleFormat::Esm => body,
+                ModuleFormat::CommonJs => commonjs_module(&body),
+            });
+            files.insert("helpers.ts".to_string(), file);
+        }
+
+        let registry = self.options.schema_registry && !declarations;
+        let hash = match self.options.hash_constant && !declarations {
+            true => Some(self.hash_constant()),
+            false => None,
+        };
+        let index = match format {
+            ModuleFormat::Esm => self.esm_index(&stems, hash.as_ref(), registry),
+            ModuleFormat::CommonJs => self.commonjs_index(&stems, hash.as_ref(), registry),
+        };
+        files.insert(format!("index.{}", extension), index);
+        if self.options.roundtrip_spec {
+            files.insert(
+                "schemas.test.ts".to_string(),
+                self.roundtrip_spec("./index"),
+            );
+        }
+        let banner = self.banner();
+        for file in files.values_mut() {
+            *file = format::format(&(banner.clone() + file), &self.options.format);
+        }
+        if self.options.source_map {
+            let map = self.source_map(&files);
+            files.insert("zorsh-map.json".to_string(), map);
+        }
+        Ok(files)
+    }
+
+    /// Returns the stem of the file a chunk goes in, relative to the output directory.
+    fn chunk_file(&self, chunk: &Chunk) -> String {
+        if !self.options.module_files {
+            return chunk.name.clone();
+        }
+        match self.options.source.modules.get(&chunk.declaration) {
+            Some(path) => path.iter().map(|m| unraw(m)).collect::<Vec<_>>().join("/"),
+            None => "lib".to_string(),
+        }
+    }
+
+    /// Returns an `index.ts` re-exporting every file, followed by the hash constant and
+    /// the schema registry if they are enabled.
+    fn esm_index(
+        &self,
+        stems: &[(String, Vec<&Chunk>)],
+        hash: Option<&(String, String)>,
+        registry: bool,
+    ) -> String {
+        let mut index = String::new();
+        if registry {
+            index.push_str(&import_statement(
+                ModuleFormat::Esm,
+                &[],
+                &["Schema"],
+                &self.options.runtime.module,
+            ));
+            for (stem, chunks) in stems {
+                let schema_names: Vec<String> = chunks
+                    .iter()
+                    .map(|chunk| self.options.schema_naming.apply(&chunk.name))
+                    .collect();
+                let names: Vec<(&str, &str)> = schema_names
+                    .iter()
+                    .map(|n| (n.as_str(), n.as_str()))
+                    .collect();
+                let module = format!("./{}", stem);
+                index.push_str(&import_statement(ModuleFormat::Esm, &names, &[], &module));
+            }
+            index.push('\n');
+        }
+        for (stem, _) in stems {
+            index.push_str(&format!("export * from \"./{}\";\n", stem));
+        }
+        if let Some((_, hash)) = hash {
+            index.push('\n');
+            index.push_str(hash);
+        }
+        if registry {
+            index.push('\n');
+            index.push_str(&self.schema_registry());
+        }
+        index
+    }
+
+    /// Returns the CommonJS counterpart of `esm_index`, which re-exports each file's
+    /// values and types by name, as `export =` can't sit next to `export *`.
+    fn commonjs_index(
+        &self,
+        stems: &[(String, Vec<&Chunk>)],
+        hash: Option<&(String, String)>,
+        registry: bool,
+    ) -> String {
+        let mut index = String::new();
+        if registry {
+            index.push_str(&import_statement(
+                ModuleFormat::CommonJs,
+                &[],
+                &["Schema"],
+                &self.options.runtime.module,
+            ));
+        }
+        let mut body = String::new();
+        for (stem, chunks) in stems {
+            let values = chunks.iter().flat_map(|chunk| &chunk.exports);
+            let types = chunks.iter().flat_map(|chunk| &chunk.types);
+            let module = format!("./{}", stem);
+            let alias = module_alias(
+                &module,
+                values.clone().chain(types.clone()).map(String::as_str),
+            );
+            index.push_str(&format!("import {} = require(\"{}\");\n", alias, module));
+            for value in values {
+                body.push_str(&format!("export const {0} = {1}.{0};\n", value, alias));
+            }
+            for ty in types {
+                body.push_str(&format!("export type {0} = {1}.{0};\n", ty, alias));
+            }
+        }
+        if let Some((_, hash)) = hash {
+            body.push('\n');
+            body.push_str(hash);
+        }
+        if registry {
+            body.push('\n');
+            body.push_str(&self.schema_registry());
+        }
+        format!("{}\n{}", index, commonjs_module(&body))
+    }
+}
+
+/// Returns the specifier importing file stem `to` from file stem `from`.
+fn relative_module(from: &str, to: &str) -> String {
+    match from.matches('/').count() {
+        0 => format!("./{}", to),
+        depth => format!("{}{}", "../".repeat(depth), to),
+    }
+}
+
+/// Returns a cycle in `imports`, a graph of file stems, starting and ending at the
+/// same file.
+fn import_cycle<'a>(imports: &BTreeMap<&'a str, BTreeSet<&'a str>>) -> Option<Vec<&'a str>> {
+    fn visit<'a>(
+        file: &'a str,
+        imports: &BTreeMap<&'a str, BTreeSet<&'a str>>,
+        path: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if let Some(start) = path.iter().position(|&f| f == file) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(file);
+            return Some(cycle);
+        }
+        if !done.insert(file) {
+            return None;
+        }
+        path.push(file);
+        for next in imports.get(file).into_iter().flatten() {
+            if let Some(cycle) = visit(next, imports, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+
+    let mut done = BTreeSet::new();
+    imports
+        .keys()
+        .find_map(|file| visit(file, imports, &mut Vec::new(), &mut done))
+}
+
+/// Returns the names of the functions a runtime helper defines.
+fn helper_names(helper: &str) -> Vec<&str> {
+    helper
+        .lines()
+        .filter_map(|line| line.strip_prefix("function "))
+        .filter_map(|line| line.split(['<', '(']).next())
+        .collect()
+}
+
+/// Returns the identifiers in `code`, skipping comments.
+fn code_tokens(code: &str) -> impl Iterator<Item = &str> {
+    code.lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !(line.starts_with("//") || line.starts_with("/*") || line.starts_with('*'))
+        })
+        .map(|line| line.split(" //").next().unwrap_or(line))
+        .flat_map(|line| line.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$')))
+        .filter(|token| !token.is_empty())
+}
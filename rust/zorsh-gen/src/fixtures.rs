@@ -0,0 +1,423 @@
+// src/fixtures.rs
+//! The cross-language test corpus, behind the `fixtures` feature: borsh payloads of
+//! primitives, floats, collections, enums and edge cases, each with the schema container
+//! of its type, for checking other implementations such as zorsh against the Rust one.
+//!
+//! The sample game types the corpus serializes are defined here too.
+use borsh::schema::BorshSchemaContainer;
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The file listing the fixtures written to a directory.
+pub const MANIFEST: &str = "manifest.json";
+
+/// A serialized value and the schema of its type.
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    /// Names the fixture's files, e.g. `u64_max` for `u64_max.bin`.
+    pub name: String,
+    pub container: BorshSchemaContainer,
+    pub payload: Vec<u8>,
+}
+
+impl Fixture {
+    fn new<T: BorshSerialize + BorshSchema>(name: &str, value: &T) -> Self {
+        Self {
+            name: name.to_string(),
+            container: BorshSchemaContainer::for_type::<T>(),
+            payload: borsh::to_vec(value).expect("fixtures always serialize"),
+        }
+    }
+
+    /// The file the payload is written to.
+    pub fn payload_file(&self) -> String {
+        format!("{}.bin", self.name)
+    }
+
+    /// The file the schema container is written to.
+    pub fn schema_file(&self) -> String {
+        format!("{}.schema.bin", self.name)
+    }
+}
+
+/// An entry of the manifest.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    name: String,
+    declaration: String,
+    payload: String,
+    schema: String,
+}
+
+/// Returns every fixture, in a fixed order.
+pub fn corpus() -> Vec<Fixture> {
+    let mut fixtures = vec![
+        // Primitives at their limits
+        Fixture::new("bool_false", &false),
+        Fixture::new("bool_true", &true),
+        Fixture::new("u8_max", &u8::MAX),
+        Fixture::new("u16_max", &u16::MAX),
+        Fixture::new("u32_max", &u32::MAX),
+        Fixture::new("u64_max", &u64::MAX),
+        Fixture::new("u128_max", &u128::MAX),
+        Fixture::new("i8_min", &i8::MIN),
+        Fixture::new("i16_min", &i16::MIN),
+        Fixture::new("i32_min", &i32::MIN),
+        Fixture::new("i64_min", &i64::MIN),
+        Fixture::new("i128_min", &i128::MIN),
+        Fixture::new("i64_neg_one", &-1i64),
+        // Integers past `Number.MAX_SAFE_INTEGER`
+        Fixture::new("u64_unsafe_integer", &((1u64 << 53) + 1)),
+    ];
+
+    // Floats; `NaN` isn't serializable
+    let floats = [
+        ("infinity", f64::INFINITY),
+        ("neg_infinity", f64::NEG_INFINITY),
+        ("max", f64::MAX),
+        ("min", f64::MIN),
+        // Rust's `MIN_POSITIVE` is JavaScript's `Number.MIN_VALUE`
+        ("min_value", f64::MIN_POSITIVE),
+        ("zero", 0.0),
+        ("neg_zero", -0.0),
+        ("one", 1.0),
+        ("neg_one", -1.0),
+        ("pi", std::f64::consts::PI),
+    ];
+    for (name, f64_value) in floats {
+        let name = format!("float_{}", name);
+        fixtures.push(Fixture::new(&name, &FloatTestCase { f64_value }));
+    }
+    fixtures.extend([
+        Fixture::new("f32_subnormal", &f32::from_bits(1)),
+        Fixture::new("f32_neg_infinity", &f32::NEG_INFINITY),
+    ]);
+
+    // Collections
+    fixtures.extend([
+        Fixture::new("string_empty", &String::new()),
+        Fixture::new("string_unicode", &"zorsh ✓ борш 🦀".to_string()),
+        Fixture::new("bytes", &vec![0u8, 1, 127, 128, 255]),
+        Fixture::new("vec_empty", &Vec::<u32>::new()),
+        Fixture::new("vec_u32", &vec![1u32, 2, 3]),
+        Fixture::new(
+            "vec_nested",
+            &vec![vec![], vec![vec![1u8]], vec![vec![2, 3], vec![]]],
+        ),
+        Fixture::new("array_u16", &[1u16, 2, 3]),
+        Fixture::new("array_empty", &[0u8; 0]),
+        Fixture::new("tuple", &(7u8, "seven".to_string(), true)),
+        Fixture::new("option_some", &Some(42u32)),
+        Fixture::new("option_none", &None::<u32>),
+        Fixture::new("option_nested", &Some(None::<u8>)),
+        Fixture::new(
+            "hash_map",
+            &HashMap::from([("b".to_string(), 2u32), ("a".to_string(), 1)]),
+        ),
+        Fixture::new("hash_set", &HashSet::from([3u8, 1, 2])),
+        Fixture::new(
+            "btree_map",
+            &BTreeMap::from([(2u16, vec!["two".to_string()]), (1, Vec::new())]),
+        ),
+        Fixture::new("btree_set", &BTreeSet::from([-1i32, 0, 1])),
+        Fixture::new("vec_large", &(0..1000u32).collect::<Vec<_>>()),
+    ]);
+
+    // Enums
+    fixtures.extend([
+        Fixture::new("effect_damage", &Effect::Damage(15)),
+        Fixture::new("effect_heal", &Effect::Heal(50)),
+        Fixture::new(
+            "effect_buff",
+            &Effect::Buff(StatBuff {
+                stat: "mana".to_string(),
+                amount: 10,
+            }),
+        ),
+        Fixture::new(
+            "effect_debuff",
+            &Effect::Debuff(StatBuff {
+                stat: "attack".to_string(),
+                amount: -5,
+            }),
+        ),
+        Fixture::new(
+            "event_item_used_none",
+            &GameEvent::ItemUsed {
+                item_id: "bomb_001".to_string(),
+                target: None,
+            },
+        ),
+        Fixture::new(
+            "event_item_used_some",
+            &GameEvent::ItemUsed {
+                item_id: "potion_001".to_string(),
+                target: Some("bob_id".to_string()),
+            },
+        ),
+        Fixture::new(
+            "event_player_left",
+            &GameEvent::PlayerLeft {
+                player_id: "bob_id".to_string(),
+            },
+        ),
+    ]);
+
+    fixtures.push(Fixture::new("complex_game_state", &game_state()));
+    fixtures
+}
+
+/// Writes each fixture's payload and schema container to `directory`, with a manifest
+/// listing them, and returns the paths written. `progress` is told how many fixtures are
+/// written after each one.
+pub fn write(
+    directory: &Path,
+    fixtures: &[Fixture],
+    mut progress: impl FnMut(usize, &Fixture),
+) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(directory)?;
+    let mut written = Vec::new();
+    let mut entries = Vec::new();
+    for (index, fixture) in fixtures.iter().enumerate() {
+        let payload = directory.join(fixture.payload_file());
+        std::fs::write(&payload, &fixture.payload)?;
+        let schema = directory.join(fixture.schema_file());
+        std::fs::write(&schema, borsh::to_vec(&fixture.container)?)?;
+        written.extend([payload, schema]);
+        entries.push(Entry {
+            name: fixture.name.clone(),
+            declaration: fixture.container.declaration().clone(),
+            payload: fixture.payload_file(),
+            schema: fixture.schema_file(),
+        });
+        progress(index + 1, fixture);
+    }
+    let manifest = directory.join(MANIFEST);
+    let json = serde_json::to_string_pretty(&entries).expect("a manifest always serializes");
+    std::fs::write(&manifest, json + "\n")?;
+    written.push(manifest);
+    Ok(written)
+}
+
+/// Reads the fixtures [`write`] wrote to `directory`, in the order of its manifest.
+pub fn read(directory: &Path) -> io::Result<Vec<Fixture>> {
+    let manifest = std::fs::read_to_string(directory.join(MANIFEST))?;
+    let entries: Vec<Entry> = serde_json::from_str(&manifest)?;
+    let mut fixtures = Vec::new();
+    for entry in entries {
+        let schema = std::fs::read(directory.join(&entry.schema))?;
+        fixtures.push(Fixture {
+            name: entry.name,
+            container: BorshSchemaContainer::try_from_slice(&schema)?,
+            payload: std::fs::read(directory.join(&entry.payload))?,
+        });
+    }
+    Ok(fixtures)
+}
+
+/// The `GameState` of the `complex_game_state` fixture.
+pub fn game_state() -> GameState {
+    let alice = Player {
+        name: "Alice".to_string(),
+        level: 10,
+        stats: Stats {
+            health: 100,
+            mana: 50,
+            attack: 20,
+            defense: 15,
+            magic_attack: 5,
+            magic_defense: 10,
+        },
+        inventory: vec![
+            Item {
+                id: "sword_001".to_string(),
+                name: "Iron Sword".to_string(),
+                weight: 2.5,
+                effects: vec![Effect::Damage(15)],
+            },
+            Item {
+                id: "potion_001".to_string(),
+                name: "Health Potion".to_string(),
+                weight: 0.125,
+                effects: vec![Effect::Heal(50)],
+            },
+        ],
+        equipped_items: HashMap::from([("hand".to_string(), "sword_001".to_string())]),
+        quest_log: HashSet::from(["quest_start".to_string(), "collect_herbs".to_string()]),
+        last_login: Some(1678886400), // Example timestamp
+    };
+
+    let bob = Player {
+        name: "Bob".to_string(),
+        level: 5,
+        stats: Stats {
+            health: 80,
+            mana: 75,
+            attack: 10,
+            defense: 5,
+            magic_attack: 30,
+            magic_defense: 20,
+        },
+        inventory: vec![Item {
+            id: "staff_001".to_string(),
+            name: "Apprentice Staff".to_string(),
+            weight: 1.25,
+            effects: vec![
+                Effect::Damage(5),
+                Effect::Buff(StatBuff {
+                    stat: "magic_attack".to_string(),
+                    amount: 10,
+                }),
+            ],
+        }],
+        equipped_items: HashMap::new(),
+        quest_log: HashSet::new(),
+        last_login: None,
+    };
+
+    let events = vec![
+        GameEvent::PlayerJoined {
+            player_id: "alice_id".to_string(),
+        },
+        GameEvent::ChatMessage {
+            sender: "alice_id".to_string(),
+            message: "Hello, world!".to_string(),
+        },
+        GameEvent::BattleResult {
+            winner: "alice_id".to_string(),
+            loser: "bob_id".to_string(),
+            rewards: vec![Reward {
+                item_id: "gold_001".to_string(),
+                quantity: 100,
+            }],
+        },
+    ];
+
+    GameState {
+        players: HashMap::from([("alice_id".to_string(), alice), ("bob_id".to_string(), bob)]),
+        current_round: 3,
+        events,
+        game_version: "1.2.3".to_string(),
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub enum GameEvent {
+    PlayerJoined {
+        player_id: String,
+    },
+    PlayerLeft {
+        player_id: String,
+    },
+    ChatMessage {
+        sender: String,
+        message: String,
+    },
+    ItemUsed {
+        item_id: String,
+        target: Option<String>,
+    }, // Option for single-target or area-effect
+    BattleResult {
+        winner: String,
+        loser: String,
+        rewards: Vec<Reward>, // Nested struct
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub struct Reward {
+    item_id: String,
+    quantity: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub struct Player {
+    pub name: String,
+    pub level: u8,
+    pub stats: Stats,                            // Nested struct
+    pub inventory: Vec<Item>,                    // Vec of nested structs
+    pub equipped_items: HashMap<String, String>, // Slot -> Item ID
+    pub quest_log: HashSet<String>,              // Set of quest IDs
+    pub last_login: Option<u64>,                 // Optional timestamp
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub struct Stats {
+    pub health: u32,
+    pub mana: u32,
+    pub attack: u32,
+    pub defense: u32,
+    pub magic_attack: u32,
+    pub magic_defense: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub struct Item {
+    pub id: String,
+    pub name: String,
+    pub weight: f32,
+    pub effects: Vec<Effect>, // Vec of nested enum
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub enum Effect {
+    Damage(u32),
+    Heal(u32),
+    Buff(StatBuff),   // Nested struct
+    Debuff(StatBuff), // Nested struct
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub struct StatBuff {
+    pub stat: String, // Could be an enum, but string for simplicity
+    pub amount: i32,  // Can be positive or negative
+}
+
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Debug)]
+pub struct GameState {
+    pub players: HashMap<String, Player>, // Map of player ID to Player struct
+    pub current_round: u32,
+    pub events: Vec<GameEvent>, // Vec of enum
+    pub game_version: String,
+}
+
+/// A struct holding an `f64`, as the float fixtures do.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, Debug)]
+pub struct FloatTestCase {
+    pub f64_value: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_manifest() {
+        let directory = std::env::temp_dir().join(format!("zorsh_fixtures_{}", std::process::id()));
+        let fixtures = &corpus()[..2];
+        let mut done = Vec::new();
+        let written = write(&directory, fixtures, |count, _| done.push(count)).unwrap();
+        assert_eq!(written.len(), 5);
+        assert_eq!(done, [1, 2]);
+        assert_eq!(std::fs::read(&written[0]).unwrap(), [0]);
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(directory.join(MANIFEST)).unwrap())
+                .unwrap();
+        assert_eq!(
+            manifest[1],
+            serde_json::json!({
+                "name": "bool_true",
+                "declaration": "bool",
+                "payload": "bool_true.bin",
+                "schema": "bool_true.schema.bin",
+            })
+        );
+        let read = read(&directory).unwrap();
+        assert_eq!(read[1].payload, fixtures[1].payload);
+        assert_eq!(read[1].container, fixtures[1].container);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
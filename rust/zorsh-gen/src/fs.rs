@@ -0,0 +1,393 @@
+// src/fs.rs
+//! Reading schema containers from files and writing generated schemas to them, behind
+//! the default `fs` feature. Everything else generates in memory, so the generator builds
+//! without it for targets with no filesystem, such as wasm.
+use super::{
+    custom, merge, render_files, render_schema, GenerationReport, GeneratorOptions, Target,
+    ZorshGenError, STDIO,
+};
+use borsh::schema::BorshSchemaContainer;
+use borsh::BorshDeserialize;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+/// Reads a borsh-serialized `BorshSchemaContainer` and writes the zorsh schema for it.
+/// An input path of `-` reads standard input, and an output path of `-` writes to
+/// standard output.
+///
+/// Lines between `// zorsh:begin-custom` and `// zorsh:end-custom` in an existing output
+/// file are kept, after the same generated line as before.
+///
+/// Returns a [`GenerationReport`], as every function writing schemas does.
+pub fn generate_zorsh_schema(
+    input_path: &str,
+    output_path: &str,
+) -> Result<GenerationReport, ZorshGenError> {
+    generate_zorsh_schema_with_options(input_path, output_path, &GeneratorOptions::default())
+}
+
+/// Like [`generate_zorsh_schema`], with explicit generator options.
+pub fn generate_zorsh_schema_with_options(
+    input_path: &str,
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    write_schema(&[input_path], output_path, options)
+}
+
+/// Like [`generate_zorsh_schema_with_options`], but for the types of several schema
+/// containers, such as one per contract, in one file. Types defined in more than one
+/// container must be defined the same way in each. The root of every container but
+/// the first is only exported if it is a struct or enum.
+pub fn generate_merged_zorsh_schema(
+    input_paths: &[&str],
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    write_schema(input_paths, output_path, options)
+}
+
+/// Like [`generate_zorsh_schema_with_options`], for a schema container built in memory,
+/// such as with `BorshSchemaContainer::for_type::<T>()`, rather than read from a file.
+pub fn generate_zorsh_schema_from_container(
+    container: &BorshSchemaContainer,
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    let rendered = render_schema(container, output_path, options)?;
+    write_schema_files(rendered, output_path)
+}
+
+fn write_schema(
+    input_paths: &[&str],
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    let rendered = rendered_schema(input_paths, output_path, options)?;
+    write_schema_files(rendered, output_path)
+}
+
+fn write_schema_files(
+    (files, report): (Vec<(std::path::PathBuf, String)>, GenerationReport),
+    output_path: &str,
+) -> Result<GenerationReport, ZorshGenError> {
+    if output_path == STDIO {
+        io::stdout().lock().write_all(files[0].1.as_bytes())?;
+    } else {
+        write_all_if_changed(&files)?;
+    }
+    Ok(report)
+}
+
+/// Writes one module with the schemas of several root types and the types they refer to,
+/// each exported once, as [`merge_containers`](super::merge_containers) merges them:
+///
+/// ```no_run
+/// use borsh_test::{GameEvent, GameState, Player};
+/// use zorsh_gen::zorsh_export;
+///
+/// zorsh_export!(Player, GameState, GameEvent => "frontend/src/generated.ts")?;
+/// # Ok::<(), zorsh_gen::ZorshGenError>(())
+/// ```
+///
+/// Options may follow the output path, e.g. `zorsh_export!(Player => "player.ts", &options)`.
+/// Evaluates to a `Result<GenerationReport, ZorshGenError>`.
+#[macro_export]
+macro_rules! zorsh_export {
+    ($($type:ty),+ $(,)? => $output:expr) => {
+        $crate::zorsh_export!(
+            $($type),+ => $output, &$crate::GeneratorOptions::default()
+        )
+    };
+    ($($type:ty),+ $(,)? => $output:expr, $options:expr $(,)?) => {
+        $crate::merge_containers(&[
+            $($crate::__private::BorshSchemaContainer::for_type::<$type>()),+
+        ])
+        .and_then(|container| {
+            $crate::generate_zorsh_schema_from_container(&container, $output, $options)
+        })
+    };
+}
+
+/// Writes the schemas of every type registered with `#[derive(ZorshExport)]` to one file,
+/// without keeping a list of them: `zorsh_gen::generate_all("frontend/src/generated.ts")`.
+#[cfg(feature = "source")]
+pub fn generate_all(output_path: &str) -> Result<GenerationReport, ZorshGenError> {
+    generate_all_with_options(output_path, &GeneratorOptions::default())
+}
+
+/// Like [`generate_all`], with explicit generator options.
+#[cfg(feature = "source")]
+pub fn generate_all_with_options(
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    generate_zorsh_schema_from_container(&super::registered_container()?, output_path, options)
+}
+
+/// Returns the files [`generate_merged_zorsh_schema`] would write, with their contents,
+/// without writing anything.
+pub fn render_zorsh_schema(
+    input_paths: &[&str],
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<Vec<(std::path::PathBuf, String)>, ZorshGenError> {
+    rendered_schema(input_paths, output_path, options).map(|(files, _)| files)
+}
+
+fn rendered_schema(
+    input_paths: &[&str],
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<(Vec<(std::path::PathBuf, String)>, GenerationReport), ZorshGenError> {
+    if output_path == STDIO && (options.source_map || options.roundtrip_spec) {
+        return Err(ZorshGenError::options("source maps and roundtrip specs are written next to the output file, so they need an output path rather than `-`"));
+    }
+    let container = read_containers(input_paths)?;
+    let options = with_source(options, input_paths);
+    render_schema(&container, output_path, &options)
+}
+
+/// Like [`generate_zorsh_schema_with_options`], but writes one file per exported type
+/// into `output_dir`, along with an `index.ts` that re-exports all of them.
+pub fn generate_zorsh_files(
+    input_path: &str,
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    write_files(&[input_path], output_dir, options)
+}
+
+/// Like [`generate_zorsh_files`], for several schema containers as
+/// [`generate_merged_zorsh_schema`] merges them.
+pub fn generate_merged_zorsh_files(
+    input_paths: &[&str],
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    write_files(input_paths, output_dir, options)
+}
+
+/// Like [`generate_zorsh_files`], for a schema container built in memory.
+pub fn generate_zorsh_files_from_container(
+    container: &BorshSchemaContainer,
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    let (files, report) = render_files(container, output_dir, options)?;
+    write_rendered(&files)?;
+    Ok(report)
+}
+
+fn write_files(
+    input_paths: &[&str],
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<GenerationReport, ZorshGenError> {
+    let (files, report) = rendered_files(input_paths, output_dir, options)?;
+    write_rendered(&files)?;
+    Ok(report)
+}
+
+/// Writes the files [`render_zorsh_schema`] or [`render_zorsh_files`] returned, creating
+/// the directories they go in, and leaving those already holding their contents alone.
+/// Returns how many files were written.
+pub fn write_rendered(files: &[(std::path::PathBuf, String)]) -> Result<usize, ZorshGenError> {
+    for (path, _) in files {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(write_all_if_changed(files)?)
+}
+
+/// Returns the files [`generate_merged_zorsh_files`] would write, with their paths under
+/// `output_dir` and their contents, without writing anything.
+pub fn render_zorsh_files(
+    input_paths: &[&str],
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<Vec<(std::path::PathBuf, String)>, ZorshGenError> {
+    rendered_files(input_paths, output_dir, options).map(|(files, _)| files)
+}
+
+fn rendered_files(
+    input_paths: &[&str],
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<(Vec<(std::path::PathBuf, String)>, GenerationReport), ZorshGenError> {
+    if output_dir == STDIO {
+        return Err(ZorshGenError::options(
+            "one file per type needs an output directory rather than `-`",
+        ));
+    }
+    if options.backend.is_some() || options.target != Target::Zorsh {
+        return Err(ZorshGenError::options(
+            "one file per type is only written for zorsh schemas; other backends write one document",
+        ));
+    }
+    let container = read_containers(input_paths)?;
+    let options = with_source(options, input_paths);
+    render_files(&container, output_dir, &options)
+}
+
+/// Reads borsh-serialized `BorshSchemaContainer`s, from standard input for `-`, and merges
+/// them into one, rooted at the first one's root.
+pub(super) fn read_containers(input_paths: &[&str]) -> Result<BorshSchemaContainer, ZorshGenError> {
+    let mut containers = Vec::new();
+    for &input_path in input_paths {
+        let mut bytes = Vec::new();
+        match input_path {
+            STDIO => io::stdin().lock().read_to_end(&mut bytes)?,
+            path => File::open(path)?.read_to_end(&mut bytes)?,
+        };
+        let container = BorshSchemaContainer::try_from_slice(&bytes).map_err(|source| {
+            ZorshGenError::Schema {
+                input: input_path.to_string(),
+                source,
+            }
+        })?;
+        containers.push((input_path.to_string(), container));
+    }
+    merge(containers)
+}
+
+/// Returns `generated` with the custom regions of the file at `path`, if there is one,
+/// carried over.
+pub(super) fn with_custom_regions(
+    path: &std::path::Path,
+    generated: String,
+) -> Result<String, ZorshGenError> {
+    let existing = match std::fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(generated),
+        Err(error) => return Err(error.into()),
+    };
+    custom::carry_over(&existing, &generated).map_err(|error| ZorshGenError::CustomRegions {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })
+}
+
+/// Writes each of `files` unless the file already holds exactly its contents, so that
+/// watchers and incremental builds aren't triggered by a no-op regeneration. Returns
+/// how many files were written.
+///
+/// Writes are all or nothing: every file is first written to a temporary file next to
+/// it and then renamed into place, so a crash never leaves a truncated file behind, and
+/// if a rename fails, the files already replaced get their previous contents back.
+fn write_all_if_changed(files: &[(std::path::PathBuf, String)]) -> io::Result<usize> {
+    let mut changed = Vec::new();
+    for (path, contents) in files {
+        let previous = match std::fs::read(path) {
+            Ok(existing) if existing == contents.as_bytes() => continue,
+            Ok(existing) => Some(existing),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+        changed.push((path, contents.as_bytes(), previous));
+    }
+
+    let mut staged = Vec::new();
+    for (path, contents, _) in &changed {
+        match stage(path, contents) {
+            Ok(temp) => staged.push(temp),
+            Err(error) => {
+                for temp in &staged {
+                    let _ = std::fs::remove_file(temp);
+                }
+                return Err(error);
+            }
+        }
+    }
+    for (index, ((path, _, _), temp)) in changed.iter().zip(&staged).enumerate() {
+        let Err(error) = std::fs::rename(temp, path) else {
+            continue;
+        };
+        for temp in &staged[index..] {
+            let _ = std::fs::remove_file(temp);
+        }
+        // Best effort: the error reported is the one that stopped the write
+        for (path, _, previous) in changed[..index].iter().rev() {
+            let _ = match previous {
+                Some(previous) => {
+                    stage(path, previous).and_then(|temp| std::fs::rename(temp, path))
+                }
+                None => std::fs::remove_file(path),
+            };
+        }
+        return Err(error);
+    }
+    Ok(changed.len())
+}
+
+/// Writes `contents` to a new temporary file in the directory of `path`, to be renamed
+/// over it, and returns the temporary file's path.
+fn stage(path: &std::path::Path, contents: &[u8]) -> io::Result<std::path::PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    match written {
+        Ok(()) => Ok(temp),
+        Err(error) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(error)
+        }
+    }
+}
+
+/// Returns `options` with the banner's source set to the file names of `input_paths`,
+/// unless it was given explicitly.
+pub(super) fn with_source(options: &GeneratorOptions, input_paths: &[&str]) -> GeneratorOptions {
+    let mut options = options.clone();
+    if let Some(banner) = &mut options.banner {
+        let names: Vec<_> = input_paths
+            .iter()
+            .map(|&input_path| match input_path {
+                STDIO => "stdin".into(),
+                path => {
+                    let name = std::path::Path::new(path).file_name();
+                    name.map_or(path.into(), |name| name.to_string_lossy())
+                }
+            })
+            .collect();
+        banner.source.get_or_insert_with(|| names.join(", "));
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_unchanged_writes() {
+        let dir = std::env::temp_dir().join(format!("zorsh_gen_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("schema.ts");
+        let _ = std::fs::remove_file(&path);
+
+        let write = |contents: &str| write_all_if_changed(&[(path.clone(), contents.to_string())]);
+        assert_eq!(write("export {};\n").unwrap(), 1);
+        assert_eq!(write("export {};\n").unwrap(), 0);
+        assert_eq!(write("export {}\n").unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "export {}\n");
+
+        // Nothing is written if any file can't be
+        let writes = [
+            (path.clone(), "export const a = 1;\n".to_string()),
+            (dir.join("missing").join("b.ts"), String::new()),
+        ];
+        assert!(write_all_if_changed(&writes).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "export {}\n");
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["schema.ts"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
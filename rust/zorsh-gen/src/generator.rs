@@ -0,0 +1,361 @@
+// src/generator.rs
+//! [`ZorshGenerator`], the generator with its options fixed, for embedding in other Rust
+//! tools, and [`ZorshGeneratorBuilder`], which sets them up one at a time.
+use super::{
+    generate_for_with_options, generate_to_string, generate_to_writer, generate_with_warnings,
+    render_zorsh_files_from_container, render_zorsh_schema_from_container, Backend, Banner,
+    ExportStyle, FieldCase, FormatOptions, Generated, GeneratorOptions, Hooks, Indent, Inlining,
+    LargeIntegers, ModuleFormat, NewtypeMode, OutputMode, Progress, Quotes, Resolver,
+    RuntimeImport, SchemaNaming, SourceInfo, Target, TypeFilter, TypeMapping, UnionStyle,
+    ZorshGenError,
+};
+#[cfg(feature = "fs")]
+use super::{
+    generate_merged_zorsh_files, generate_merged_zorsh_schema, generate_zorsh_files_from_container,
+    generate_zorsh_schema_from_container, render_zorsh_files, render_zorsh_schema,
+    GenerationReport,
+};
+use borsh::schema::{BorshSchemaContainer, Declaration};
+use borsh::BorshSchema;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Generates zorsh schemas with a set of options.
+///
+/// ```
+/// use zorsh_gen::{FieldCase, ZorshGenerator};
+///
+/// let generator = ZorshGenerator::builder()
+///     .field_case(FieldCase::Camel)
+///     .include("Player*")
+///     .strict(true)
+///     .build();
+/// let output = generator.generate_for::<borsh_test::GameState>()?;
+/// assert!(output.contains("  equippedItems: "));
+/// # Ok::<(), zorsh_gen::ZorshGenError>(())
+/// ```
+///
+/// With the `fs` feature, it also reads schema containers from files and writes schemas
+/// to them, e.g. `generator.generate(&["schema.bin"], "src/schemas.ts")`.
+#[derive(Clone, Debug, Default)]
+pub struct ZorshGenerator {
+    options: GeneratorOptions,
+}
+
+impl ZorshGenerator {
+    /// A generator with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn builder() -> ZorshGeneratorBuilder {
+        ZorshGeneratorBuilder::default()
+    }
+
+    pub fn options(&self) -> &GeneratorOptions {
+        &self.options
+    }
+
+    /// Returns the schema for `container`. See [`generate_to_string`].
+    pub fn generate_to_string(
+        &self,
+        container: &BorshSchemaContainer,
+    ) -> Result<String, ZorshGenError> {
+        generate_to_string(container, &self.options)
+    }
+
+    /// Returns the schema for `container` with the warnings about it. See
+    /// [`generate_with_warnings`].
+    pub fn generate_with_warnings(
+        &self,
+        container: &BorshSchemaContainer,
+    ) -> Result<Generated, ZorshGenError> {
+        generate_with_warnings(container, &self.options)
+    }
+
+    /// Returns the schema for `T` and the types it refers to.
+    pub fn generate_for<T: BorshSchema + ?Sized>(&self) -> Result<String, ZorshGenError> {
+        generate_for_with_options::<T>(&self.options)
+    }
+
+    /// Writes the schema for `container` to `writer`.
+    pub fn generate_to_writer(
+        &self,
+        container: &BorshSchemaContainer,
+        writer: &mut impl Write,
+    ) -> Result<(), ZorshGenError> {
+        generate_to_writer(container, writer, &self.options)
+    }
+
+    /// Returns the files holding the schemas for `container`, as
+    /// `ZorshGenerator::generate_container` would write them.
+    pub fn render_container(
+        &self,
+        container: &BorshSchemaContainer,
+        output_path: &str,
+    ) -> Result<Vec<(PathBuf, String)>, ZorshGenError> {
+        render_zorsh_schema_from_container(container, output_path, &self.options)
+    }
+
+    /// Returns a file per exported type of `container` under `output_dir`, as
+    /// `ZorshGenerator::generate_container_files` would write them.
+    pub fn render_container_files(
+        &self,
+        container: &BorshSchemaContainer,
+        output_dir: &str,
+    ) -> Result<Vec<(PathBuf, String)>, ZorshGenError> {
+        render_zorsh_files_from_container(container, output_dir, &self.options)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl ZorshGenerator {
+    /// Reads schema containers, `-` for standard input, and writes their schemas to one
+    /// file, `-` for standard output. See [`generate_merged_zorsh_schema`].
+    pub fn generate(
+        &self,
+        input_paths: &[&str],
+        output_path: &str,
+    ) -> Result<GenerationReport, ZorshGenError> {
+        generate_merged_zorsh_schema(input_paths, output_path, &self.options)
+    }
+
+    /// Reads schema containers and writes a file per exported type into `output_dir`.
+    /// See [`generate_merged_zorsh_files`].
+    pub fn generate_files(
+        &self,
+        input_paths: &[&str],
+        output_dir: &str,
+    ) -> Result<GenerationReport, ZorshGenError> {
+        generate_merged_zorsh_files(input_paths, output_dir, &self.options)
+    }
+
+    /// Writes the schemas for `container` to one file, `-` for standard output.
+    pub fn generate_container(
+        &self,
+        container: &BorshSchemaContainer,
+        output_path: &str,
+    ) -> Result<GenerationReport, ZorshGenError> {
+        generate_zorsh_schema_from_container(container, output_path, &self.options)
+    }
+
+    /// Writes a file per exported type of `container` into `output_dir`.
+    pub fn generate_container_files(
+        &self,
+        container: &BorshSchemaContainer,
+        output_dir: &str,
+    ) -> Result<GenerationReport, ZorshGenError> {
+        generate_zorsh_files_from_container(container, output_dir, &self.options)
+    }
+
+    /// Returns the files [`ZorshGenerator::generate`] would write, with their contents.
+    pub fn render(
+        &self,
+        input_paths: &[&str],
+        output_path: &str,
+    ) -> Result<Vec<(PathBuf, String)>, ZorshGenError> {
+        render_zorsh_schema(input_paths, output_path, &self.options)
+    }
+
+    /// Returns the files [`ZorshGenerator::generate_files`] would write, with their
+    /// contents.
+    pub fn render_files(
+        &self,
+        input_paths: &[&str],
+        output_dir: &str,
+    ) -> Result<Vec<(PathBuf, String)>, ZorshGenError> {
+        render_zorsh_files(input_paths, output_dir, &self.options)
+    }
+}
+
+impl From<GeneratorOptions> for ZorshGenerator {
+    fn from(options: GeneratorOptions) -> Self {
+        Self { options }
+    }
+}
+
+/// Sets up a [`ZorshGenerator`]. Each method sets the [`GeneratorOptions`] field of the
+/// same name, except where noted.
+#[derive(Clone, Debug, Default)]
+pub struct ZorshGeneratorBuilder {
+    options: GeneratorOptions,
+}
+
+/// Methods setting the option of their name to their argument.
+macro_rules! setters {
+    ($($(#[$doc:meta])* $name:ident: $type:ty,)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(mut self, $name: $type) -> Self {
+                self.options.$name = $name;
+                self
+            }
+        )*
+    };
+}
+
+impl ZorshGeneratorBuilder {
+    /// Starts from existing options, such as ones read from a config file.
+    pub fn with_options(options: GeneratorOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn build(self) -> ZorshGenerator {
+        ZorshGenerator {
+            options: self.options,
+        }
+    }
+
+    // What is generated
+    setters! {
+        target: Target,
+        output_mode: OutputMode,
+        module_format: ModuleFormat,
+        runtime: RuntimeImport,
+        export_style: ExportStyle,
+        inlining: Inlining,
+        hoist_duplicates: bool,
+        module_files: bool,
+    }
+
+    /// Generates single files with `backend` rather than `target`'s.
+    pub fn backend(mut self, backend: impl Backend + Send + Sync + 'static) -> Self {
+        self.options.backend = Some(Arc::new(backend));
+        self
+    }
+
+    // How types are written
+    setters! {
+        newtype_mode: NewtypeMode,
+        large_integers: LargeIntegers,
+        readonly_types: bool,
+        rust_type_docs: bool,
+        source: SourceInfo,
+    }
+
+    /// Writes enum types out as unions in this style.
+    pub fn union_types(mut self, style: UnionStyle) -> Self {
+        self.options.union_types = Some(style);
+        self
+    }
+
+    // Extra exports
+    setters! {
+        codec_functions: bool,
+        type_guards: bool,
+        tag_constants: bool,
+        variant_constructors: bool,
+        mocks: bool,
+        roundtrip_spec: bool,
+        byte_sizes: bool,
+        schema_registry: bool,
+        hash_constant: bool,
+        source_map: bool,
+        field_offsets: bool,
+    }
+
+    // Naming
+    setters! {
+        field_case: FieldCase,
+        schema_naming: SchemaNaming,
+    }
+
+    /// Prepends `prefix` to every exported type name.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.options.prefix = prefix.into();
+        self
+    }
+
+    /// Wraps every export in `export namespace {namespace} { ... }`.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.options.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Exports `declaration` as `name`, adding to the renames already given.
+    pub fn rename(mut self, declaration: impl Into<Declaration>, name: impl Into<String>) -> Self {
+        self.options.renames.insert(declaration.into(), name.into());
+        self
+    }
+
+    /// Uses `mapping` for `declaration`, adding to the mappings already given.
+    pub fn map_type(mut self, declaration: impl Into<Declaration>, mapping: TypeMapping) -> Self {
+        self.options
+            .type_mappings
+            .insert(declaration.into(), mapping);
+        self
+    }
+
+    // Filters
+    setters! {
+        filter: TypeFilter,
+    }
+
+    /// Exports types matching `pattern`, adding to the patterns already given.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.options.filter.include.push(pattern.into());
+        self
+    }
+
+    /// Doesn't export types matching `pattern`, adding to the patterns already given.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.options.filter.exclude.push(pattern.into());
+        self
+    }
+
+    // Formatting
+    setters! {
+        format: FormatOptions,
+    }
+
+    /// Sets `format.indent`.
+    pub fn indent(mut self, indent: Indent) -> Self {
+        self.options.format.indent = indent;
+        self
+    }
+
+    /// Sets `format.quotes`.
+    pub fn quotes(mut self, quotes: Quotes) -> Self {
+        self.options.format.quotes = quotes;
+        self
+    }
+
+    /// Sets `format.semicolons`.
+    pub fn semicolons(mut self, semicolons: bool) -> Self {
+        self.options.format.semicolons = semicolons;
+        self
+    }
+
+    /// Sets `format.trailing_commas`.
+    pub fn trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.options.format.trailing_commas = trailing_commas;
+        self
+    }
+
+    /// Starts the output with this banner.
+    pub fn banner(mut self, banner: Banner) -> Self {
+        self.options.banner = Some(banner);
+        self
+    }
+
+    // Strictness and hooks
+    setters! {
+        strict: bool,
+    }
+
+    pub fn resolver(mut self, resolver: impl Resolver + Send + Sync + 'static) -> Self {
+        self.options.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    pub fn hooks(mut self, hooks: impl Hooks + Send + Sync + 'static) -> Self {
+        self.options.hooks = Some(Arc::new(hooks));
+        self
+    }
+
+    pub fn progress(mut self, progress: impl Progress + Send + Sync + 'static) -> Self {
+        self.options.progress = Some(Arc::new(progress));
+        self
+    }
+}
@@ -0,0 +1,141 @@
+// src/hooks.rs
+//! [`Hooks`], which change how single types and fields are generated, and their
+//! application to the intermediate representation before any backend reads it.
+use super::{ir, GeneratorOptions, TypeMapping, ZorshGenError};
+use borsh::schema::Declaration;
+use std::borrow::Cow;
+use std::fmt;
+
+/// Overrides the generation of single types and struct fields, for changes the options
+/// can't express, such as writing one field as bytes:
+///
+/// ```
+/// use borsh::schema::Declaration;
+/// use zorsh_gen::{ir, Hooks, Override, ZorshGenerator};
+///
+/// struct RawNames;
+///
+/// impl Hooks for RawNames {
+///     fn on_field(&self, parent: &Declaration, field: &str, _ty: &Declaration) -> Option<Override> {
+///         let length = ir::Length {
+///             width: 4,
+///             range: 0..=u32::MAX as u64,
+///         };
+///         (parent == "Player" && field == "name").then_some(Override::Type(ir::Type::Bytes(length)))
+///     }
+/// }
+///
+/// let generator = ZorshGenerator::builder().hooks(RawNames).build();
+/// let output = generator.generate_for::<borsh_test::Player>()?;
+/// assert!(output.contains("  name: b.bytes(),\n"));
+/// # Ok::<(), zorsh_gen::ZorshGenError>(())
+/// ```
+///
+/// Both are called once per generation, before anything is generated, and return `None`
+/// to leave things as they are.
+pub trait Hooks {
+    /// Called for each type the schema container defines. An override applies wherever
+    /// the type is used.
+    fn on_type(&self, declaration: &Declaration, ty: &ir::Type) -> Option<Override> {
+        let _ = (declaration, ty);
+        None
+    }
+
+    /// Called for each field of a struct or enum variant, named as in Rust or by its
+    /// position in tuple structs, with the declaration of its type after `on_type`.
+    fn on_field(&self, parent: &Declaration, field: &str, ty: &Declaration) -> Option<Override> {
+        let _ = (parent, field, ty);
+        None
+    }
+}
+
+impl fmt::Debug for dyn Hooks + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hooks")
+    }
+}
+
+/// What a hook generates instead of a type or field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Override {
+    /// This type instead, e.g. [`ir::Type::Bytes`] for a sequence to be written as bytes.
+    /// Fields can't be overridden with structs or enums, which would need exporting under
+    /// a name of their own.
+    Type(ir::Type),
+    /// A zorsh expression or imported schema, as if `type_mappings` mapped it.
+    Mapping(TypeMapping),
+}
+
+/// Returns `schema` with the overrides of `options.hooks` applied, and `options` with the
+/// mappings they ask for. A field that is overridden gets a declaration of its own,
+/// `{parent}.{field}`, so that every emitter sees the override.
+pub(super) fn apply(
+    mut schema: ir::Schema,
+    options: &GeneratorOptions,
+) -> Result<(ir::Schema, Cow<'_, GeneratorOptions>), ZorshGenError> {
+    let Some(hooks) = &options.hooks else {
+        return Ok((schema, Cow::Borrowed(options)));
+    };
+    let mut mappings = options.type_mappings.clone();
+
+    let overrides: Vec<_> = schema
+        .types
+        .iter()
+        .filter_map(|(declaration, ty)| {
+            Some((declaration.clone(), hooks.on_type(declaration, ty)?))
+        })
+        .collect();
+    for (declaration, with) in overrides {
+        match with {
+            Override::Type(ty) => schema.insert(declaration, ty),
+            Override::Mapping(mapping) => {
+                mappings.insert(declaration, mapping);
+            }
+        }
+    }
+
+    let mut fields = Vec::new();
+    for (parent, ty) in &schema.types {
+        let ir::Type::Struct(struct_fields) = ty else {
+            continue;
+        };
+        let names: Vec<String> = match struct_fields {
+            ir::Fields::Named(named) => named.iter().map(|field| field.name.clone()).collect(),
+            _ => (0..struct_fields.types().len())
+                .map(|i| i.to_string())
+                .collect(),
+        };
+        for (index, (name, field)) in names.into_iter().zip(struct_fields.types()).enumerate() {
+            if let Some(with) = hooks.on_field(parent, &name, field) {
+                fields.push((parent.clone(), index, format!("{}.{}", parent, name), with));
+            }
+        }
+    }
+    for (parent, index, declaration, with) in fields {
+        match with {
+            Override::Type(ir::Type::Struct(_) | ir::Type::Enum { .. }) => {
+                return Err(ZorshGenError::options(format!(
+                    "field `{}` can't be overridden with a struct or enum, which would need a name of its own; override the type of the field instead",
+                    declaration
+                )));
+            }
+            Override::Type(ty) => schema.insert(declaration.clone(), ty),
+            Override::Mapping(mapping) => {
+                mappings.insert(declaration.clone(), mapping);
+            }
+        }
+        if let Some(ir::Type::Struct(fields)) = schema.types.get_mut(&parent) {
+            match fields {
+                ir::Fields::Named(named) => named[index].ty = declaration,
+                ir::Fields::Unnamed(elements) => elements[index] = declaration,
+                ir::Fields::Empty => {}
+            }
+        }
+    }
+
+    let options = GeneratorOptions {
+        type_mappings: mappings,
+        ..options.clone()
+    };
+    Ok((schema, Cow::Owned(options)))
+}
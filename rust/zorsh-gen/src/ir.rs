@@ -0,0 +1,321 @@
+// src/ir.rs
+//! The typed intermediate representation the emitters work from: each definition of a
+//! schema container interpreted once, with strings, byte sequences, maps, sets and
+//! options told apart from other sequences and enums, map entries split into keys and
+//! values, and enum variants in discriminant order.
+//!
+//! Types refer to each other by declaration, as borsh definitions do, so recursive types
+//! need no special handling; [`Schema::get`] looks references up.
+use super::{collection_name, normalize, option_value, parse_map_types};
+use borsh::schema::{self, BorshSchemaContainer, Declaration, Definition};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+
+/// Every definition of a schema container, as [`Type`]s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+    /// The declaration of the root type.
+    pub root: Declaration,
+    pub types: BTreeMap<Declaration, Type>,
+    /// The declarations of `types`, keyed by whitespace-normalized declaration.
+    index: HashMap<String, Declaration>,
+    container: BorshSchemaContainer,
+}
+
+impl Schema {
+    pub fn new(container: &BorshSchemaContainer) -> Self {
+        let definitions: HashMap<String, &Definition> = container
+            .definitions()
+            .map(|(declaration, definition)| (normalize(declaration), definition))
+            .collect();
+        let types: BTreeMap<Declaration, Type> = container
+            .definitions()
+            .map(|(declaration, definition)| {
+                (
+                    declaration.clone(),
+                    Type::new(declaration, definition, &definitions),
+                )
+            })
+            .collect();
+        let index = types
+            .keys()
+            .map(|declaration| (normalize(declaration), declaration.clone()))
+            .collect();
+        Self {
+            root: container.declaration().clone(),
+            types,
+            index,
+            container: container.clone(),
+        }
+    }
+
+    /// The schema container this was interpreted from, for backends needing what the
+    /// representation leaves out, such as its exact encoding.
+    pub fn container(&self) -> &BorshSchemaContainer {
+        &self.container
+    }
+
+    /// Looks up a declaration regardless of whitespace, returning the container's spelling
+    /// of it along with its type.
+    pub fn get(&self, declaration: &str) -> Option<(&Declaration, &Type)> {
+        let declaration = self.index.get(&normalize(declaration))?;
+        self.types.get_key_value(declaration)
+    }
+
+    /// Defines or redefines `declaration`, keeping lookups by [`Schema::get`] in step.
+    pub(super) fn insert(&mut self, declaration: Declaration, ty: Type) {
+        self.index
+            .insert(normalize(&declaration), declaration.clone());
+        self.types.insert(declaration, ty);
+    }
+}
+
+impl From<&BorshSchemaContainer> for Schema {
+    fn from(container: &BorshSchemaContainer) -> Self {
+        Self::new(container)
+    }
+}
+
+/// What a declaration is, as far as generating code for it goes. Kinds of type may be
+/// added in minor versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Type {
+    /// An integer, float, `bool` or `()` of this many bytes, named by its declaration.
+    Primitive(u8),
+    /// A `String`.
+    String(Length),
+    /// A sequence of `u8`s, such as `Vec<u8>` or `[u8; 32]`.
+    Bytes(Length),
+    /// Any other sequence, such as a `Vec`, `VecDeque` or array.
+    Sequence {
+        element: Declaration,
+        length: Length,
+    },
+    /// A `HashMap` or `BTreeMap`.
+    Map {
+        key: Declaration,
+        value: Declaration,
+        length: Length,
+    },
+    /// A `HashSet` or `BTreeSet`.
+    Set {
+        element: Declaration,
+        length: Length,
+    },
+    Tuple(Vec<Declaration>),
+    /// An `Option`, or an enum shaped like one, with this `Some` payload.
+    Option(Declaration),
+    Struct(Fields),
+    /// Any other enum.
+    Enum {
+        /// How many bytes the variant index is encoded with.
+        tag_width: u8,
+        /// Sorted by discriminant.
+        variants: Vec<Variant>,
+    },
+}
+
+impl Type {
+    fn new(
+        declaration: &str,
+        definition: &Definition,
+        definitions: &HashMap<String, &Definition>,
+    ) -> Self {
+        match definition {
+            Definition::Primitive(size) => Type::Primitive(*size),
+            Definition::Sequence {
+                length_width,
+                length_range,
+                elements,
+            } => {
+                let length = Length {
+                    width: *length_width,
+                    range: length_range.clone(),
+                };
+                let element = elements.clone();
+                match collection_name(declaration) {
+                    "String" if length.width != Definition::ARRAY_LENGTH_WIDTH => {
+                        return Type::String(length);
+                    }
+                    "HashMap" | "BTreeMap" => {
+                        let (key, value) = match definitions.get(&normalize(elements)) {
+                            Some(Definition::Tuple { elements }) if elements.len() == 2 => {
+                                (elements[0].clone(), elements[1].clone())
+                            }
+                            _ => parse_map_types(declaration),
+                        };
+                        return Type::Map { key, value, length };
+                    }
+                    "HashSet" | "BTreeSet" => return Type::Set { element, length },
+                    _ => {}
+                }
+                match normalize(elements).as_str() {
+                    "u8" => Type::Bytes(length),
+                    _ => Type::Sequence { element, length },
+                }
+            }
+            Definition::Tuple { elements } => Type::Tuple(elements.clone()),
+            Definition::Enum {
+                tag_width,
+                variants,
+            } => match option_value(variants) {
+                Some(value) if *tag_width == 1 => Type::Option(value.clone()),
+                _ => {
+                    let mut variants: Vec<Variant> = variants
+                        .iter()
+                        .map(|(discriminant, name, payload)| Variant {
+                            discriminant: *discriminant,
+                            name: name.clone(),
+                            payload: payload.clone(),
+                        })
+                        .collect();
+                    variants.sort_by_key(|variant| variant.discriminant);
+                    Type::Enum {
+                        tag_width: *tag_width,
+                        variants,
+                    }
+                }
+            },
+            Definition::Struct { fields } => Type::Struct(match fields {
+                schema::Fields::NamedFields(fields) => Fields::Named(
+                    fields
+                        .iter()
+                        .map(|(name, ty)| Field {
+                            name: name.clone(),
+                            ty: ty.clone(),
+                        })
+                        .collect(),
+                ),
+                schema::Fields::UnnamedFields(elements) => Fields::Unnamed(elements.clone()),
+                schema::Fields::Empty => Fields::Empty,
+            }),
+        }
+    }
+
+    /// The declarations this type refers to directly, in the order they appear.
+    pub fn references(&self) -> Vec<&Declaration> {
+        match self {
+            Type::Primitive(_) | Type::String(_) | Type::Bytes(_) => Vec::new(),
+            Type::Sequence { element, .. } | Type::Set { element, .. } => vec![element],
+            Type::Map { key, value, .. } => vec![key, value],
+            Type::Tuple(elements) => elements.iter().collect(),
+            Type::Option(value) => vec![value],
+            Type::Struct(fields) => fields.types(),
+            Type::Enum { variants, .. } => variants.iter().map(|v| &v.payload).collect(),
+        }
+    }
+}
+
+/// How long a sequence is, and how its length is encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Length {
+    /// How many bytes the length prefix is; 0 for arrays, which have none.
+    pub width: u8,
+    pub range: RangeInclusive<u64>,
+}
+
+impl Length {
+    /// The length of an array, which is fixed and so not encoded.
+    pub fn fixed(&self) -> Option<u64> {
+        let fixed =
+            self.width == Definition::ARRAY_LENGTH_WIDTH && self.range.start() == self.range.end();
+        fixed.then_some(*self.range.start())
+    }
+
+    /// The lengths allowed, if they are narrower than any `u32` length.
+    pub fn bounds(&self) -> Option<&RangeInclusive<u64>> {
+        let bounded = self.width != Definition::ARRAY_LENGTH_WIDTH
+            && self.range != Definition::DEFAULT_LENGTH_RANGE;
+        bounded.then_some(&self.range)
+    }
+}
+
+/// The fields of a struct, or of an enum variant's payload struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fields {
+    Named(Vec<Field>),
+    /// The types of a tuple struct's fields.
+    Unnamed(Vec<Declaration>),
+    Empty,
+}
+
+impl Fields {
+    /// The declarations of the fields, in order.
+    pub fn types(&self) -> Vec<&Declaration> {
+        match self {
+            Fields::Named(fields) => fields.iter().map(|field| &field.ty).collect(),
+            Fields::Unnamed(elements) => elements.iter().collect(),
+            Fields::Empty => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// The name as written in Rust, including any `r#`.
+    pub name: String,
+    pub ty: Declaration,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variant {
+    pub discriminant: i64,
+    pub name: String,
+    /// The payload's declaration: `()` for unit variants, and otherwise a struct borsh
+    /// derives named after the enum and variant, such as `GameEventPlayerJoined`.
+    pub payload: Declaration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh_test::GameState;
+
+    #[test]
+    fn interprets_definitions() {
+        let schema = Schema::new(&BorshSchemaContainer::for_type::<GameState>());
+        let ty = |declaration: &str| schema.get(declaration).unwrap().1.clone();
+        let unbounded = Length {
+            width: 4,
+            range: Definition::DEFAULT_LENGTH_RANGE,
+        };
+
+        assert_eq!(schema.root, "GameState");
+        assert_eq!(ty("String"), Type::String(unbounded.clone()));
+        assert_eq!(
+            ty("HashMap<String, Player>"),
+            Type::Map {
+                key: "String".to_string(),
+                value: "Player".to_string(),
+                length: unbounded.clone(),
+            }
+        );
+        assert_eq!(ty("Option<u64>"), Type::Option("u64".to_string()));
+        assert_eq!(
+            ty("Vec<Item>"),
+            Type::Sequence {
+                element: "Item".to_string(),
+                length: unbounded,
+            }
+        );
+        let Type::Enum {
+            tag_width,
+            variants,
+        } = ty("GameEvent")
+        else {
+            panic!("`GameEvent` isn't an enum");
+        };
+        assert_eq!(tag_width, 1);
+        assert_eq!(variants[0].name, "PlayerJoined");
+        assert_eq!(variants[0].payload, "GameEventPlayerJoined");
+        assert_eq!(
+            ty("GameEventPlayerJoined"),
+            Type::Struct(Fields::Named(vec![Field {
+                name: "player_id".to_string(),
+                ty: "String".to_string(),
+            }]))
+        );
+        assert_eq!(ty("GameState").references()[0], "HashMap<String, Player>");
+    }
+}
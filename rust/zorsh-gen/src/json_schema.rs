@@ -0,0 +1,289 @@
+// src/json_schema.rs
+//! The JSON Schema backend: a JSON Schema (draft 2020-12) document describing the JSON
+//! form of values as zorsh decodes them, for validating API payloads and for tools that
+//! don't read TypeScript.
+//!
+//! Values have the shapes of the generated TypeScript types, with what JSON can't hold
+//! written the way `JSON.stringify` replacers commonly do: 64- and 128-bit integers as
+//! decimal strings unless `LargeIntegers::Number` makes `u64` and `i64` numbers, byte
+//! sequences as arrays of bytes, maps with string keys as objects and other maps as
+//! arrays of `[key, value]` pairs, and sets as arrays of unique items.
+use super::{
+    ir, large_integer, normalize, primitive_to_ts, LargeIntegers, NewtypeMode, Parser,
+    ZorshGenError,
+};
+use serde_json::{json, Map, Value};
+
+/// The JSON Schema dialect of the documents written.
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+impl Parser<'_> {
+    /// Generates the JSON Schema document, with a definition in `$defs` for each struct
+    /// and enum that would be exported as its own schema.
+    pub(super) fn json_schema(&mut self) -> Result<String, ZorshGenError> {
+        let mut defs = Map::new();
+        let root = self.container.declaration().clone();
+        let schema = self.json_type(&root, &mut defs);
+
+        let mut document = Map::new();
+        document.insert("$schema".to_string(), Value::from(DIALECT));
+        if self.options.banner.is_some() {
+            let banner = self.banner();
+            let lines: Vec<&str> = banner
+                .lines()
+                .filter_map(|line| line.strip_prefix("// "))
+                .collect();
+            document.insert("$comment".to_string(), Value::from(lines.join(" ")));
+        }
+        document.insert("title".to_string(), Value::from(self.type_name(&root)));
+        if let Value::Object(schema) = schema {
+            document.extend(schema);
+        }
+        if !defs.is_empty() {
+            document.insert("$defs".to_string(), Value::Object(defs));
+        }
+        if !self.errors.is_empty() {
+            return Err(self.failure());
+        }
+        let json = serde_json::to_string_pretty(&document).expect("a schema always serializes");
+        Ok(json + "\n")
+    }
+
+    /// Returns the schema of a declaration, referring to its entry in `defs`, added if
+    /// needed, for exported structs and enums.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, defs))
+    )]
+    fn json_type(&mut self, declaration: &str, defs: &mut Map<String, Value>) -> Value {
+        if self.mappings.contains_key(&normalize(declaration)) {
+            return json!({ "description": format!("Rust: {}, mapped to a custom schema", declaration) });
+        }
+        let Some((declaration, ty)) = self.ir.get(declaration) else {
+            self.unresolved.insert(declaration.to_string());
+            self.strict_error(
+                declaration,
+                format!(
+                    "`{}` isn't defined in the schema container, mapped or resolved",
+                    declaration
+                ),
+            );
+            return json!({ "description": format!("TODO: Resolve {}", declaration) });
+        };
+        if !self.is_exported(declaration, ty) {
+            return self.json_definition(declaration, ty, defs);
+        }
+        let name = self.type_name(declaration);
+        if !defs.contains_key(&name) {
+            // Added before its own fields, which may refer back to it
+            defs.insert(name.clone(), Value::Bool(true));
+            self.exported.push(declaration.clone());
+            let schema = self.json_definition(declaration, ty, defs);
+            defs.insert(name.clone(), schema);
+        }
+        json!({ "$ref": format!("#/$defs/{}", name) })
+    }
+
+    /// Returns the schema of a type, even if it is exported.
+    fn json_definition(
+        &mut self,
+        declaration: &str,
+        ty: &ir::Type,
+        defs: &mut Map<String, Value>,
+    ) -> Value {
+        match ty {
+            ir::Type::Primitive(size) => self.json_primitive(declaration, *size),
+            ir::Type::String(_) => json!({ "type": "string" }),
+            ir::Type::Map { key, value, .. } => {
+                let value = self.json_type(value, defs);
+                let key_schema = self.json_type(key, defs);
+                if key_schema == json!({ "type": "string" }) {
+                    return json!({ "type": "object", "additionalProperties": value });
+                }
+                json!({ "type": "array", "items": pair(key_schema, value) })
+            }
+            ir::Type::Set { element, .. } => {
+                let items = self.json_type(element, defs);
+                json!({ "type": "array", "items": items, "uniqueItems": true })
+            }
+            ir::Type::Bytes(length) => {
+                let items = json!({ "type": "integer", "minimum": 0, "maximum": 255 });
+                array(items, length)
+            }
+            ir::Type::Sequence { element, length } => {
+                let items = self.json_type(element, defs);
+                array(items, length)
+            }
+            ir::Type::Tuple(elements) => self.json_tuple(elements, defs),
+            ir::Type::Option(value) => {
+                let value = self.json_option_value(value, defs);
+                json!({ "anyOf": [value, { "type": "null" }] })
+            }
+            ir::Type::Enum { variants, .. } => {
+                if variants.is_empty() {
+                    return json!({ "not": {} });
+                }
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|variant| {
+                        let payload = self.json_variant(&variant.payload, defs);
+                        keyed(&variant.name, payload)
+                    })
+                    .collect();
+                json!({ "oneOf": variants })
+            }
+            ir::Type::Struct(fields) => match fields {
+                ir::Fields::Empty => empty_object(),
+                ir::Fields::Named(fields) => self.json_object(fields, defs),
+                ir::Fields::Unnamed(elements)
+                    if elements.len() == 1 && self.options.newtype_mode != NewtypeMode::Tuple =>
+                {
+                    self.json_type(&elements[0], defs)
+                }
+                ir::Fields::Unnamed(elements) => self.json_tuple(elements, defs),
+            },
+        }
+    }
+
+    /// Returns the schema of a primitive, matching `primitive_ts`.
+    fn json_primitive(&mut self, declaration: &str, size: u8) -> Value {
+        match (
+            self.options.large_integers,
+            large_integer(declaration, size),
+        ) {
+            (LargeIntegers::Number, Some(large @ ("u64" | "i64"))) => {
+                let minimum = if large == "u64" { 0 } else { i64::MIN };
+                return json!({ "type": "integer", "minimum": minimum });
+            }
+            (_, Some(large)) => {
+                let pattern = if large.starts_with('u') {
+                    "^[0-9]+$"
+                } else {
+                    "^-?[0-9]+$"
+                };
+                return json!({ "type": "string", "pattern": pattern });
+            }
+            _ => {}
+        }
+        match (declaration, primitive_to_ts(declaration, size)) {
+            ("f32" | "f64", _) => json!({ "type": "number" }),
+            ("bool", _) => json!({ "type": "boolean" }),
+            ("()", _) => empty_object(),
+            ("i8", _) => json!({ "type": "integer", "minimum": i8::MIN, "maximum": i8::MAX }),
+            ("i16", _) => json!({ "type": "integer", "minimum": i16::MIN, "maximum": i16::MAX }),
+            ("i32", _) => json!({ "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX }),
+            (_, "number") => {
+                let maximum = (1u64 << (8 * u32::from(size))) - 1;
+                json!({ "type": "integer", "minimum": 0, "maximum": maximum })
+            }
+            _ => {
+                self.strict_error(
+                    declaration,
+                    format!(
+                        "primitive `{}` is {} bytes wide, which no zorsh builder reads",
+                        declaration, size
+                    ),
+                );
+                json!({ "description": format!("TODO: Read {}-byte primitive {}", size, declaration) })
+            }
+        }
+    }
+
+    /// Returns the schema of an `Option` payload, matching `parse_option_value`: nested
+    /// options are keyed `None`/`Some` objects, so that `Some(None)` isn't `null`.
+    fn json_option_value(&mut self, declaration: &str, defs: &mut Map<String, Value>) -> Value {
+        if let Some(ir::Type::Option(value)) = self.ty(declaration) {
+            let value = self.json_option_value(value, defs);
+            return json!({ "oneOf": [keyed("None", empty_object()), keyed("Some", value)] });
+        }
+        self.json_type(declaration, defs)
+    }
+
+    /// Returns the schema of an enum variant payload, matching `parse_ts_variant`.
+    fn json_variant(&mut self, declaration: &str, defs: &mut Map<String, Value>) -> Value {
+        let declaration = self.resolve(declaration).map_or(declaration, |(d, _)| d);
+        if !self.variant_structs.contains(declaration) {
+            return self.json_type(declaration, defs);
+        }
+        match self.ir.get(declaration) {
+            Some((_, ir::Type::Struct(fields))) => match fields {
+                ir::Fields::Named(fields) => self.json_object(fields, defs),
+                ir::Fields::Unnamed(elements) if elements.len() == 1 => {
+                    self.json_type(&elements[0], defs)
+                }
+                ir::Fields::Unnamed(elements) => self.json_tuple(elements, defs),
+                ir::Fields::Empty => empty_object(),
+            },
+            _ => self.json_type(declaration, defs),
+        }
+    }
+
+    fn json_object(&mut self, fields: &[ir::Field], defs: &mut Map<String, Value>) -> Value {
+        let fields: Vec<(String, Value)> = fields
+            .iter()
+            .map(|field| {
+                (
+                    self.field_name(&field.name),
+                    self.json_type(&field.ty, defs),
+                )
+            })
+            .collect();
+        object(fields)
+    }
+
+    fn json_tuple(&mut self, elements: &[String], defs: &mut Map<String, Value>) -> Value {
+        let items: Vec<Value> = elements.iter().map(|e| self.json_type(e, defs)).collect();
+        let count = items.len();
+        json!({
+            "type": "array",
+            "prefixItems": items,
+            "items": false,
+            "minItems": count,
+        })
+    }
+}
+
+/// An object with exactly the given properties.
+fn object(properties: Vec<(String, Value)>) -> Value {
+    let required: Vec<&String> = properties.iter().map(|(name, _)| name).collect();
+    let required = json!(required);
+    json!({
+        "type": "object",
+        "properties": Map::from_iter(properties),
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// An array of `items`, with the lengths a sequence may have unless any `u32` length is
+/// allowed.
+fn array(items: Value, length: &ir::Length) -> Value {
+    let mut schema = json!({ "type": "array", "items": items });
+    if *length.range.start() > 0 {
+        schema["minItems"] = Value::from(*length.range.start());
+    }
+    if *length.range.end() < u64::from(u32::MAX) {
+        schema["maxItems"] = Value::from(*length.range.end());
+    }
+    schema
+}
+
+/// An object with a single property `name`, as enum values are.
+fn keyed(name: &str, payload: Value) -> Value {
+    object(vec![(name.to_string(), payload)])
+}
+
+/// The `{}` of unit values.
+fn empty_object() -> Value {
+    json!({ "type": "object", "maxProperties": 0 })
+}
+
+/// A `[key, value]` entry of a map with keys other than strings.
+fn pair(key: Value, value: Value) -> Value {
+    json!({
+        "type": "array",
+        "prefixItems": [key, value],
+        "items": false,
+        "minItems": 2,
+    })
+}
@@ -0,0 +1,5801 @@
+// src/lib.rs
+//! Generates zorsh TypeScript schemas from a serialized `BorshSchemaContainer`.
+//!
+//! Map keys may be any type: `HashMap<u64, Player>` becomes
+//! `b.hashMap(b.u64(), PlayerSchema)` and struct, tuple or enum keys reference their
+//! own schemas. On the TypeScript side such keys decode to fresh objects, so a
+//! `Map` keyed by a struct can't be looked up by value and should be iterated
+//! instead. zorsh also orders entries by their string form when encoding, so a map
+//! with non-string keys may be written in a different order than Rust's canonical
+//! encoding; Rust still decodes it unless built with `de_strict_order`.
+// Lets `#[derive(ZorshExport)]` refer to this crate by name from within it too
+extern crate self as zorsh_gen;
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition};
+use borsh::{BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Records a debug event, such as how the parser resolved a type, with the `tracing`
+/// feature; without it, nothing.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+mod backend;
+#[cfg(feature = "fs")]
+mod custom;
+mod diagnostics;
+mod error;
+mod files;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+mod format;
+#[cfg(feature = "fs")]
+mod fs;
+mod generator;
+mod hooks;
+pub mod ir;
+#[cfg(feature = "backend-json-schema")]
+mod json_schema;
+mod mock;
+#[cfg(feature = "inspect")]
+pub mod payload;
+mod report;
+mod settings;
+mod source;
+mod source_map;
+mod spec;
+mod tagged;
+mod visit;
+#[cfg(feature = "backend-zod")]
+mod zod;
+
+#[cfg(feature = "backend-json-schema")]
+pub use backend::JsonSchemaBackend;
+#[cfg(feature = "backend-zod")]
+pub use backend::ZodBackend;
+pub use backend::{Backend, ZorshBackend};
+pub use diagnostics::{Diagnostic, Diagnostics};
+pub use error::ZorshGenError;
+pub use format::{FormatOptions, Indent, Quotes};
+#[cfg(all(feature = "fs", feature = "source"))]
+pub use fs::{generate_all, generate_all_with_options};
+#[cfg(feature = "fs")]
+pub use fs::{
+    generate_merged_zorsh_files, generate_merged_zorsh_schema, generate_zorsh_files,
+    generate_zorsh_files_from_container, generate_zorsh_schema,
+    generate_zorsh_schema_from_container, generate_zorsh_schema_with_options, render_zorsh_files,
+    render_zorsh_schema, write_rendered,
+};
+pub use generator::{ZorshGenerator, ZorshGeneratorBuilder};
+pub use hooks::{Hooks, Override};
+pub use report::GenerationReport;
+pub use settings::{Settings, UnionTypes};
+pub use source::SourceInfo;
+pub use visit::{walk, SchemaVisitor};
+#[cfg(feature = "source")]
+pub use zorsh_gen_derive::ZorshExport;
+
+/// How single-field tuple structs such as `struct AccountId(String)` are emitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum NewtypeMode {
+    /// A one-element `b.tuple`, so values are written as `["alice"]`.
+    #[default]
+    Tuple,
+    /// The inner schema itself, keeping the newtype's name for the schema and type.
+    Flatten,
+    /// The inner schema with a branded type (`string & { readonly __brand: "AccountId" }`),
+    /// so distinct newtypes over the same type can't be mixed up.
+    Branded,
+}
+
+/// How struct field names are spelled in the generated schemas and types.
+///
+/// Borsh encodes fields by position, so renaming them doesn't change the wire format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldCase {
+    /// The Rust name, e.g. `player_id`.
+    #[default]
+    Keep,
+    /// `playerId`.
+    Camel,
+    /// `PlayerId`.
+    Pascal,
+}
+
+impl FieldCase {
+    fn apply(self, name: &str) -> String {
+        let words = name.trim_start_matches('_');
+        let prefix = &name[..name.len() - words.len()];
+        let pascal = pascal_case(words);
+        match self {
+            FieldCase::Keep => name.to_string(),
+            FieldCase::Pascal => format!("{}{}", prefix, pascal),
+            FieldCase::Camel => {
+                let mut chars = pascal.chars();
+                let first = chars.next().map(|c| c.to_ascii_lowercase());
+                format!(
+                    "{}{}{}",
+                    prefix,
+                    first.into_iter().collect::<String>(),
+                    chars.as_str()
+                )
+            }
+        }
+    }
+}
+
+/// How the exported schema constant for a type is named.
+#[derive(Clone)]
+pub enum SchemaNaming {
+    /// The type name between a prefix and a suffix; the default is `PlayerSchema`.
+    Affix { prefix: String, suffix: String },
+    /// Computes the constant name from the type name, e.g. `|name| format!("${}", name)`.
+    Custom(Arc<dyn Fn(&str) -> String + Send + Sync>),
+}
+
+impl SchemaNaming {
+    fn apply(&self, name: &str) -> String {
+        match self {
+            SchemaNaming::Affix { prefix, suffix } => format!("{}{}{}", prefix, name, suffix),
+            SchemaNaming::Custom(format) => format(name),
+        }
+    }
+}
+
+impl Default for SchemaNaming {
+    fn default() -> Self {
+        SchemaNaming::Affix {
+            prefix: String::new(),
+            suffix: "Schema".to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for SchemaNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaNaming::Affix { prefix, suffix } => f
+                .debug_struct("Affix")
+                .field("prefix", prefix)
+                .field("suffix", suffix)
+                .finish(),
+            SchemaNaming::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// The npm package of the zorsh runtime, which generated code imports by default.
+pub const RUNTIME_PACKAGE: &str = "@zorsh/zorsh";
+
+/// Where the generated code imports the zorsh runtime from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeImport {
+    /// The module specifier, e.g. `@zorsh/zorsh`, the default, or `./zorsh-shim`.
+    pub module: String,
+    /// The exported binding holding the builders. Anything other than `b` is imported as `b`.
+    pub binding: String,
+}
+
+impl Default for RuntimeImport {
+    fn default() -> Self {
+        Self {
+            module: RUNTIME_PACKAGE.to_string(),
+            binding: "b".to_string(),
+        }
+    }
+}
+
+/// The module syntax used for imports and exports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleFormat {
+    /// `import { b } from "@zorsh/zorsh";` and `export const`.
+    #[default]
+    Esm,
+    /// TypeScript's CommonJS syntax: `import zorsh = require("@zorsh/zorsh");`, and the
+    /// exports in a namespace assigned to `module.exports` with `export =`.
+    #[cfg_attr(feature = "cli", value(name = "commonjs"))]
+    #[serde(rename = "commonjs")]
+    CommonJs,
+}
+
+/// What the schema container is converted to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Target {
+    /// TypeScript zorsh schemas, shaped by the other options.
+    #[default]
+    Zorsh,
+    /// A JSON Schema document describing the JSON form of the values zorsh decodes, for
+    /// validating API payloads. Naming, field case, newtype and large integer options
+    /// apply; options adding TypeScript code don't. Only supported for single-file
+    /// output.
+    #[cfg(feature = "backend-json-schema")]
+    JsonSchema,
+    /// TypeScript Zod schemas validating the values zorsh decodes, each exported with its
+    /// inferred type. Naming, field case, newtype and large integer options apply, except
+    /// for branding; options adding zorsh code don't. Only supported for single-file
+    /// output.
+    #[cfg(feature = "backend-zod")]
+    Zod,
+}
+
+/// What the generated file contains.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputMode {
+    /// zorsh schema constants with their inferred types.
+    #[default]
+    Schemas,
+    /// Only `interface` and `type` declarations, suitable for a `.d.ts` file. Nothing is
+    /// imported from zorsh, and mapped or resolver-provided declarations become `unknown`.
+    Declarations,
+}
+
+/// How 64- and 128-bit integers appear in TypeScript.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum LargeIntegers {
+    /// As `bigint`, like zorsh.
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "bigint"))]
+    #[serde(rename = "bigint")]
+    BigInt,
+    /// `u64` and `i64` as `number`, for code that can't handle `bigint`. Values outside
+    /// `Number.MIN_SAFE_INTEGER..=Number.MAX_SAFE_INTEGER` throw a `RangeError` when
+    /// encoded or decoded. `u128` and `i128` stay `bigint`.
+    Number,
+    /// All of them as decimal strings such as `"18446744073709551615"`, which survive
+    /// `JSON.stringify`. Encoding anything else throws a `SyntaxError`.
+    String,
+}
+
+/// Which structs and enums get their own exported schema, and which are written out
+/// where they are used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum Inlining {
+    /// Export named structs and enums; inline enum variant payloads and anonymous types.
+    #[default]
+    Auto,
+    /// Also export enum variant payloads with named fields or several fields, e.g.
+    /// `GameEventPlayerJoinedSchema`.
+    Reference,
+    /// Export only the root type, with everything it refers to written out in full.
+    Inline,
+}
+
+/// How enums are typed when `GeneratorOptions::union_types` is set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnionStyle {
+    /// A union keyed by variant name, written out instead of inferred from the schema:
+    /// `type GameEvent = { PlayerJoined: { player_id: string } } | ...`.
+    Keyed,
+    /// The keyed union, plus a `{Enum}Tagged` union with the variant name in `field`,
+    /// e.g. `{ kind: "PlayerJoined"; player_id: string }`, and `to{Enum}Tagged` and
+    /// `from{Enum}Tagged` functions converting between the two. Payloads other than
+    /// named fields go in a `value` property.
+    Tagged { field: String },
+}
+
+/// How schemas are exported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportStyle {
+    /// One `export const {Type}Schema` per type.
+    #[default]
+    Separate,
+    /// A single `export const schemas = { Type: ... } as const` object and a `types`
+    /// namespace with the inferred types, for bundlers that handle many exports badly and
+    /// for looking schemas up by name. Only supported for single-file output; helper
+    /// functions are still exported one by one.
+    Object,
+}
+
+/// A comment at the top of generated files tracing them back to their input.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Banner {
+    /// The schema file the code was generated from. Filled in with the input's file
+    /// name by the functions that read one.
+    pub source: Option<String>,
+    /// Include the time of generation. Off by default so that output is reproducible.
+    pub timestamp: bool,
+}
+
+/// Resolves declarations that have no definition in the schema container, such as
+/// types with hand-written `BorshSchema` impls.
+pub trait Resolver {
+    /// Returns a zorsh expression for `declaration`, or `None` to leave it unresolved.
+    fn resolve(&self, declaration: &str) -> Option<String>;
+}
+
+impl<F: Fn(&str) -> Option<String>> Resolver for F {
+    fn resolve(&self, declaration: &str) -> Option<String> {
+        self(declaration)
+    }
+}
+
+impl fmt::Debug for dyn Resolver + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Resolver")
+    }
+}
+
+/// Told about each struct and enum as it is generated, to report progress on large
+/// schema containers.
+pub trait Progress {
+    /// Called before generating `declaration`, the `done`th of `total`, counting from 1.
+    fn declaration(&self, done: usize, total: usize, declaration: &str);
+}
+
+impl<F: Fn(usize, usize, &str)> Progress for F {
+    fn declaration(&self, done: usize, total: usize, declaration: &str) {
+        self(done, total, declaration)
+    }
+}
+
+impl fmt::Debug for dyn Progress + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
+
+/// A replacement for the schema generated for a declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeMapping {
+    /// A zorsh expression used verbatim, e.g. `b.string()`.
+    Expression(String),
+    /// A schema imported by name, emitted as `import { name } from "module";`.
+    Import { module: String, name: String },
+}
+
+/// Selects the structs and enums to export by declaration name, using glob patterns in
+/// which `*` matches any run of characters and `?` any single one, e.g. `Player*` or
+/// `my_crate::internal::*`.
+///
+/// Types that a selected type refers to are exported too, even if they are excluded,
+/// since its schema couldn't be built without them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TypeFilter {
+    /// Export only types matching one of these patterns, or every type if empty.
+    pub include: Vec<String>,
+    /// Don't export types matching one of these patterns.
+    pub exclude: Vec<String>,
+}
+
+impl TypeFilter {
+    /// Whether `declaration` is selected by the filter.
+    pub fn matches(&self, declaration: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| glob_matches(p, declaration));
+        included && !self.exclude.iter().any(|p| glob_matches(p, declaration))
+    }
+}
+
+/// Options controlling the generated TypeScript.
+///
+/// Serialized as [`Settings`], the keys of `zorsh.toml`, which leave out the source info,
+/// custom schema naming and the hooks.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(try_from = "Settings", into = "Settings")]
+pub struct GeneratorOptions {
+    pub target: Target,
+    /// Generates with this rather than `target`'s built-in backend. Custom backends write
+    /// one file, without source maps or roundtrip specs.
+    pub backend: Option<Arc<dyn Backend + Send + Sync>>,
+    pub newtype_mode: NewtypeMode,
+    pub field_case: FieldCase,
+    pub schema_naming: SchemaNaming,
+    pub runtime: RuntimeImport,
+    pub module_format: ModuleFormat,
+    pub output_mode: OutputMode,
+    pub export_style: ExportStyle,
+    pub inlining: Inlining,
+    pub large_integers: LargeIntegers,
+    /// Export enum variant payloads with identical fields, such as several variants all
+    /// carrying `{ player_id: String }`, once as a shared schema named after the first
+    /// of them, instead of writing each out. Only applies to `Inlining::Auto`.
+    pub hoist_duplicates: bool,
+    /// Write enum types out as unions instead of `b.infer<typeof Schema>`, which editors
+    /// show as a deep expansion.
+    pub union_types: Option<UnionStyle>,
+    /// Write exported types out with `readonly` properties and tuples, `ReadonlyArray`,
+    /// `ReadonlyMap` and `ReadonlySet`, for code keeping state immutable.
+    pub readonly_types: bool,
+    /// Annotate exported schemas and struct fields with the Rust declaration they came
+    /// from, e.g. `/** Rust: HashMap<String, Player> */`.
+    pub rust_type_docs: bool,
+    /// Emit `serialize{Type}` and `deserialize{Type}` functions next to each schema.
+    pub codec_functions: bool,
+    /// Emit an `is{Variant}` type guard for each enum variant.
+    pub type_guards: bool,
+    /// Emit a `{Enum}Tag` object mapping each variant name to its discriminant.
+    pub tag_constants: bool,
+    /// Emit a `{Enum}` object with a constructor per variant, e.g. `GameEvent.PlayerLeft(...)`.
+    pub variant_constructors: bool,
+    /// Emit a `mock{Type}(seed?)` function returning a random value for each schema, for
+    /// tests and stories. Values are reproducible for a given seed.
+    pub mocks: bool,
+    /// Also write a vitest spec round-tripping a mock value of every exported type through
+    /// its schema: `{output}.test.ts` next to a single file, or `schemas.test.ts` among
+    /// split files. Turns on `mocks`, which provide the values.
+    pub roundtrip_spec: bool,
+    /// Emit a `{TYPE}_BYTE_SIZE` constant for each type with a fixed encoded size, and
+    /// `{TYPE}_MIN_BYTE_SIZE` for the others.
+    pub byte_sizes: bool,
+    /// Emit `schemaRegistry`, an object mapping each Rust declaration name to its schema,
+    /// for tools that decode values given a type name at runtime.
+    pub schema_registry: bool,
+    /// Emit `SCHEMA_HASH`, the SHA-256 of the schema container's borsh encoding, which
+    /// is also given in the banner.
+    pub hash_constant: bool,
+    /// Also write a JSON file mapping each Rust declaration to the name, file and line it
+    /// was generated at: `{output}.map.json` next to a single file, or `zorsh-map.json`
+    /// among split files.
+    pub source_map: bool,
+    /// Emit a `{Struct}Offsets` object giving the byte offset and size of each field of
+    /// structs with a fixed encoded size, for reading fields straight out of a buffer.
+    pub field_offsets: bool,
+    pub format: FormatOptions,
+    /// Start the output with a comment naming the generator version, the source schema
+    /// and a hash of it.
+    pub banner: Option<Banner>,
+    /// Schemas to use for specific declarations (e.g. near-sdk's `AccountId` or `U128`),
+    /// consulted before anything else. Mapped declarations are never exported themselves.
+    pub type_mappings: BTreeMap<Declaration, TypeMapping>,
+    /// Names to export specific declarations under instead of their Rust names, e.g.
+    /// `State` → `ContractState`, which gives `ContractStateSchema` and `ContractState`.
+    pub renames: BTreeMap<Declaration, String>,
+    /// Prepended to every exported type name, and so to the names of schemas and helpers,
+    /// e.g. `Ft` for `FtPlayer` and `FtPlayerSchema`. Keeps the output of several
+    /// contracts apart in one project.
+    pub prefix: String,
+    /// Wrap every export in `export namespace {name} { ... }`, for the same purpose as
+    /// `prefix`. Only supported for single-file output.
+    pub namespace: Option<String>,
+    /// When splitting files, put types in one file per Rust module they are declared in,
+    /// per `source.modules`, e.g. `game/players.ts`, instead of one file per type. Types
+    /// outside any module go in `lib.ts`.
+    pub module_files: bool,
+    pub filter: TypeFilter,
+    /// Type aliases to export as `{Alias}Schema`, and the fields that should reference them.
+    pub source: SourceInfo,
+    /// Consulted for declarations the container doesn't define, before falling back
+    /// to a `/* TODO: Resolve X */` placeholder.
+    pub resolver: Option<Arc<dyn Resolver + Send + Sync>>,
+    /// Consulted for each type and field before generating, to override single ones.
+    pub hooks: Option<Arc<dyn Hooks + Send + Sync>>,
+    /// Fail instead of writing placeholders: for declarations nothing defines, maps or
+    /// resolves, primitives of sizes zorsh has no builder for, and type aliases skipped
+    /// because their target is missing.
+    pub strict: bool,
+    /// Told about each declaration as it is generated.
+    pub progress: Option<Arc<dyn Progress + Send + Sync>>,
+}
+
+/// Returns the zorsh builder for a primitive, or `None` for sizes no builder reads.
+fn primitive_to_zorsh(declaration: &str, size: u8) -> Option<String> {
+    let builder = match declaration {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" | "f32"
+        | "f64" | "bool" => format!("b.{}()", declaration),
+        "()" => "b.unit()".to_string(),
+        _ => match size {
+            1 => "b.u8()".to_string(),
+            2 => "b.u16()".to_string(),
+            4 => "b.u32()".to_string(),
+            8 => "b.u64()".to_string(),
+            16 => "b.u128()".to_string(),
+            _ => return None,
+        },
+    };
+    Some(builder)
+}
+
+/// Returns the 64- or 128-bit integer type a primitive is encoded as, if any.
+fn large_integer(declaration: &str, size: u8) -> Option<&'static str> {
+    match (declaration, size) {
+        ("u64", _) => Some("u64"),
+        ("i64", _) => Some("i64"),
+        ("u128", _) => Some("u128"),
+        ("i128", _) => Some("i128"),
+        (_, 8) if primitive_to_ts(declaration, size) == "bigint" => Some("u64"),
+        (_, 16) if primitive_to_ts(declaration, size) == "bigint" => Some("u128"),
+        _ => None,
+    }
+}
+
+/// Returns the TypeScript type zorsh infers for a primitive, matching `primitive_to_zorsh`.
+fn primitive_to_ts(declaration: &str, size: u8) -> &'static str {
+    match declaration {
+        "u8" | "u16" | "u32" | "i8" | "i16" | "i32" | "f32" | "f64" => "number",
+        "u64" | "u128" | "i64" | "i128" => "bigint",
+        "bool" => "boolean",
+        "()" => "Record<string, never>",
+        _ => match size {
+            1 | 2 | 4 => "number",
+            8 | 16 => "bigint",
+            _ => "unknown",
+        },
+    }
+}
+
+/// Returns the hex SHA-256 of the container's borsh encoding.
+fn schema_hash(container: &BorshSchemaContainer) -> String {
+    let mut bytes = Vec::new();
+    container
+        .serialize(&mut bytes)
+        .expect("writing to a Vec can't fail");
+    Sha256::digest(&bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Formats `time` as an ISO 8601 UTC timestamp, e.g. `2025-01-31T12:00:00Z`.
+fn utc_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    // Civil date from days since the epoch, per Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Returns the statements importing `values`, as `(exported, local)` name pairs, and
+/// `types` from `module`. CommonJS imports the module with `import … = require(…)`
+/// and aliases each name, which unlike destructuring binds types as well as values.
+fn import_statement(
+    format: ModuleFormat,
+    values: &[(&str, &str)],
+    types: &[&str],
+    module: &str,
+) -> String {
+    let mut statement = String::new();
+    match format {
+        ModuleFormat::Esm => {
+            let mut names: Vec<String> = values
+                .iter()
+                .map(|&(exported, local)| match exported == local {
+                    true => local.to_string(),
+                    false => format!("{} as {}", exported, local),
+                })
+                .collect();
+            names.extend(types.iter().map(|name| format!("type {}", name)));
+            if !names.is_empty() {
+                statement = format!("import {{ {} }} from \"{}\";\n", names.join(", "), module);
+            }
+        }
+        ModuleFormat::CommonJs => {
+            if values.is_empty() && types.is_empty() {
+                return statement;
+            }
+            let locals = values
+                .iter()
+                .map(|&(_, local)| local)
+                .chain(types.iter().copied());
+            let alias = module_alias(module, locals);
+            statement = format!("import {} = require(\"{}\");\n", alias, module);
+            let names = values
+                .iter()
+                .copied()
+                .chain(types.iter().map(|&name| (name, name)));
+            for (exported, local) in names {
+                statement.push_str(&format!("import {} = {}.{};\n", local, alias, exported));
+            }
+        }
+    }
+    statement
+}
+
+/// Returns the name a CommonJS import binds `module` to: its last path segment in camel
+/// case, such as `zorsh` for `@zorsh/zorsh`, suffixed with `Module` if that is also the
+/// name of one of the `imported` bindings.
+fn module_alias<'a>(module: &str, mut imported: impl Iterator<Item = &'a str>) -> String {
+    let segment = module.rsplit('/').next().unwrap_or(module);
+    let mut alias = String::new();
+    for part in segment.split(|c: char| !c.is_ascii_alphanumeric()) {
+        match alias.is_empty() {
+            true => alias.push_str(part),
+            false => alias.push_str(&pascal_case(part)),
+        }
+    }
+    if !alias.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        alias.insert_str(0, "module");
+    } else if imported.any(|name| name == alias) {
+        alias.push_str("Module");
+    }
+    alias
+}
+
+/// Wraps the exports of a CommonJS file in a namespace assigned to `module.exports`, as
+/// TypeScript can't combine `export =` with other exports such as those of types.
+fn commonjs_module(body: &str) -> String {
+    format!(
+        "namespace exported {{\n{}\n}}\n\nexport = exported;\n",
+        body.trim_end()
+    )
+}
+
+/// Returns the `Some` payload if the variants have the shape of an `Option`.
+fn option_value(variants: &[(i64, String, Declaration)]) -> Option<&Declaration> {
+    match variants {
+        [(0, none, unit), (1, some, value)] if none == "None" && unit == "()" && some == "Some" => {
+            Some(value)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `declaration` names a type, optionally qualified by a module path like `my_crate::Config`.
+fn is_identifier(declaration: &str) -> bool {
+    declaration.split("::").all(|segment| {
+        let mut chars = unraw(segment).chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Strips the `r#` prefix of a Rust raw identifier such as `r#type`.
+fn unraw(name: &str) -> &str {
+    name.strip_prefix("r#").unwrap_or(name)
+}
+
+/// Whether `text` matches a glob `pattern` of literal characters, `*` and `?`.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // The position after the last `*`, and the text position it is currently matched up to
+    let mut star = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    star = Some((after, matched + 1));
+                    p = after;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Converts a type name such as `GameState` to `GAME_STATE`.
+fn screaming_snake_case(name: &str) -> String {
+    let mut output = String::new();
+    let chars: Vec<char> = name.chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        let starts_word = i > 0
+            && c.is_ascii_uppercase()
+            && (chars[i - 1].is_ascii_lowercase()
+                || chars[i - 1].is_ascii_digit()
+                || chars
+                    .get(i + 1)
+                    .is_some_and(|next| next.is_ascii_lowercase()));
+        if starts_word && !output.ends_with('_') {
+            output.push('_');
+        }
+        output.push(c.to_ascii_uppercase());
+    }
+    output
+}
+
+/// Converts a module path segment such as `my_crate` to `MyCrate`.
+fn pascal_case(segment: &str) -> String {
+    unraw(segment)
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
+}
+
+/// Picks TypeScript names for module-qualified declarations.
+///
+/// `my_crate::config::Config` becomes `Config`. When two declarations would share a
+/// name, each is prefixed with as many of its enclosing modules as it takes to tell
+/// them apart, e.g. `ServerConfig` and `ClientConfig`.
+fn type_names<'a>(declarations: impl Iterator<Item = &'a Declaration>) -> HashMap<String, String> {
+    let paths: Vec<(&Declaration, Vec<&str>)> = declarations
+        .filter(|declaration| is_identifier(declaration))
+        .map(|declaration| (declaration, declaration.split("::").collect()))
+        .collect();
+    let name = |segments: &[&str], depth: usize| {
+        let (modules, last) = segments[segments.len() - depth..].split_at(depth - 1);
+        modules.iter().map(|m| pascal_case(m)).collect::<String>() + unraw(last[0])
+    };
+
+    let mut depths = vec![1; paths.len()];
+    loop {
+        let mut claimed: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, (_, segments)) in paths.iter().enumerate() {
+            claimed
+                .entry(name(segments, depths[i]))
+                .or_default()
+                .push(i);
+        }
+        let mut changed = false;
+        for owners in claimed.values().filter(|owners| owners.len() > 1) {
+            for &i in owners {
+                if depths[i] < paths[i].1.len() {
+                    depths[i] += 1;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    paths
+        .iter()
+        .zip(depths)
+        .filter(|((declaration, segments), _)| segments.len() > 1 || declaration.contains("r#"))
+        .map(|((declaration, segments), depth)| (normalize(declaration), name(segments, depth)))
+        .collect()
+}
+
+/// Returns a key that is equal for variant structs with the same fields.
+fn payload_key(ty: &ir::Type) -> String {
+    match ty {
+        ir::Type::Struct(ir::Fields::Named(fields)) => fields
+            .iter()
+            .map(|field| format!("{}: {};", field.name, normalize(&field.ty)))
+            .collect(),
+        ir::Type::Struct(ir::Fields::Unnamed(elements)) => elements
+            .iter()
+            .map(|e| format!("{};", normalize(e)))
+            .collect(),
+        _ => String::new(),
+    }
+}
+
+/// Removes whitespace so that `HashMap<String,u32>` and `HashMap<String, u32>` compare equal.
+fn normalize(declaration: &str) -> String {
+    declaration.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Returns the name a declaration's generic arguments are attached to, e.g. `HashMap`.
+fn collection_name(declaration: &str) -> &str {
+    declaration.split('<').next().unwrap_or_default().trim()
+}
+
+/// Words that can't be used as identifiers in strict-mode TypeScript.
+const RESERVED_WORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "implements",
+    "interface",
+    "let",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "static",
+    "yield",
+    "await",
+];
+
+/// Whether `name` can be written bare as an object key or after a `.`.
+fn is_plain_property(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !RESERVED_WORDS.contains(&name)
+}
+
+/// Returns `name` as an object literal key, quoted if it is reserved or not an identifier.
+fn property_key(name: &str) -> String {
+    let name = unraw(name);
+    if is_plain_property(name) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name)
+    }
+}
+
+/// Returns an expression reading property `name` of `value`.
+fn property_access(value: &str, name: &str) -> String {
+    let name = unraw(name);
+    if is_plain_property(name) {
+        format!("{}.{}", value, name)
+    } else {
+        format!("{}[\"{}\"]", value, name)
+    }
+}
+
+/// Splits `HashMap<K, V>` into its key and value declarations.
+fn parse_map_types(declaration: &str) -> (String, String) {
+    let (Some(start), Some(end)) = (declaration.find('<'), declaration.rfind('>')) else {
+        return (declaration.trim().to_string(), String::new());
+    };
+    let inner = &declaration[start + 1..end];
+    let mut depth = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                return (
+                    inner[..i].trim().to_string(),
+                    inner[i + 1..].trim().to_string(),
+                );
+            }
+            _ => {}
+        }
+    }
+    (inner.trim().to_string(), String::new())
+}
+
+struct Parser<'a> {
+    container: &'a BorshSchemaContainer,
+    options: &'a GeneratorOptions,
+    /// The definitions as the emitters read them.
+    ir: &'a ir::Schema,
+    /// TypeScript names of module-qualified declarations, keyed by normalized declaration.
+    names: HashMap<String, String>,
+    /// `options.type_mappings` keyed by whitespace-normalized declaration.
+    mappings: HashMap<String, &'a TypeMapping>,
+    /// Names imported by type mappings, per module.
+    imports: BTreeMap<String, BTreeSet<String>>,
+    /// Declarations already exported as `{Type}Schema` constants.
+    generated: HashSet<Declaration>,
+    /// Structs borsh derives for enum variants; these are inlined into their enum.
+    variant_structs: HashSet<Declaration>,
+    /// Variant structs with the same fields as an earlier one, mapped to that one, which
+    /// is exported once for all of them; see `GeneratorOptions::hoist_duplicates`.
+    hoisted: HashMap<Declaration, Declaration>,
+    /// The `Enum::Variant`s sharing each hoisted variant struct.
+    shared: HashMap<Declaration, Vec<String>>,
+    /// Exported schemas that have a generated `validate{Type}` function.
+    validated: HashSet<Declaration>,
+    /// Runtime helpers the output uses, in the order they were first needed.
+    helpers: Vec<&'static str>,
+    errors: Vec<Diagnostic>,
+    /// Placeholders and other problems written into the output as comments.
+    warnings: Vec<Diagnostic>,
+    /// Runtime values exported so far, which other files import by name.
+    exports: Vec<String>,
+    /// Types exported so far, which a CommonJS `index.ts` re-exports by name.
+    types: Vec<String>,
+    /// The exports of each type, in output order.
+    chunks: Vec<Chunk>,
+    /// Declarations exported as their own schema, in output order.
+    exported: Vec<Declaration>,
+    /// Declarations referred to that nothing defines, maps or resolves.
+    unresolved: BTreeSet<Declaration>,
+    output: String,
+}
+
+/// The code exported for one type: its schema, type and any validation function.
+struct Chunk {
+    /// The Rust declaration or type alias.
+    declaration: String,
+    /// The type name.
+    name: String,
+    /// Runtime values defined by `text`.
+    exports: Vec<String>,
+    /// Types defined by `text`.
+    types: Vec<String>,
+    text: String,
+}
+
+impl<'a> Parser<'a> {
+    fn new(ir: &'a ir::Schema, options: &'a GeneratorOptions) -> Self {
+        let container = ir.container();
+        let mut variant_structs = HashSet::new();
+        let mut payloads: BTreeMap<String, Vec<(&Declaration, String)>> = BTreeMap::new();
+        for (declaration, ty) in &ir.types {
+            let ir::Type::Enum { variants, .. } = ty else {
+                continue;
+            };
+            for variant in variants {
+                let label = format!("{}::{}", declaration, unraw(&variant.name));
+                if normalize(&variant.payload)
+                    != normalize(&format!("{}{}", declaration, variant.name))
+                {
+                    continue;
+                }
+                if let Some((payload, ty)) = ir.get(&variant.payload) {
+                    // Referenced payloads are exported like any other struct, except those
+                    // zorsh writes as their only field
+                    let referenced = match ty {
+                        ir::Type::Struct(ir::Fields::Named(_)) => true,
+                        ir::Type::Struct(ir::Fields::Unnamed(elements)) => elements.len() > 1,
+                        _ => false,
+                    };
+                    if !(referenced && options.inlining == Inlining::Reference) {
+                        variant_structs.insert(payload.to_string());
+                    }
+                    if referenced {
+                        payloads
+                            .entry(payload_key(ty))
+                            .or_default()
+                            .push((payload, label));
+                    }
+                }
+            }
+        }
+
+        let mut hoisted = HashMap::new();
+        let mut shared = HashMap::new();
+        if options.hoist_duplicates && options.inlining == Inlining::Auto {
+            for mut group in payloads.into_values().filter(|group| group.len() > 1) {
+                group.sort();
+                let first = group[0].0;
+                for (variant, _) in &group {
+                    hoisted.insert(variant.to_string(), first.to_string());
+                }
+                shared.insert(
+                    first.to_string(),
+                    group.into_iter().map(|(_, label)| label).collect(),
+                );
+            }
+        }
+
+        let mappings = options
+            .type_mappings
+            .iter()
+            .map(|(declaration, mapping)| (normalize(declaration), mapping))
+            .collect();
+
+        let mut errors = Vec::new();
+        let mut names = type_names(container.definitions().map(|(declaration, _)| declaration));
+        if !options.prefix.is_empty() && !is_plain_property(&format!("{}A", options.prefix)) {
+            errors.push(Diagnostic::new(
+                None,
+                format!(
+                    "`{}` can't prefix exported names, which must be TypeScript identifiers",
+                    options.prefix
+                ),
+            ));
+        }
+        if let Some(namespace) = options.namespace.as_ref().filter(|n| !is_plain_property(n)) {
+            errors.push(Diagnostic::new(
+                None,
+                format!(
+                    "`{}` can't name a namespace, which must be a TypeScript identifier",
+                    namespace
+                ),
+            ));
+        }
+        if options.roundtrip_spec && options.output_mode == OutputMode::Declarations {
+            errors.push(Diagnostic::new(
+                None,
+                "a roundtrip spec needs schemas to test, which declaration-only output doesn't have"
+                    .to_string(),
+            ));
+        }
+        for (declaration, name) in &options.renames {
+            if is_plain_property(name) {
+                names.insert(normalize(declaration), name.clone());
+            } else {
+                errors.push(Diagnostic::new(
+                    None,
+                    format!(
+                        "`{}` can't be renamed to `{}`, which isn't a TypeScript identifier",
+                        declaration, name
+                    ),
+                ));
+            }
+        }
+        let mut claimed: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (declaration, _) in container.definitions() {
+            if is_identifier(declaration) {
+                let name = names
+                    .get(&normalize(declaration))
+                    .map_or(declaration.as_str(), |n| n);
+                claimed.entry(name).or_default().push(declaration);
+            }
+        }
+        for (name, owners) in claimed {
+            let renamed = owners.iter().any(|owner| {
+                options
+                    .renames
+                    .keys()
+                    .any(|d| normalize(d) == normalize(owner))
+            });
+            if let [first @ .., last] = &owners[..] {
+                if !first.is_empty() && renamed {
+                    errors.push(Diagnostic::new(
+                        None,
+                        format!(
+                            "`{}` and `{}` would both be exported as `{}`",
+                            first.join("`, `"),
+                            last,
+                            name
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Self {
+            container,
+            options,
+            ir,
+            names,
+            mappings,
+            imports: BTreeMap::new(),
+            generated: HashSet::new(),
+            variant_structs,
+            hoisted,
+            shared,
+            validated: HashSet::new(),
+            helpers: Vec::new(),
+            errors,
+            warnings: Vec::new(),
+            exports: Vec::new(),
+            types: Vec::new(),
+            chunks: Vec::new(),
+            exported: Vec::new(),
+            unresolved: BTreeSet::new(),
+            output: String::new(),
+        }
+    }
+
+    /// Reports a placeholder, once: as an error if `strict` is set, otherwise as a warning.
+    fn strict_error(&mut self, declaration: &str, message: String) {
+        let found = match self.options.strict {
+            true => &mut self.errors,
+            false => &mut self.warnings,
+        };
+        if !found.iter().any(|d| d.message == message) {
+            found.push(Diagnostic {
+                placeholder: true,
+                ..Diagnostic::new(Some(declaration), message)
+            });
+        }
+    }
+
+    /// Generates every export, failing if anything couldn't be represented.
+    fn run(&mut self) -> Result<(), ZorshGenError> {
+        // Type aliases, unless nothing in this container could back them
+        let options = self.options;
+        for (alias, target) in &options.source.aliases {
+            if self.resolve(target).is_some() || self.mappings.contains_key(&normalize(target)) {
+                self.parse_alias(alias);
+            } else {
+                self.strict_error(
+                    alias,
+                    format!(
+                        "type alias `{}` was skipped, since nothing defines or maps `{}`",
+                        alias, target
+                    ),
+                );
+            }
+        }
+
+        // Structs and enums, each after the definitions it refers to
+        let order = self.export_order();
+        if options.inlining == Inlining::Inline && !self.errors.is_empty() {
+            // Inlining a type that refers to itself would never end
+            return Err(self.failure());
+        }
+        let total = order.len();
+        for (index, declaration) in order.into_iter().enumerate() {
+            if let Some(progress) = &options.progress {
+                progress.declaration(index + 1, total, declaration);
+            }
+            self.parse_type(declaration);
+        }
+
+        // Finally the root type, if it wasn't emitted above
+        let root = self.container.declaration();
+        if !self.generated.contains(root) && options.filter.matches(root) {
+            let schema = self.parse_type(root);
+            let checks = self.parse_validation(root, "value", root, 0);
+            self.push_export(root, &schema, checks);
+        }
+
+        if !self.errors.is_empty() {
+            return Err(self.failure());
+        }
+        Ok(())
+    }
+
+    /// Generates a single file containing every export.
+    fn parse(&mut self) -> Result<String, ZorshGenError> {
+        self.run()?;
+        if self.options.output_mode == OutputMode::Declarations {
+            let output = self.banner() + &self.namespaced(&self.output);
+            return Ok(format::format(&output, &self.options.format));
+        }
+
+        let format = self.options.module_format;
+        let runtime = &self.options.runtime;
+        if self.options.export_style == ExportStyle::Object {
+            let object = self.schema_object();
+            self.output.push_str(&object);
+        }
+        if self.options.hash_constant {
+            let (name, constant) = self.hash_constant();
+            self.exports.insert(0, name);
+            self.output.insert_str(0, &constant);
+        }
+        if self.options.schema_registry {
+            self.exports.push("schemaRegistry".to_string());
+            let registry = self.schema_registry();
+            self.output.push_str(&registry);
+        }
+        let types: &[&str] = if self.needs_schema_type() {
+            &["Schema"]
+        } else {
+            &[]
+        };
+        let mut output = self.banner();
+        output += &import_statement(
+            format,
+            &[(runtime.binding.as_str(), "b")],
+            types,
+            &runtime.module,
+        );
+        for (module, names) in &self.imports {
+            let names: Vec<(&str, &str)> = names.iter().map(|n| (n.as_str(), n.as_str())).collect();
+            output.push_str(&import_statement(format, &names, &[], module));
+        }
+        output.push('\n');
+        for helper in &self.helpers {
+            output.push_str(helper);
+        }
+        let body = self.namespaced(&self.output);
+        output.push_str(&match format {
+            ModuleFormat::Esm => body,
+            // Helpers stay private to the file, outside the exported namespace
+            ModuleFormat::CommonJs => commonjs_module(&body),
+        });
+        Ok(format::format(&output, &self.options.format))
+    }
+
+    /// Returns the banner comment, or nothing if it's disabled.
+    fn banner(&self) -> String {
+        let Some(banner) = &self.options.banner else {
+            return String::new();
+        };
+        let mut lines = vec![format!(
+            "Generated by zorsh-gen {}. Do not edit by hand.",
+            env!("CARGO_PKG_VERSION")
+        )];
+        if let Some(source) = &banner.source {
+            lines.push(format!("Source: {}", source));
+        }
+        lines.push(format!(
+            "Schema hash: sha256:{}",
+            schema_hash(self.container)
+        ));
+        if banner.timestamp {
+            lines.push(format!(
+                "Generated at: {}",
+                utc_timestamp(SystemTime::now())
+            ));
+        }
+        let lines: Vec<String> = lines.iter().map(|line| format!("// {}\n", line)).collect();
+        lines.concat() + "\n"
+    }
+
+    /// Whether a type is exported as its own schema rather than inlined where it's used.
+    fn is_exported(&self, declaration: &str, ty: &ir::Type) -> bool {
+        if self.options.inlining == Inlining::Inline {
+            return declaration == self.container.declaration();
+        }
+        match ty {
+            ir::Type::Struct(ir::Fields::Named(_) | ir::Fields::Unnamed(_)) => {
+                !self.variant_structs.contains(declaration)
+            }
+            ir::Type::Enum { .. } => is_identifier(declaration),
+            _ => false,
+        }
+    }
+
+    /// Looks up a declaration's type regardless of whitespace.
+    fn ty(&self, declaration: &str) -> Option<&'a ir::Type> {
+        self.ir.get(declaration).map(|(_, ty)| ty)
+    }
+
+    /// Orders the exported definitions so that each comes after every definition it
+    /// refers to, visiting definitions and their references in a fixed order so the
+    /// output is the same on every run.
+    ///
+    /// Reference cycles are reported as errors: schemas are plain constants, so one
+    /// can't refer to a schema that is defined after it.
+    fn export_order(&mut self) -> Vec<&'a Declaration> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        let mut path = Vec::new();
+        for (declaration, ty) in &self.ir.types {
+            let Some((declaration, _)) = self.resolve(declaration) else {
+                continue;
+            };
+            if self.is_exported(declaration, ty) && self.options.filter.matches(declaration) {
+                self.visit(declaration, &mut visited, &mut path, &mut order);
+            }
+        }
+        order
+    }
+
+    fn visit(
+        &mut self,
+        declaration: &'a Declaration,
+        visited: &mut HashSet<&'a Declaration>,
+        path: &mut Vec<&'a Declaration>,
+        order: &mut Vec<&'a Declaration>,
+    ) {
+        if let Some(start) = path.iter().position(|d| *d == declaration) {
+            let mut cycle: Vec<&str> = path[start..].iter().map(|d| d.as_str()).collect();
+            cycle.push(declaration);
+            self.error(
+                declaration,
+                format!(
+                    "type `{}` refers to itself ({}), which zorsh cannot represent",
+                    declaration,
+                    cycle.join(" -> ")
+                ),
+            );
+            return;
+        }
+        if self.mappings.contains_key(&normalize(declaration)) || !visited.insert(declaration) {
+            return;
+        }
+        let Some(ty) = self.ir.types.get(declaration) else {
+            return;
+        };
+
+        path.push(declaration);
+        for dependency in ty.references() {
+            if let Some((dependency, _)) = self.resolve(dependency) {
+                self.visit(dependency, visited, path, order);
+            }
+        }
+        path.pop();
+
+        if self.is_exported(declaration, ty) {
+            order.push(declaration);
+        }
+    }
+
+    /// Returns the name a declaration is exported under, without the `Schema` suffix.
+    fn type_name(&self, declaration: &str) -> String {
+        let name = self
+            .names
+            .get(&normalize(declaration))
+            .map_or(declaration, |name| name.as_str());
+        format!("{}{}", self.options.prefix, name)
+    }
+
+    /// Returns the name of the schema constant exported for a declaration.
+    fn schema_name(&self, declaration: &str) -> String {
+        self.options
+            .schema_naming
+            .apply(&self.type_name(declaration))
+    }
+
+    /// Returns the JSDoc lines for an exported declaration or type alias.
+    fn docs(&self, declaration: &str) -> Vec<String> {
+        let mut docs = self
+            .options
+            .source
+            .docs
+            .get(declaration)
+            .cloned()
+            .unwrap_or_default();
+        if let Some(users) = self.shared.get(declaration) {
+            if !docs.is_empty() {
+                docs.push(String::new());
+            }
+            let (last, first) = users
+                .split_last()
+                .expect("payloads are shared by several variants");
+            docs.push(format!(
+                "The payload of `{}` and `{}`.",
+                first.join("`, `"),
+                last
+            ));
+        }
+        if self.options.rust_type_docs {
+            if !docs.is_empty() {
+                docs.push(String::new());
+            }
+            match self.options.source.aliases.get(declaration) {
+                Some(target) => docs.push(format!("Rust: type {} = {}", declaration, target)),
+                None => docs.push(format!("Rust: {}", declaration)),
+            }
+        }
+        docs
+    }
+
+    /// Returns the JSDoc lines for a struct field or enum variant.
+    fn member_docs(&self, declaration: &str, member: &str) -> Vec<String> {
+        let key = (declaration.to_string(), member.to_string());
+        let docs = self.options.source.member_docs.get(&key);
+        docs.cloned().unwrap_or_default()
+    }
+
+    /// Returns the JSDoc lines for field `name` of type `field` in `declaration`.
+    fn field_docs(&self, declaration: &str, name: &str, field: &str) -> Vec<String> {
+        let mut docs = self.member_docs(declaration, name);
+        if self.options.rust_type_docs {
+            if !docs.is_empty() {
+                docs.push(String::new());
+            }
+            docs.push(format!("Rust: {}", field));
+        }
+        docs
+    }
+
+    /// Returns the TypeScript name of a struct field, in the configured case.
+    fn field_name(&self, name: &str) -> String {
+        self.options.field_case.apply(unraw(name))
+    }
+
+    fn use_helper(&mut self, helper: &'static str) {
+        if !self.helpers.contains(&helper) {
+            self.helpers.push(helper);
+        }
+    }
+
+    /// Emits a struct or tuple struct as an exported schema constant and type.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    fn parse_definition(&mut self, declaration: &str) {
+        if self.mappings.contains_key(&normalize(declaration)) {
+            debug!("mapped, so not exported");
+            return;
+        }
+        if !self.generated.insert(declaration.to_string()) {
+            debug!("already exported");
+            return;
+        }
+        if let Some((schema, checks)) = self.definition_schema(declaration) {
+            debug!(schema, "exported");
+            self.push_export(declaration, &schema, checks);
+        }
+    }
+
+    /// Returns the schema of a struct or enum, written out in full, and the checks
+    /// validating its values.
+    fn definition_schema(&mut self, declaration: &str) -> Option<(String, Vec<String>)> {
+        let mut checks = Vec::new();
+        let schema = match self.ir.get(declaration)?.1 {
+            ir::Type::Struct(ir::Fields::Named(fields)) => {
+                let mut schema = String::from("b.struct({\n");
+                for ir::Field { name, ty: field } in fields {
+                    let schema_field = self.parse_field(declaration, name, field);
+                    let renamed = self.field_name(name);
+                    schema.push_str(&jsdoc(&self.field_docs(declaration, name, field), "  "));
+                    schema.push_str(&format!("  {}: {},", property_key(&renamed), schema_field));
+                    if renamed != unraw(name) {
+                        schema.push_str(&format!(" // {}", unraw(name)));
+                    }
+                    schema.push('\n');
+                    let value = property_access("value", &renamed);
+                    let path = format!("{}.{}", self.type_name(declaration), renamed);
+                    checks.extend(self.parse_validation(field, &value, &path, 0));
+                }
+                schema.push_str("})");
+                schema
+            }
+            ir::Type::Struct(ir::Fields::Unnamed(elements))
+                if elements.len() == 1 && self.options.newtype_mode != NewtypeMode::Tuple =>
+            {
+                let path = format!("{}[0]", self.type_name(declaration));
+                checks = self.parse_validation(&elements[0], "value", &path, 0);
+                let inner = self.parse_type(&elements[0]);
+                if self.options.newtype_mode == NewtypeMode::Branded {
+                    self.use_helper(BRAND_HELPER);
+                    format!("branded({}, \"{}\")", inner, self.type_name(declaration))
+                } else {
+                    inner
+                }
+            }
+            ir::Type::Struct(ir::Fields::Unnamed(elements)) => {
+                let mut schema_elements = Vec::new();
+                for (i, element) in elements.iter().enumerate() {
+                    schema_elements.push(self.parse_field(declaration, &i.to_string(), element));
+                    let value = format!("value[{}]", i);
+                    let path = format!("{}[{}]", self.type_name(declaration), i);
+                    checks.extend(self.parse_validation(element, &value, &path, 0));
+                }
+                format!("b.tuple({})", schema_elements.join(", "))
+            }
+            ty @ ir::Type::Enum { variants, .. } => {
+                let schema = self.parse_enum(declaration, variants, true);
+                let path = self.type_name(declaration);
+                checks = self.parse_definition_validation(ty, declaration, "value", &path, 0);
+                schema
+            }
+            _ => return None,
+        };
+        Some((schema, checks))
+    }
+
+    /// Returns the schema for a struct field, referencing its type alias if it was declared with one.
+    fn parse_field(&mut self, declaration: &str, name: &str, field: &str) -> String {
+        let key = (declaration.to_string(), name.to_string());
+        match self.options.source.field_aliases.get(&key) {
+            Some(alias) => self.parse_alias(alias),
+            None => self.parse_type(field),
+        }
+    }
+
+    /// Exports a type alias as `{Alias}Schema` if it hasn't been yet, and returns its name.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    fn parse_alias(&mut self, alias: &str) -> String {
+        let Some(target) = self.options.source.aliases.get(alias) else {
+            self.unresolved.insert(alias.to_string());
+            self.strict_error(
+                alias,
+                format!("type alias `{}` isn't defined in the source", alias),
+            );
+            return format!("/* TODO: Resolve {} */", alias);
+        };
+        if self.generated.insert(alias.to_string()) {
+            let schema = self.parse_type(target);
+            let checks = self.parse_validation(target, "value", alias, 0);
+            self.push_export(alias, &schema, checks);
+        }
+        self.schema_name(alias)
+    }
+
+    /// Returns the `b.enum` expression for a tagged union, one variant per line if `multiline`.
+    fn parse_enum(
+        &mut self,
+        declaration: &str,
+        variants: &[ir::Variant],
+        multiline: bool,
+    ) -> String {
+        // zorsh numbers variants by position, so they must be in discriminant order,
+        // as the IR keeps them, without gaps.
+        let contiguous = variants
+            .iter()
+            .enumerate()
+            .all(|(index, variant)| variant.discriminant == index as i64);
+        if !contiguous {
+            self.error(
+                declaration,
+                format!(
+                    "enum `{}` has non-sequential discriminants, which zorsh cannot represent",
+                    declaration
+                ),
+            );
+        }
+
+        if multiline && !variants.is_empty() {
+            let mut schema = String::from("b.enum({\n");
+            for ir::Variant { name, payload, .. } in variants {
+                let payload = self.parse_variant(payload);
+                schema.push_str(&jsdoc(&self.member_docs(declaration, name), "  "));
+                schema.push_str(&format!("  {}: {},\n", property_key(name), payload));
+            }
+            schema.push_str("})");
+            return schema;
+        }
+        let variants: Vec<String> = variants
+            .iter()
+            .map(|variant| {
+                let payload = self.parse_variant(&variant.payload);
+                format!("{}: {}", property_key(&variant.name), payload)
+            })
+            .collect();
+        if variants.is_empty() {
+            "b.enum({})".to_string()
+        } else {
+            format!("b.enum({{ {} }})", variants.join(", "))
+        }
+    }
+
+    /// Looks up a declaration regardless of whitespace, returning the container's spelling of it.
+    fn resolve(&self, declaration: &str) -> Option<(&'a Declaration, &'a ir::Type)> {
+        self.ir.get(declaration)
+    }
+
+    /// Returns the zorsh expression for a declaration.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), ret)
+    )]
+    fn parse_type(&mut self, declaration: &str) -> String {
+        if let Some(mapping) = self.mappings.get(&normalize(declaration)) {
+            debug!(?mapping, "mapped");
+            return match mapping {
+                TypeMapping::Expression(expression) => expression.clone(),
+                TypeMapping::Import { module, name } => {
+                    let names = self.imports.entry(module.clone()).or_default();
+                    names.insert(name.clone());
+                    name.clone()
+                }
+            };
+        }
+        let Some((declaration, ty)) = self.ir.get(declaration) else {
+            let custom = self.options.resolver.as_ref();
+            if let Some(schema) = custom.and_then(|resolver| resolver.resolve(declaration)) {
+                debug!("resolved by the resolver");
+                return schema;
+            }
+            debug!("not defined, mapped or resolved");
+            self.unresolved.insert(declaration.to_string());
+            self.strict_error(
+                declaration,
+                format!(
+                    "`{}` isn't defined in the schema container, mapped or resolved",
+                    declaration
+                ),
+            );
+            return format!("/* TODO: Resolve {} */", declaration);
+        };
+        if self.generated.contains(declaration) {
+            debug!("already exported");
+            return self.schema_name(declaration);
+        }
+        self.type_schema(declaration, ty)
+    }
+
+    /// Returns the zorsh builder expression for a type.
+    fn type_schema(&mut self, declaration: &str, ty: &'a ir::Type) -> String {
+        match ty {
+            ir::Type::Primitive(size) => self.parse_primitive(declaration, *size),
+            ir::Type::String(length) => {
+                self.parse_length(declaration, length);
+                format!("b.string({})", length_options(length).unwrap_or_default())
+            }
+            ir::Type::Bytes(length) => match self.parse_length(declaration, length) {
+                Some(length) => format!("b.bytes({})", length),
+                None => format!("b.bytes({})", length_options(length).unwrap_or_default()),
+            },
+            ir::Type::Sequence { element, length } => {
+                match self.parse_length(declaration, length) {
+                    Some(fixed) => format!("b.array({}, {})", self.parse_type(element), fixed),
+                    None => {
+                        let mut arguments = vec![self.parse_type(element)];
+                        arguments.extend(length_options(length));
+                        format!("b.vec({})", arguments.join(", "))
+                    }
+                }
+            }
+            ir::Type::Map { key, value, length } => {
+                self.parse_length(declaration, length);
+                let mut arguments = vec![self.parse_type(key), self.parse_type(value)];
+                arguments.extend(length_options(length));
+                format!("b.hashMap({})", arguments.join(", "))
+            }
+            ir::Type::Set { element, length } => {
+                self.parse_length(declaration, length);
+                let mut arguments = vec![self.parse_type(element)];
+                arguments.extend(length_options(length));
+                format!("b.hashSet({})", arguments.join(", "))
+            }
+            ir::Type::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| self.parse_type(e)).collect();
+                format!("b.tuple({})", elements.join(", "))
+            }
+            ir::Type::Option(value) => format!("b.option({})", self.parse_option_value(value)),
+            ir::Type::Enum {
+                tag_width,
+                variants,
+            } => {
+                if *tag_width != 1 {
+                    let message = match tag_width {
+                        0 => format!(
+                            "enum `{}` is untagged, which zorsh cannot represent",
+                            declaration
+                        ),
+                        _ => format!(
+                            "enum `{}` uses a {}-byte tag, but zorsh enums are encoded with a u8 tag",
+                            declaration, tag_width
+                        ),
+                    };
+                    self.error(declaration, message);
+                }
+                if self.is_exported(declaration, ty) {
+                    self.parse_definition(declaration);
+                    return self.schema_name(declaration);
+                }
+                if self.options.inlining == Inlining::Inline && is_identifier(declaration) {
+                    return self.definition_schema(declaration).unwrap_or_default().0;
+                }
+                self.parse_enum(declaration, variants, false)
+            }
+            ir::Type::Struct(ir::Fields::Empty) => "b.unit()".to_string(),
+            ir::Type::Struct(_) if self.is_exported(declaration, ty) => {
+                self.parse_definition(declaration);
+                self.schema_name(declaration)
+            }
+            ir::Type::Struct(_) => self.definition_schema(declaration).unwrap_or_default().0,
+        }
+    }
+
+    /// Returns the length of an array, reporting sequences whose length zorsh can't
+    /// encode.
+    fn parse_length(&mut self, declaration: &str, length: &ir::Length) -> Option<u64> {
+        if let Some(fixed) = length.fixed() {
+            return Some(fixed);
+        }
+        match length.width {
+            Definition::ARRAY_LENGTH_WIDTH => self.error(declaration, format!(
+                "sequence `{}` has no length prefix and a variable length, which zorsh cannot represent",
+                declaration
+            )),
+            1 | 2 | 4 | 8 => {}
+            width => self.error(declaration, format!(
+                "sequence `{}` uses a {}-byte length prefix, but zorsh length prefixes are 1, 2, 4 or 8 bytes",
+                declaration, width
+            )),
+        }
+        None
+    }
+
+    /// Returns the schema of a primitive, converting large integers if configured to.
+    fn parse_primitive(&mut self, declaration: &str, size: u8) -> String {
+        let Some(schema) = primitive_to_zorsh(declaration, size) else {
+            self.strict_error(
+                declaration,
+                format!(
+                    "primitive `{}` is {} bytes wide, which no zorsh builder reads",
+                    declaration, size
+                ),
+            );
+            return format!("/* TODO: Read {}-byte primitive {} */", size, declaration);
+        };
+        match (
+            self.options.large_integers,
+            large_integer(declaration, size),
+        ) {
+            (LargeIntegers::Number, Some("u64" | "i64")) => {
+                self.use_helper(SAFE_NUMBER_HELPER);
+                format!("safeNumber({})", schema)
+            }
+            (LargeIntegers::String, Some(_)) => {
+                self.use_helper(DECIMAL_STRING_HELPER);
+                format!("decimalString({})", schema)
+            }
+            _ => schema,
+        }
+    }
+
+    /// Returns the TypeScript type of a primitive, matching `parse_primitive`.
+    fn primitive_ts(&self, declaration: &str, size: u8) -> &'static str {
+        match (
+            self.options.large_integers,
+            large_integer(declaration, size),
+        ) {
+            (LargeIntegers::Number, Some("u64" | "i64")) => "number",
+            (LargeIntegers::String, Some(_)) => "string",
+            _ => primitive_to_ts(declaration, size),
+        }
+    }
+
+    /// Returns the payload expression of an `Option`.
+    ///
+    /// A nested `Option` payload is spelled out as an explicit `None`/`Some` enum,
+    /// which has the same encoding, so that `Some(None)` doesn't collapse into the
+    /// same `null` as `None`.
+    fn parse_option_value(&mut self, declaration: &str) -> String {
+        if let Some(ir::Type::Option(value)) = self.ty(declaration) {
+            return format!(
+                "b.enum({{ None: b.unit(), Some: {} }})",
+                self.parse_option_value(value)
+            );
+        }
+        self.parse_type(declaration)
+    }
+
+    /// Returns the payload expression for an enum variant.
+    fn parse_variant(&mut self, declaration: &str) -> String {
+        let declaration = self.resolve(declaration).map_or(declaration, |(d, _)| d);
+        if !self.variant_structs.contains(declaration) {
+            return self.parse_type(declaration);
+        }
+        if let Some(shared) = self.hoisted.get(declaration).cloned() {
+            if self.generated.insert(shared.clone()) {
+                let schema = self.variant_schema(&shared);
+                let path = self.type_name(&shared);
+                let checks = self.parse_fields_validation(&shared, "value", &path, 0);
+                self.push_export(&shared, &schema, checks);
+            }
+            return self.schema_name(&shared);
+        }
+        self.variant_schema(declaration)
+    }
+
+    /// Returns the payload expression of a variant struct, written out in full.
+    fn variant_schema(&mut self, declaration: &str) -> String {
+        match self.ir.get(declaration) {
+            Some((_, ir::Type::Struct(fields))) => match fields {
+                ir::Fields::Named(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|field| {
+                            let name = self.field_name(&field.name);
+                            format!("{}: {}", property_key(&name), self.parse_type(&field.ty))
+                        })
+                        .collect();
+                    format!("b.struct({{ {} }})", fields.join(", "))
+                }
+                ir::Fields::Unnamed(elements) if elements.len() == 1 => {
+                    self.parse_type(&elements[0])
+                }
+                ir::Fields::Unnamed(elements) => {
+                    let elements: Vec<String> =
+                        elements.iter().map(|e| self.parse_type(e)).collect();
+                    format!("b.tuple({})", elements.join(", "))
+                }
+                ir::Fields::Empty => "b.unit()".to_string(),
+            },
+            _ => self.parse_type(declaration),
+        }
+    }
+
+    /// Returns the exported `interface` or `type` declaration for a declaration or type alias.
+    fn type_declaration(&self, declaration: &str) -> String {
+        let name = self.type_name(declaration);
+        if let Some(target) = self.options.source.aliases.get(declaration) {
+            return format!("export type {} = {};\n\n", name, self.parse_ts_type(target));
+        }
+        let ty = match self.ty(declaration) {
+            Some(ir::Type::Struct(ir::Fields::Named(fields))) => {
+                let mut output = format!("export interface {} {{\n", name);
+                for ir::Field {
+                    name: field_name,
+                    ty: field,
+                } in fields
+                {
+                    output.push_str(&jsdoc(
+                        &self.field_docs(declaration, field_name, field),
+                        "  ",
+                    ));
+                    output.push_str(&format!(
+                        "  {}{}: {};\n",
+                        self.readonly(),
+                        property_key(&self.field_name(field_name)),
+                        self.parse_ts_field(declaration, field_name, field)
+                    ));
+                }
+                output.push_str("}\n\n");
+                return output;
+            }
+            Some(ir::Type::Struct(ir::Fields::Unnamed(elements)))
+                if elements.len() == 1 && self.options.newtype_mode != NewtypeMode::Tuple =>
+            {
+                let inner = self.parse_ts_field(declaration, "0", &elements[0]);
+                if self.options.newtype_mode == NewtypeMode::Branded {
+                    format!("{} & {{ readonly __brand: \"{}\" }}", inner, name)
+                } else {
+                    inner
+                }
+            }
+            Some(ir::Type::Struct(ir::Fields::Unnamed(elements))) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| self.parse_ts_field(declaration, &i.to_string(), element))
+                    .collect();
+                self.ts_tuple(&elements)
+            }
+            Some(ir::Type::Enum { variants, .. }) => {
+                if variants.is_empty() {
+                    return format!("export type {} = never;\n\n", name);
+                }
+                let variants: String = variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "\n{}  | {{ {}{}: {} }}",
+                            jsdoc(&self.member_docs(declaration, &variant.name), "  "),
+                            self.readonly(),
+                            property_key(&variant.name),
+                            self.parse_ts_variant(&variant.payload)
+                        )
+                    })
+                    .collect();
+                return format!("export type {} ={};\n\n", name, variants);
+            }
+            _ => self.parse_ts_definition(declaration),
+        };
+        format!("export type {} = {};\n\n", name, ty)
+    }
+
+    /// Returns the TypeScript type of a struct field, referencing its type alias if it has one.
+    fn parse_ts_field(&self, declaration: &str, name: &str, field: &str) -> String {
+        let key = (declaration.to_string(), name.to_string());
+        match self.options.source.field_aliases.get(&key) {
+            Some(alias) if self.options.source.aliases.contains_key(alias) => self.type_name(alias),
+            _ => self.parse_ts_type(field),
+        }
+    }
+
+    /// Returns the TypeScript type zorsh infers for a declaration, matching `parse_type`.
+    fn parse_ts_type(&self, declaration: &str) -> String {
+        if let Some(mapping) = self.mappings.get(&normalize(declaration)) {
+            return match mapping {
+                TypeMapping::Import { name, .. }
+                    if self.options.output_mode == OutputMode::Schemas =>
+                {
+                    format!("b.infer<typeof {}>", name)
+                }
+                _ => "unknown".to_string(),
+            };
+        }
+        let Some((declaration, _)) = self.ir.get(declaration) else {
+            return match self.options.resolver {
+                Some(_) => "unknown".to_string(),
+                None => format!("unknown /* TODO: Resolve {} */", declaration),
+            };
+        };
+        if self.generated.contains(declaration) {
+            return self.type_name(declaration);
+        }
+        self.parse_ts_definition(declaration)
+    }
+
+    /// Returns the TypeScript type of a definition, even if it is exported.
+    fn parse_ts_definition(&self, declaration: &str) -> String {
+        let Some(ty) = self.ty(declaration) else {
+            return self.parse_ts_type(declaration);
+        };
+        match ty {
+            ir::Type::Primitive(size) => self.primitive_ts(declaration, *size).to_string(),
+            ir::Type::String(_) => "string".to_string(),
+            ir::Type::Bytes(_) => "Uint8Array".to_string(),
+            ir::Type::Sequence { element, .. } => format!(
+                "{}Array<{}>",
+                self.readonly_collection(),
+                self.parse_ts_type(element)
+            ),
+            ir::Type::Map { key, value, .. } => format!(
+                "{}Map<{}, {}>",
+                self.readonly_collection(),
+                self.parse_ts_type(key),
+                self.parse_ts_type(value)
+            ),
+            ir::Type::Set { element, .. } => format!(
+                "{}Set<{}>",
+                self.readonly_collection(),
+                self.parse_ts_type(element)
+            ),
+            ir::Type::Tuple(elements) => {
+                let elements: Vec<String> =
+                    elements.iter().map(|e| self.parse_ts_type(e)).collect();
+                self.ts_tuple(&elements)
+            }
+            ir::Type::Option(value) => format!("{} | null", self.parse_ts_option_value(value)),
+            ir::Type::Enum { variants, .. } => {
+                if variants.is_empty() {
+                    return "never".to_string();
+                }
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "{{ {}{}: {} }}",
+                            self.readonly(),
+                            property_key(&variant.name),
+                            self.parse_ts_variant(&variant.payload)
+                        )
+                    })
+                    .collect();
+                format!("({})", variants.join(" | "))
+            }
+            ir::Type::Struct(fields) => match fields {
+                ir::Fields::Empty => "Record<string, never>".to_string(),
+                _ if self.is_exported(declaration, ty) => self.type_name(declaration),
+                ir::Fields::Named(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|field| {
+                            format!(
+                                "{}{}: {}",
+                                self.readonly(),
+                                property_key(&self.field_name(&field.name)),
+                                self.parse_ts_field(declaration, &field.name, &field.ty)
+                            )
+                        })
+                        .collect();
+                    format!("{{ {} }}", fields.join("; "))
+                }
+                ir::Fields::Unnamed(elements)
+                    if elements.len() == 1 && self.options.newtype_mode != NewtypeMode::Tuple =>
+                {
+                    let inner = self.parse_ts_field(declaration, "0", &elements[0]);
+                    match self.options.newtype_mode {
+                        NewtypeMode::Branded => format!(
+                            "({} & {{ readonly __brand: \"{}\" }})",
+                            inner,
+                            self.type_name(declaration)
+                        ),
+                        _ => inner,
+                    }
+                }
+                ir::Fields::Unnamed(elements) => {
+                    let elements: Vec<String> = elements
+                        .iter()
+                        .enumerate()
+                        .map(|(i, e)| self.parse_ts_field(declaration, &i.to_string(), e))
+                        .collect();
+                    self.ts_tuple(&elements)
+                }
+            },
+        }
+    }
+
+    /// Returns the TypeScript type of an `Option` payload, matching `parse_option_value`.
+    fn parse_ts_option_value(&self, declaration: &str) -> String {
+        if let Some(ir::Type::Option(value)) = self.ty(declaration) {
+            return format!(
+                "({{ {0}None: Record<string, never> }} | {{ {0}Some: {1} }})",
+                self.readonly(),
+                self.parse_ts_option_value(value)
+            );
+        }
+        let ty = self.parse_ts_type(declaration);
+        if ty.contains(" | ") && !ty.starts_with('(') {
+            format!("({})", ty)
+        } else {
+            ty
+        }
+    }
+
+    /// Returns the TypeScript type of an enum variant payload, matching `parse_variant`.
+    fn parse_ts_variant(&self, declaration: &str) -> String {
+        let declaration = self.resolve(declaration).map_or(declaration, |(d, _)| d);
+        if !self.variant_structs.contains(declaration) {
+            return self.parse_ts_type(declaration);
+        }
+        if let Some(shared) = self.hoisted.get(declaration) {
+            if self.generated.contains(shared) {
+                return self.type_name(shared);
+            }
+        }
+        match self.ty(declaration) {
+            Some(ir::Type::Struct(fields)) => match fields {
+                ir::Fields::Named(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|field| {
+                            format!(
+                                "{}{}: {}",
+                                self.readonly(),
+                                property_key(&self.field_name(&field.name)),
+                                self.parse_ts_type(&field.ty)
+                            )
+                        })
+                        .collect();
+                    format!("{{ {} }}", fields.join("; "))
+                }
+                ir::Fields::Unnamed(elements) if elements.len() == 1 => {
+                    self.parse_ts_type(&elements[0])
+                }
+                ir::Fields::Unnamed(elements) => {
+                    let elements: Vec<String> =
+                        elements.iter().map(|e| self.parse_ts_type(e)).collect();
+                    self.ts_tuple(&elements)
+                }
+                ir::Fields::Empty => "Record<string, never>".to_string(),
+            },
+            _ => self.parse_ts_type(declaration),
+        }
+    }
+
+    /// Returns the modifier for properties of exported types, given `readonly_types`.
+    fn readonly(&self) -> &'static str {
+        match self.options.readonly_types {
+            true => "readonly ",
+            false => "",
+        }
+    }
+
+    /// Returns the prefix of `Array`, `Map` and `Set`, given `readonly_types`.
+    fn readonly_collection(&self) -> &'static str {
+        match self.options.readonly_types {
+            true => "Readonly",
+            false => "",
+        }
+    }
+
+    /// Returns a tuple type of `elements`.
+    fn ts_tuple(&self, elements: &[String]) -> String {
+        format!("{}[{}]", self.readonly(), elements.join(", "))
+    }
+
+    /// Returns TypeScript statements checking the length bounds of sequences in `value`.
+    fn parse_validation(
+        &self,
+        declaration: &str,
+        value: &str,
+        path: &str,
+        depth: usize,
+    ) -> Vec<String> {
+        if self.mappings.contains_key(&normalize(declaration)) {
+            return Vec::new();
+        }
+        let Some((declaration, ty)) = self.ir.get(declaration) else {
+            return Vec::new();
+        };
+        if self.validated.contains(declaration) {
+            return vec![format!(
+                "validate{}({});",
+                self.type_name(declaration),
+                value
+            )];
+        }
+        if self.generated.contains(declaration) {
+            return Vec::new();
+        }
+        self.parse_definition_validation(ty, declaration, value, path, depth)
+    }
+
+    /// Returns validation statements for a type, even if it is exported.
+    fn parse_definition_validation(
+        &self,
+        ty: &ir::Type,
+        declaration: &str,
+        value: &str,
+        path: &str,
+        depth: usize,
+    ) -> Vec<String> {
+        let item = format!("item{}", depth);
+        let element_path = format!("{}[]", path);
+        let check_length = |length: &ir::Length, measured: String| {
+            length.bounds().map(|range| {
+                format!(
+                    "checkLength({}, {}, {}, \"{}\");",
+                    measured,
+                    range.start(),
+                    range.end(),
+                    path
+                )
+            })
+        };
+        let (checks, binding, element_checks) = match ty {
+            ir::Type::String(length) => {
+                let measured = format!("new TextEncoder().encode({}).length", value);
+                return check_length(length, measured).into_iter().collect();
+            }
+            ir::Type::Bytes(length) => {
+                return check_length(length, format!("{}.length", value))
+                    .into_iter()
+                    .collect();
+            }
+            ir::Type::Sequence { element, length } => (
+                check_length(length, format!("{}.length", value)),
+                item.clone(),
+                self.parse_validation(element, &item, &element_path, depth + 1),
+            ),
+            ir::Type::Set { element, length } => (
+                check_length(length, format!("{}.size", value)),
+                item.clone(),
+                self.parse_validation(element, &item, &element_path, depth + 1),
+            ),
+            ir::Type::Map {
+                key: key_type,
+                value: value_type,
+                length,
+            } => {
+                let key = format!("key{}", depth);
+                let mut entry_checks =
+                    self.parse_validation(key_type, &key, &element_path, depth + 1);
+                entry_checks.extend(self.parse_validation(
+                    value_type,
+                    &item,
+                    &element_path,
+                    depth + 1,
+                ));
+                (
+                    check_length(length, format!("{}.size", value)),
+                    format!("[{}, {}]", key, item),
+                    entry_checks,
+                )
+            }
+            ir::Type::Tuple(elements) => {
+                return elements
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, element)| {
+                        let value = format!("{}[{}]", value, i);
+                        let path = format!("{}[{}]", path, i);
+                        self.parse_validation(element, &value, &path, depth)
+                    })
+                    .collect();
+            }
+            ir::Type::Option(inner) => {
+                let checks = self.parse_option_validation(inner, value, path, depth);
+                return guard(format!("if ({} !== null) {{", value), checks);
+            }
+            ir::Type::Enum { variants, .. } => {
+                return variants
+                    .iter()
+                    .flat_map(|variant| {
+                        let payload = property_access(value, &variant.name);
+                        let path = format!("{}.{}", path, unraw(&variant.name));
+                        let checks = if self.variant_structs.contains(&variant.payload) {
+                            self.parse_fields_validation(&variant.payload, &payload, &path, depth)
+                        } else {
+                            self.parse_validation(&variant.payload, &payload, &path, depth)
+                        };
+                        guard(format!("if (\"{}\" in {}) {{", variant.name, value), checks)
+                    })
+                    .collect();
+            }
+            ir::Type::Struct(_) => {
+                return self.parse_fields_validation(declaration, value, path, depth);
+            }
+            ir::Type::Primitive(_) => return Vec::new(),
+        };
+        let mut checks: Vec<String> = checks.into_iter().collect();
+        if !element_checks.is_empty() {
+            checks.push(format!("for (const {} of {}) {{", binding, value));
+            checks.extend(indent(element_checks));
+            checks.push("}".to_string());
+        }
+        checks
+    }
+
+    /// Returns validation statements for an `Option` payload, matching `parse_option_value`.
+    fn parse_option_validation(
+        &self,
+        declaration: &str,
+        value: &str,
+        path: &str,
+        depth: usize,
+    ) -> Vec<String> {
+        if let Some(ir::Type::Option(inner)) = self.ty(declaration) {
+            let payload = format!("{}.Some", value);
+            let checks = self.parse_option_validation(inner, &payload, path, depth);
+            return guard(format!("if (\"Some\" in {}) {{", value), checks);
+        }
+        self.parse_validation(declaration, value, path, depth)
+    }
+
+    /// Returns validation statements for an inlined struct's fields.
+    fn parse_fields_validation(
+        &self,
+        declaration: &str,
+        value: &str,
+        path: &str,
+        depth: usize,
+    ) -> Vec<String> {
+        match self.ty(declaration) {
+            Some(ir::Type::Struct(ir::Fields::Named(fields))) => fields
+                .iter()
+                .flat_map(|field| {
+                    let name = self.field_name(&field.name);
+                    let value = property_access(value, &name);
+                    let path = format!("{}.{}", path, name);
+                    self.parse_validation(&field.ty, &value, &path, depth)
+                })
+                .collect(),
+            Some(ir::Type::Struct(ir::Fields::Unnamed(elements)))
+                if elements.len() == 1 && self.variant_structs.contains(declaration) =>
+            {
+                self.parse_validation(&elements[0], value, path, depth)
+            }
+            Some(ir::Type::Struct(ir::Fields::Unnamed(elements))) => elements
+                .iter()
+                .enumerate()
+                .flat_map(|(i, element)| {
+                    let value = format!("{}[{}]", value, i);
+                    let path = format!("{}[{}]", path, i);
+                    self.parse_validation(element, &value, &path, depth)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Writes the exports for a declaration and records them as a [`Chunk`].
+    fn push_export(&mut self, declaration: &str, schema: &str, checks: Vec<String>) {
+        let start = self.output.len();
+        let exports = self.exports.len();
+        let types = self.types.len();
+        self.exported.push(declaration.to_string());
+        if let Some(ir::Type::Enum { variants, .. }) = self.ty(declaration) {
+            if variants.is_empty() {
+                // `b.enum({})` infers `never`, which matches an uninhabited Rust enum
+                let message = format!(
+                    "enum `{}` has no variants, so no value can be serialized or deserialized with it",
+                    declaration
+                );
+                self.output.push_str(&format!("// Warning: {}.\n", message));
+                self.warn(declaration, message);
+            }
+        }
+        let docs = jsdoc(&self.docs(declaration), "");
+        self.output.push_str(&docs);
+        if self.options.output_mode == OutputMode::Declarations {
+            let ty = self.type_declaration(declaration);
+            self.output.push_str(&ty);
+            self.types.push(self.type_name(declaration));
+            if let Some(UnionStyle::Tagged { field }) = &self.options.union_types {
+                let tagged = self.tagged_union(declaration, field, false);
+                self.output.push_str(&tagged);
+            }
+        } else {
+            self.write_export(declaration, schema, checks);
+            self.write_helpers(declaration);
+        }
+        self.chunks.push(Chunk {
+            declaration: declaration.to_string(),
+            name: self.type_name(declaration),
+            exports: self.exports[exports..].to_vec(),
+            types: self.types[types..].to_vec(),
+            text: self.output[start..].to_string(),
+        });
+    }
+
+    /// Writes the optional per-type functions that accompany a schema.
+    fn write_helpers(&mut self, declaration: &str) {
+        let name = self.type_name(declaration);
+        let schema_name = self.schema_name(declaration);
+        if self.options.codec_functions {
+            self.output.push_str(&format!(
+                "export function serialize{0}(value: {0}): Uint8Array {{\n  return {1}.serialize({2});\n}}\n\n",
+                name,
+                schema_name,
+                // Readonly collections aren't assignable to the mutable ones schemas take
+                match self.options.readonly_types {
+                    true => format!("value as b.infer<typeof {}>", schema_name),
+                    false => "value".to_string(),
+                }
+            ));
+            self.output.push_str(&format!(
+                "export function deserialize{0}(bytes: Uint8Array): {0} {{\n  return {1}.deserialize(bytes);\n}}\n\n",
+                name, schema_name
+            ));
+            self.exports.push(format!("serialize{}", name));
+            self.exports.push(format!("deserialize{}", name));
+        }
+        let variants = match self.ty(declaration) {
+            Some(ir::Type::Enum { variants, .. }) => variants.clone(),
+            _ => Vec::new(),
+        };
+        if self.options.tag_constants && !variants.is_empty() {
+            let tags = format!("{}Tag", name);
+            self.output
+                .push_str(&format!("export const {} = {{\n", tags));
+            for variant in &variants {
+                self.output.push_str(&format!(
+                    "  {}: {},\n",
+                    property_key(&variant.name),
+                    variant.discriminant
+                ));
+            }
+            self.output.push_str("} as const;\n\n");
+            self.exports.push(tags);
+        }
+        if self.options.variant_constructors && !variants.is_empty() {
+            // A value and a type can share a name, so `GameEvent.PlayerLeft(...)` builds a `GameEvent`
+            self.output
+                .push_str(&format!("export const {} = {{\n", name));
+            for ir::Variant {
+                name: variant,
+                payload,
+                ..
+            } in &variants
+            {
+                let key = property_key(variant);
+                if self.is_unit(payload) {
+                    self.output.push_str(&format!(
+                        "  {0}: (): {1} => ({{ {0}: {{}} }}),\n",
+                        key, name
+                    ));
+                } else {
+                    self.output.push_str(&format!(
+                        "  {0}: (value: Extract<{1}, {{ {0}: unknown }}>[\"{2}\"]): {1} => ({{ {0}: value }}),\n",
+                        key,
+                        name,
+                        unraw(variant)
+                    ));
+                }
+            }
+            self.output.push_str("} as const;\n\n");
+            self.exports.push(name.clone());
+        }
+        if let Some(UnionStyle::Tagged { field }) = &self.options.union_types {
+            let tagged = self.tagged_union(declaration, field, true);
+            self.output.push_str(&tagged);
+        }
+        if self.options.byte_sizes {
+            let constant = screaming_snake_case(&name);
+            match self.encoded_size(declaration, &mut Vec::new()) {
+                Some((min, Some(max))) if min == max => {
+                    self.output.push_str(&format!(
+                        "export const {}_BYTE_SIZE = {};\n\n",
+                        constant, min
+                    ));
+                    self.exports.push(format!("{}_BYTE_SIZE", constant));
+                }
+                Some((min, _)) => {
+                    self.output.push_str(&format!(
+                        "export const {}_MIN_BYTE_SIZE = {};\n\n",
+                        constant, min
+                    ));
+                    self.exports.push(format!("{}_MIN_BYTE_SIZE", constant));
+                }
+                None => {}
+            }
+        }
+        if self.options.field_offsets {
+            if let Some(offsets) = self.field_offsets(declaration) {
+                let table = format!("{}Offsets", name);
+                self.output
+                    .push_str(&format!("export const {} = {{\n", table));
+                for (field, offset, size) in offsets {
+                    self.output.push_str(&format!(
+                        "  {}: {{ offset: {}, size: {} }},\n",
+                        property_key(&field),
+                        offset,
+                        size
+                    ));
+                }
+                self.output.push_str("} as const;\n\n");
+                self.exports.push(table);
+            }
+        }
+        if self.options.mocks || self.options.roundtrip_spec {
+            self.use_helper(mock::MOCK_HELPERS);
+            let mock = self.mock_function(declaration);
+            self.output.push_str(&mock);
+            self.exports.push(format!("mock{}", name));
+        }
+        if self.options.type_guards {
+            for ir::Variant { name: variant, .. } in &variants {
+                let guard = self.guard_name(declaration, variant);
+                self.output.push_str(&format!(
+                    "export function {0}(value: {1}): value is Extract<{1}, {{ {2}: unknown }}> {{\n  return \"{3}\" in value;\n}}\n\n",
+                    guard,
+                    name,
+                    property_key(variant),
+                    unraw(variant)
+                ));
+                self.exports.push(guard);
+            }
+        }
+    }
+
+    /// Returns the smallest and largest encoded size of a declaration in bytes, with no
+    /// largest size if it is unbounded, or `None` if it can't be computed because part
+    /// of it is mapped, unresolved, recursive or uninhabited.
+    fn encoded_size<'b>(
+        &'b self,
+        declaration: &'b str,
+        visiting: &mut Vec<&'b str>,
+    ) -> Option<(u64, Option<u64>)> {
+        let declaration = match self.options.source.aliases.get(declaration) {
+            Some(target) => target.as_str(),
+            None => declaration,
+        };
+        if self.mappings.contains_key(&normalize(declaration)) {
+            return None;
+        }
+        let (declaration, ty) = self.resolve(declaration)?;
+        if visiting.contains(&declaration.as_str()) {
+            return None;
+        }
+        visiting.push(declaration);
+        let size = self.type_size(ty, visiting);
+        visiting.pop();
+        size
+    }
+
+    /// Returns the name, byte offset and size of each field of a struct whose fields all
+    /// have a fixed encoded size, or `None` for anything else.
+    fn field_offsets(&self, declaration: &str) -> Option<Vec<(String, u64, u64)>> {
+        let fields: Vec<(String, &str)> = match self.ty(declaration)? {
+            ir::Type::Struct(ir::Fields::Named(fields)) => fields
+                .iter()
+                .map(|field| (self.field_name(&field.name), field.ty.as_str()))
+                .collect(),
+            ir::Type::Struct(ir::Fields::Unnamed(elements)) => elements
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (index.to_string(), element.as_str()))
+                .collect(),
+            _ => return None,
+        };
+        let mut offset = 0;
+        let mut offsets = Vec::with_capacity(fields.len());
+        for (name, field) in fields {
+            let size = match self.encoded_size(field, &mut vec![declaration]) {
+                Some((min, Some(max))) if min == max => min,
+                _ => return None,
+            };
+            offsets.push((name, offset, size));
+            offset += size;
+        }
+        Some(offsets)
+    }
+
+    fn type_size<'b>(
+        &'b self,
+        ty: &'b ir::Type,
+        visiting: &mut Vec<&'b str>,
+    ) -> Option<(u64, Option<u64>)> {
+        let sum = |sizes: Vec<Option<(u64, Option<u64>)>>| {
+            sizes
+                .into_iter()
+                .try_fold((0u64, Some(0u64)), |(min, max), size| {
+                    let (element_min, element_max) = size?;
+                    let max = max.zip(element_max).and_then(|(a, b)| a.checked_add(b));
+                    Some((min.saturating_add(element_min), max))
+                })
+        };
+        let sequence = |element: Option<(u64, Option<u64>)>, length: &ir::Length| {
+            let (element_min, element_max) = element?;
+            let width = u64::from(length.width);
+            let min = width.saturating_add(length.range.start().saturating_mul(element_min));
+            let bounded = length.width == Definition::ARRAY_LENGTH_WIDTH
+                || length.range != Definition::DEFAULT_LENGTH_RANGE;
+            let max = element_max
+                .filter(|_| bounded)
+                .and_then(|max| length.range.end().checked_mul(max))
+                .and_then(|max| max.checked_add(width));
+            Some((min, max))
+        };
+        match ty {
+            ir::Type::Primitive(size) => Some((u64::from(*size), Some(u64::from(*size)))),
+            ir::Type::String(length) | ir::Type::Bytes(length) => {
+                sequence(Some((1, Some(1))), length)
+            }
+            ir::Type::Sequence { element, length } | ir::Type::Set { element, length } => {
+                sequence(self.encoded_size(element, visiting), length)
+            }
+            ir::Type::Map { key, value, length } => {
+                let entry = sum(vec![
+                    self.encoded_size(key, visiting),
+                    self.encoded_size(value, visiting),
+                ]);
+                sequence(entry, length)
+            }
+            ir::Type::Tuple(elements) => sum(elements
+                .iter()
+                .map(|element| self.encoded_size(element, visiting))
+                .collect()),
+            ir::Type::Option(value) => {
+                // A tag, then nothing for `None` or the value for `Some`
+                let (_, max) = self.encoded_size(value, visiting)?;
+                Some((1, max.and_then(|max| max.checked_add(1))))
+            }
+            ir::Type::Enum {
+                tag_width,
+                variants,
+            } => {
+                let sizes = variants
+                    .iter()
+                    .map(|variant| self.encoded_size(&variant.payload, visiting))
+                    .collect::<Option<Vec<_>>>()?;
+                let min = sizes.iter().map(|(min, _)| *min).min()?;
+                let max = sizes
+                    .iter()
+                    .map(|(_, max)| *max)
+                    .collect::<Option<Vec<_>>>()
+                    .and_then(|maxes| maxes.into_iter().max());
+                let tag = u64::from(*tag_width);
+                Some((min + tag, max.map(|max| max + tag)))
+            }
+            ir::Type::Struct(fields) => sum(fields
+                .types()
+                .into_iter()
+                .map(|field| self.encoded_size(field, visiting))
+                .collect()),
+        }
+    }
+
+    /// Whether a declaration is encoded as nothing, like a unit enum variant.
+    fn is_unit(&self, declaration: &str) -> bool {
+        match self.ty(declaration) {
+            Some(ir::Type::Struct(ir::Fields::Empty) | ir::Type::Primitive(0)) => true,
+            _ => normalize(declaration) == "()",
+        }
+    }
+
+    /// Returns the name of the type guard for `variant`: `is{Variant}`, or
+    /// `is{Enum}{Variant}` if another exported enum has a variant of the same name.
+    fn guard_name(&self, declaration: &str, variant: &str) -> String {
+        let shared = self.ir.types.iter().any(|(other, ty)| match ty {
+            ir::Type::Enum { variants, .. } => {
+                other != declaration
+                    && self.is_exported(other, ty)
+                    && variants.iter().any(|v| v.name == variant)
+            }
+            _ => false,
+        });
+        match shared {
+            true => format!("is{}{}", self.type_name(declaration), unraw(variant)),
+            false => format!("is{}{}", self.options.prefix, unraw(variant)),
+        }
+    }
+
+    /// Returns the `schemas` object and `types` namespace collecting every schema, for
+    /// `ExportStyle::Object`.
+    fn schema_object(&mut self) -> String {
+        let mut schemas = String::from("export const schemas = {\n");
+        let mut types = String::from("export namespace types {\n");
+        for chunk in &self.chunks {
+            let schema_name = self.options.schema_naming.apply(&chunk.name);
+            schemas.push_str(&format!("  {}: {},\n", chunk.name, schema_name));
+            if !self.options.readonly_types {
+                types.push_str(&format!(
+                    "  export type {} = b.infer<typeof {}>;\n",
+                    chunk.name, schema_name
+                ));
+                continue;
+            }
+            // Inferred types are mutable, so the readonly declarations are repeated
+            for line in self.type_declaration(&chunk.declaration).trim_end().lines() {
+                types.push_str(&format!("  {}\n", line));
+            }
+        }
+        self.exports.push("schemas".to_string());
+        format!("{}}} as const;\n\n{}}}\n\n", schemas, types)
+    }
+
+    /// Returns `schemaRegistry`, mapping each Rust declaration to its schema so values can
+    /// be decoded given a type name at runtime.
+    fn schema_registry(&self) -> String {
+        let mut registry =
+            String::from("export const schemaRegistry: Record<string, Schema<unknown>> = {\n");
+        for chunk in &self.chunks {
+            registry.push_str(&format!(
+                "  \"{}\": {},\n",
+                chunk.declaration,
+                self.options.schema_naming.apply(&chunk.name)
+            ));
+        }
+        registry + "};\n\n"
+    }
+
+    /// Returns the name and definition of the `SCHEMA_HASH` constant, which lets clients
+    /// check at runtime that they were generated from the same schema as the contract
+    /// they talk to.
+    fn hash_constant(&self) -> (String, String) {
+        let name = match self.options.prefix.as_str() {
+            "" => "SCHEMA_HASH".to_string(),
+            prefix => format!("{}_SCHEMA_HASH", screaming_snake_case(prefix)),
+        };
+        let constant = format!(
+            "export const {} = \"sha256:{}\";\n\n",
+            name,
+            schema_hash(self.container)
+        );
+        (name, constant)
+    }
+
+    /// Whether the generated code refers to zorsh's `Schema` type.
+    fn needs_schema_type(&self) -> bool {
+        self.options.schema_registry || self.helpers.iter().any(|h| h.contains("Schema<"))
+    }
+
+    /// Wraps the generated exports in the configured namespace, if any.
+    fn namespaced(&self, body: &str) -> String {
+        match &self.options.namespace {
+            Some(namespace) => {
+                format!(
+                    "export namespace {} {{\n{}\n}}\n",
+                    namespace,
+                    body.trim_end()
+                )
+            }
+            None => body.to_string(),
+        }
+    }
+
+    fn write_export(&mut self, declaration: &str, schema: &str, checks: Vec<String>) {
+        let name = self.type_name(declaration);
+        let schema_name = self.schema_name(declaration);
+        // The object style exports schemas and types from `schemas` and `types` only, and
+        // keeps these local for the functions below to refer to.
+        let export = match self.options.export_style {
+            ExportStyle::Separate => {
+                self.exports.push(schema_name.clone());
+                self.types.push(name.clone());
+                "export "
+            }
+            ExportStyle::Object => "",
+        };
+        let is_enum = matches!(self.ty(declaration), Some(ir::Type::Enum { .. }));
+        let declared =
+            (is_enum && self.options.union_types.is_some()) || self.options.readonly_types;
+        let ty = match declared {
+            true => {
+                let ty = self.type_declaration(declaration);
+                format!("{}{}", export, ty.strip_prefix("export ").unwrap_or(&ty))
+            }
+            false => format!(
+                "{}type {} = b.infer<typeof {}>;\n\n",
+                export, name, schema_name
+            ),
+        };
+        if checks.is_empty() {
+            self.output.push_str(&format!(
+                "{}const {} = {};\n{}",
+                export, schema_name, schema, ty
+            ));
+            return;
+        }
+        self.exports.push(format!("validate{}", name));
+
+        self.validated.insert(declaration.to_string());
+        self.use_helper(VALIDATION_HELPERS);
+        self.output.push_str(&format!(
+            "{2}const {0} = withValidation({1}, validate{3});\n{4}",
+            schema_name, schema, export, name, ty
+        ));
+        self.output.push_str(&format!(
+            "export function validate{0}(value: {0}): void {{\n",
+            name
+        ));
+        for check in indent(checks) {
+            self.output.push_str(&format!("{}\n", check));
+        }
+        self.output.push_str("}\n\n");
+    }
+}
+
+/// Runtime helpers emitted when any schema carries length bounds.
+const VALIDATION_HELPERS: &str = r#"function checkLength(length: number, min: number, max: number, path: string): void {
+  if (length < min || length > max) {
+    throw new Error(`${path} must have between ${min} and ${max} elements, got ${length}`);
+  }
+}
+
+function withValidation<T>(schema: Schema<T>, validate: (value: T) => void): Schema<T> {
+  // A wrapper of its own, since `schema` may be another exported schema
+  const validated = Object.create(schema) as Schema<T>;
+  validated.serialize = (value: T) => {
+    validate(value);
+    return schema.serialize(value);
+  };
+  return validated;
+}
+
+"#;
+
+/// Runtime helper emitted when 64-bit integers are decoded as numbers, built on
+/// `b.transform`.
+const SAFE_NUMBER_HELPER: &str = r#"function safeNumber(schema: Schema<bigint>): Schema<number> {
+  return b.transform(schema, `${schema.type}AsNumber`, (value) => {
+    if (value < BigInt(Number.MIN_SAFE_INTEGER) || value > BigInt(Number.MAX_SAFE_INTEGER)) {
+      throw new RangeError(`${value} is outside the range of safe integers`);
+    }
+    return Number(value);
+  }, (value) => {
+    if (!Number.isSafeInteger(value)) {
+      throw new RangeError(`${value} is not a safe integer`);
+    }
+    return BigInt(value);
+  });
+}
+
+"#;
+/// Runtime helper emitted when large integers are decoded as decimal strings.
+const DECIMAL_STRING_HELPER: &str = r#"function decimalString(schema: Schema<bigint>): Schema<string> {
+  return b.transform(schema, `${schema.type}AsString`, (value) => value.toString(), (value) => {
+    if (!/^-?[0-9]+$/.test(value)) {
+      throw new SyntaxError(`${JSON.stringify(value)} is not a decimal integer`);
+    }
+    return BigInt(value);
+  });
+}
+
+"#;
+/// Runtime helper emitted when newtypes are branded.
+const BRAND_HELPER: &str = r#"function branded<T, B extends string>(schema: Schema<T>, _brand: B): Schema<T & { readonly __brand: B }> {
+  return schema as Schema<T & { readonly __brand: B }>;
+}
+
+"#;
+
+/// The options argument of a builder whose length prefix isn't zorsh's default u32, e.g.
+/// `{ lengthWidth: 2 }`.
+fn length_options(length: &ir::Length) -> Option<String> {
+    match length.width {
+        Definition::ARRAY_LENGTH_WIDTH | 4 => None,
+        width => Some(format!("{{ lengthWidth: {} }}", width)),
+    }
+}
+
+/// Formats `lines` as a JSDoc comment at `indent`, or returns nothing if there are none.
+fn jsdoc(lines: &[String], indent: &str) -> String {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| line.replace("*/", "*\\/"))
+        .collect();
+    match lines.as_slice() {
+        [] => String::new(),
+        [line] => format!("{}/** {} */\n", indent, line),
+        _ => {
+            let mut comment = format!("{}/**\n", indent);
+            for line in &lines {
+                match line.as_str() {
+                    "" => comment.push_str(&format!("{} *\n", indent)),
+                    line => comment.push_str(&format!("{} * {}\n", indent, line)),
+                }
+            }
+            comment.push_str(&format!("{} */\n", indent));
+            comment
+        }
+    }
+}
+
+fn indent(lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|line| format!("  {}", line))
+        .collect()
+}
+
+/// Wraps `checks` in a block opened by `open`, or returns nothing if there are no checks.
+fn guard(open: String, checks: Vec<String>) -> Vec<String> {
+    if checks.is_empty() {
+        return checks;
+    }
+    let mut lines = vec![open];
+    lines.extend(indent(checks));
+    lines.push("}".to_string());
+    lines
+}
+
+/// The path standing for standard input, or standard output.
+const STDIO: &str = "-";
+
+/// Returns the schema for a schema container, such as one built with
+/// `BorshSchemaContainer::for_type::<T>()`, for callers that post-process the output or
+/// send it elsewhere than a file. Source maps and roundtrip specs, which are written next
+/// to an output file, aren't supported.
+pub fn generate_to_string(
+    container: &BorshSchemaContainer,
+    options: &GeneratorOptions,
+) -> Result<String, ZorshGenError> {
+    generate_with_warnings(container, options).map(|generated| generated.output)
+}
+
+/// A schema with the warnings about it, returned by [`generate_with_warnings`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Generated {
+    pub output: String,
+    /// What the output only holds a comment for, in the order it was found: placeholders
+    /// that `strict` would have made errors, and enums without variants.
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Like [`generate_to_string`], also returning the warnings that the output only mentions
+/// in comments, each with the declaration and how the root type refers to it, for
+/// surfacing in CI or an editor.
+pub fn generate_with_warnings(
+    container: &BorshSchemaContainer,
+    options: &GeneratorOptions,
+) -> Result<Generated, ZorshGenError> {
+    if options.source_map || options.roundtrip_spec {
+        return Err(ZorshGenError::options("source maps and roundtrip specs are written next to the output file, so they need an output path"));
+    }
+    let (schema, options) = hooks::apply(ir::Schema::new(container), options)?;
+    backend(&options).generate(&schema, &options)
+}
+
+/// The backend `options` generate with: the custom one if given, otherwise the target's.
+fn backend(options: &GeneratorOptions) -> &dyn Backend {
+    match &options.backend {
+        Some(backend) => backend.as_ref(),
+        None => options.target.backend(),
+    }
+}
+
+/// Returns the schema for `T` and the types it refers to, generated with the default
+/// options, e.g. `generate_for::<GameState>()` in a test or build script.
+pub fn generate_for<T: BorshSchema + ?Sized>() -> Result<String, ZorshGenError> {
+    generate_for_with_options::<T>(&GeneratorOptions::default())
+}
+
+/// Like [`generate_for`], with explicit generator options.
+pub fn generate_for_with_options<T: BorshSchema + ?Sized>(
+    options: &GeneratorOptions,
+) -> Result<String, ZorshGenError> {
+    generate_to_string(&BorshSchemaContainer::for_type::<T>(), options)
+}
+
+/// What [`zorsh_export!`](crate::zorsh_export) and `#[derive(ZorshExport)]` refer to, for crates without
+/// their own `borsh` or `inventory` dependency.
+#[doc(hidden)]
+pub mod __private {
+    pub use borsh::schema::BorshSchemaContainer;
+    #[cfg(feature = "source")]
+    pub use inventory;
+}
+
+/// A type registered with `#[derive(ZorshExport)]`.
+#[cfg(feature = "source")]
+pub struct Registered {
+    /// The type's name, as written where it's defined.
+    pub name: &'static str,
+    /// The module it's defined in, e.g. `my_contract::state`.
+    pub module: &'static str,
+    pub container: fn() -> BorshSchemaContainer,
+}
+
+#[cfg(feature = "source")]
+inventory::collect!(Registered);
+
+/// Returns the schema containers of every type registered with `#[derive(ZorshExport)]`
+/// anywhere in the program, merged as [`merge_containers`] merges them. Types are taken
+/// in order of module path and name, so the output doesn't depend on link order.
+#[cfg(feature = "source")]
+pub fn registered_container() -> Result<BorshSchemaContainer, ZorshGenError> {
+    let mut registered: Vec<&Registered> = inventory::iter::<Registered>().collect();
+    if registered.is_empty() {
+        return Err(ZorshGenError::options(
+            "no types are registered with `#[derive(ZorshExport)]`",
+        ));
+    }
+    registered.sort_by_key(|registered| (registered.module, registered.name));
+    let containers: Vec<_> = registered.iter().map(|r| (r.container)()).collect();
+    merge_containers(&containers)
+}
+
+/// Like [`generate_to_string`], writing the schema to `writer`.
+pub fn generate_to_writer(
+    container: &BorshSchemaContainer,
+    writer: &mut impl Write,
+    options: &GeneratorOptions,
+) -> Result<(), ZorshGenError> {
+    let output = generate_to_string(container, options)?;
+    Ok(writer.write_all(output.as_bytes())?)
+}
+
+/// Like [`render_zorsh_schema`], for a schema container built in memory, such as with
+/// `BorshSchemaContainer::for_type::<T>()`, rather than read from files. Custom regions
+/// are only carried over from existing files with the `fs` feature.
+pub fn render_zorsh_schema_from_container(
+    container: &BorshSchemaContainer,
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<Vec<(std::path::PathBuf, String)>, ZorshGenError> {
+    render_schema(container, output_path, options).map(|(files, _)| files)
+}
+
+/// Returns the files [`render_zorsh_schema_from_container`] returns, with the report on
+/// generating them.
+fn render_schema(
+    container: &BorshSchemaContainer,
+    output_path: &str,
+    options: &GeneratorOptions,
+) -> Result<(Vec<(std::path::PathBuf, String)>, GenerationReport), ZorshGenError> {
+    if output_path == STDIO && (options.source_map || options.roundtrip_spec) {
+        return Err(ZorshGenError::options("source maps and roundtrip specs are written next to the output file, so they need an output path rather than `-`"));
+    }
+    let (schema, options) = hooks::apply(ir::Schema::new(container), options)?;
+    let options = options.as_ref();
+    if options.backend.is_some() || options.target != Target::Zorsh {
+        if options.source_map || options.roundtrip_spec {
+            return Err(ZorshGenError::options(
+                "source maps and roundtrip specs are only written for zorsh schemas",
+            ));
+        }
+        let (generated, report) = backend::generate_reported(&schema, options)?;
+        let files = vec![(output_path.into(), generated.output)];
+        return Ok(report.with_files(files));
+    }
+    let mut parser = Parser::new(&schema, options);
+    let output = parser.parse()?;
+    let report = parser.report();
+    #[cfg(feature = "fs")]
+    let output = match output_path {
+        STDIO => output,
+        path => fs::with_custom_regions(std::path::Path::new(path), output)?,
+    };
+    let mut files = vec![(output_path.into(), output.clone())];
+    if options.source_map {
+        let path = std::path::Path::new(output_path);
+        let name = path
+            .file_name()
+            .map_or(output_path.into(), |n| n.to_string_lossy());
+        let map = parser.source_map(&BTreeMap::from([(name.into_owned(), output)]));
+        files.push((format!("{}.map.json", output_path).into(), map));
+    }
+    if options.roundtrip_spec {
+        let path = std::path::Path::new(output_path);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let spec = parser.roundtrip_spec(&format!("./{}", stem));
+        let spec = format::format(&(parser.banner() + &spec), &options.format);
+        files.push((path.with_file_name(format!("{}.test.ts", stem)), spec));
+    }
+    Ok(report.with_files(files))
+}
+
+/// Like [`render_zorsh_files`], for a schema container built in memory. Custom regions
+/// are only carried over from existing files with the `fs` feature.
+pub fn render_zorsh_files_from_container(
+    container: &BorshSchemaContainer,
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<Vec<(std::path::PathBuf, String)>, ZorshGenError> {
+    render_files(container, output_dir, options).map(|(files, _)| files)
+}
+
+/// Returns the files [`render_zorsh_files_from_container`] returns, with the report on
+/// generating them.
+fn render_files(
+    container: &BorshSchemaContainer,
+    output_dir: &str,
+    options: &GeneratorOptions,
+) -> Result<(Vec<(std::path::PathBuf, String)>, GenerationReport), ZorshGenError> {
+    if output_dir == STDIO {
+        return Err(ZorshGenError::options(
+            "one file per type needs an output directory rather than `-`",
+        ));
+    }
+    if options.backend.is_some() || options.target != Target::Zorsh {
+        return Err(ZorshGenError::options(
+            "one file per type is only written for zorsh schemas; other backends write one document",
+        ));
+    }
+    let (schema, options) = hooks::apply(ir::Schema::new(container), options)?;
+    let options = options.as_ref();
+    let mut parser = Parser::new(&schema, options);
+    let mut files = parser.parse_files()?;
+    let report = parser.report();
+    #[cfg(feature = "fs")]
+    for (name, contents) in files.iter_mut() {
+        if name.ends_with(".ts") {
+            let path = std::path::Path::new(output_dir).join(name);
+            *contents = fs::with_custom_regions(&path, std::mem::take(contents))?;
+        }
+    }
+    if options.source_map {
+        // Carried over regions move the generated lines down
+        files.remove("zorsh-map.json");
+        let map = parser.source_map(&files);
+        files.insert("zorsh-map.json".to_string(), map);
+    }
+    let files = files
+        .into_iter()
+        .map(|(name, contents)| (std::path::Path::new(output_dir).join(name), contents))
+        .collect();
+    Ok(report.with_files(files))
+}
+
+/// Merges schema containers, such as those of several root types, into one rooted at the
+/// first one's root. Types defined in more than one container must be defined the same
+/// way in each.
+pub fn merge_containers(
+    containers: &[BorshSchemaContainer],
+) -> Result<BorshSchemaContainer, ZorshGenError> {
+    let containers = containers
+        .iter()
+        .map(|container| (container.declaration().clone(), container.clone()))
+        .collect();
+    merge(containers)
+}
+
+/// Merges containers, each labelled with where it came from for errors.
+fn merge(
+    containers: Vec<(String, BorshSchemaContainer)>,
+) -> Result<BorshSchemaContainer, ZorshGenError> {
+    let mut root = None;
+    let mut definitions: BTreeMap<Declaration, (Definition, &str)> = BTreeMap::new();
+    for (label, container) in &containers {
+        root.get_or_insert_with(|| container.declaration().clone());
+        for (declaration, definition) in container.definitions() {
+            match definitions.get(declaration) {
+                Some((existing, _)) if existing == definition => {}
+                Some((_, first)) => {
+                    return Err(ZorshGenError::Conflict {
+                        declaration: declaration.clone(),
+                        first: first.to_string(),
+                        second: label.clone(),
+                    });
+                }
+                None => {
+                    definitions.insert(declaration.clone(), (definition.clone(), label));
+                }
+            }
+        }
+    }
+    let Some(root) = root else {
+        return Err(ZorshGenError::options(
+            "no schema containers to generate from",
+        ));
+    };
+    let definitions = definitions
+        .into_iter()
+        .map(|(declaration, (definition, _))| (declaration, definition))
+        .collect();
+    Ok(BorshSchemaContainer::new(root, definitions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::schema::Fields;
+    use borsh_test::{GameEvent, GameState, Player, Stats};
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    fn generate(container: &BorshSchemaContainer) -> Result<String, ZorshGenError> {
+        generate_with(container, &GeneratorOptions::default())
+    }
+
+    fn generate_with(
+        container: &BorshSchemaContainer,
+        options: &GeneratorOptions,
+    ) -> Result<String, ZorshGenError> {
+        Parser::new(&ir::Schema::new(container), options).parse()
+    }
+
+    #[test]
+    fn generates_game_state_schema() {
+        let output = generate(&BorshSchemaContainer::for_type::<GameState>()).unwrap();
+
+        assert!(output.starts_with("import { b } from \"@zorsh/zorsh\";\n"));
+        assert!(output.contains("export const StatsSchema = b.struct({\n  health: b.u32(),\n"));
+        assert!(output.contains("  inventory: b.vec(ItemSchema),\n"));
+        assert!(output.contains("  equipped_items: b.hashMap(b.string(), b.string()),\n"));
+        assert!(output.contains("  quest_log: b.hashSet(b.string()),\n"));
+        assert!(output.contains("  last_login: b.option(b.u64()),\n"));
+        assert!(output.contains("  effects: b.vec(EffectSchema),\n"));
+        assert!(output.contains("  events: b.vec(GameEventSchema),\n"));
+        assert!(output.contains("export type GameState = b.infer<typeof GameStateSchema>;"));
+
+        // Dependencies are declared before the structs that reference them
+        let stats = output.find("export const StatsSchema").unwrap();
+        let player = output.find("export const PlayerSchema").unwrap();
+        let game_state = output.find("export const GameStateSchema").unwrap();
+        assert!(stats < player && player < game_state);
+    }
+
+    #[test]
+    fn exports_tuple_structs() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Wrapper(u64, String);
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Holder {
+            wrapped: Vec<Wrapper>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Holder>()).unwrap();
+        assert!(output.contains(
+            "export const WrapperSchema = b.tuple(b.u64(), b.string());\nexport type Wrapper = b.infer<typeof WrapperSchema>;\n"
+        ));
+        assert!(output.contains("  wrapped: b.vec(WrapperSchema),\n"));
+        assert!(output.find("WrapperSchema =").unwrap() < output.find("HolderSchema =").unwrap());
+    }
+
+    #[test]
+    fn exports_enums() {
+        let output = generate(&BorshSchemaContainer::for_type::<GameState>()).unwrap();
+
+        assert!(output.contains(
+            "export const EffectSchema = b.enum({\n  Damage: b.u32(),\n  Heal: b.u32(),\n  Buff: StatBuffSchema,\n  Debuff: StatBuffSchema,\n});\nexport type Effect = b.infer<typeof EffectSchema>;\n"
+        ));
+        assert!(output.contains("  PlayerJoined: b.struct({ player_id: b.string() }),\n"));
+        assert!(output.contains("export type GameEvent = b.infer<typeof GameEventSchema>;"));
+        assert!(output.find("EffectSchema =").unwrap() < output.find("ItemSchema =").unwrap());
+        assert!(
+            output.find("GameEventSchema =").unwrap() < output.find("GameStateSchema =").unwrap()
+        );
+    }
+
+    #[test]
+    fn emits_never_schema_for_empty_enums() {
+        #[derive(borsh::BorshSchema)]
+        enum Never {}
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Holder {
+            never: Option<Never>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Holder>()).unwrap();
+        assert!(output.contains(
+            "// Warning: enum `Never` has no variants, so no value can be serialized or deserialized with it.\nexport const NeverSchema = b.enum({});\nexport type Never = b.infer<typeof NeverSchema>;\n"
+        ));
+        assert!(output.contains("  never: b.option(NeverSchema),\n"));
+    }
+
+    #[test]
+    fn keeps_nested_options_distinct() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Nested {
+            a: Option<Option<Vec<Option<String>>>>,
+            b: Option<Option<Option<u8>>>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Nested>()).unwrap();
+        assert!(output.contains(
+            "  a: b.option(b.enum({ None: b.unit(), Some: b.vec(b.option(b.string())) })),\n"
+        ));
+        assert!(output.contains(
+            "  b: b.option(b.enum({ None: b.unit(), Some: b.enum({ None: b.unit(), Some: b.u8() }) })),\n"
+        ));
+    }
+
+    #[test]
+    fn resolves_deeply_nested_generics() {
+        #[allow(dead_code, clippy::type_complexity)]
+        #[derive(borsh::BorshSchema)]
+        struct Deep {
+            a: HashMap<String, Vec<Option<HashMap<u8, (u16, Option<bool>)>>>>,
+            b: Vec<Vec<HashSet<String>>>,
+            c: Option<Vec<HashMap<String, Option<u64>>>>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Deep>()).unwrap();
+        assert!(output.contains(
+            "  a: b.hashMap(b.string(), b.vec(b.option(b.hashMap(b.u8(), b.tuple(b.u16(), b.option(b.bool())))))),\n"
+        ));
+        assert!(output.contains("  b: b.vec(b.vec(b.hashSet(b.string()))),\n"));
+        assert!(
+            output.contains("  c: b.option(b.vec(b.hashMap(b.string(), b.option(b.u64())))),\n")
+        );
+    }
+
+    #[test]
+    fn composes_nested_fixed_arrays() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Matrices {
+            hashes: [[u8; 32]; 4],
+            grid: [[u16; 3]; 2],
+            cube: [[[i8; 2]; 3]; 4],
+            roots: Vec<[u8; 32]>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Matrices>()).unwrap();
+        assert!(output.contains("  hashes: b.array(b.bytes(32), 4),\n"));
+        assert!(output.contains("  grid: b.array(b.array(b.u16(), 3), 2),\n"));
+        assert!(output.contains("  cube: b.array(b.array(b.array(b.i8(), 2), 3), 4),\n"));
+        assert!(output.contains("  roots: b.vec(b.bytes(32)),\n"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(borsh::BorshSchema)]
+    struct AccountId(String);
+
+    #[allow(dead_code)]
+    #[derive(borsh::BorshSchema)]
+    struct Account {
+        id: AccountId,
+        friends: Vec<AccountId>,
+    }
+
+    #[test]
+    fn emits_newtypes_as_tuples_by_default() {
+        let output = generate(&BorshSchemaContainer::for_type::<Account>()).unwrap();
+        assert!(output.contains("export const AccountIdSchema = b.tuple(b.string());\n"));
+    }
+
+    #[test]
+    fn flattens_newtypes() {
+        let options = GeneratorOptions {
+            newtype_mode: NewtypeMode::Flatten,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Account>(), &options).unwrap();
+
+        assert!(output.contains(
+            "export const AccountIdSchema = b.string();\nexport type AccountId = b.infer<typeof AccountIdSchema>;\n"
+        ));
+        assert!(output.contains("  id: AccountIdSchema,\n  friends: b.vec(AccountIdSchema),\n"));
+    }
+
+    #[test]
+    fn brands_newtypes() {
+        let options = GeneratorOptions {
+            newtype_mode: NewtypeMode::Branded,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Account>(), &options).unwrap();
+
+        assert!(output
+            .starts_with("import { b, type Schema } from \"@zorsh/zorsh\";\n\nfunction branded<"));
+        assert!(output.contains(
+            "export const AccountIdSchema = branded(b.string(), \"AccountId\");\nexport type AccountId = b.infer<typeof AccountIdSchema>;\n"
+        ));
+        assert!(output.contains("  friends: b.vec(AccountIdSchema),\n"));
+    }
+
+    #[test]
+    fn resolves_fixed_arrays_of_user_types() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Loadout {
+            slots: [borsh_test::Item; 4],
+            effects: [borsh_test::Effect; 2],
+            pairs: [AccountId; 3],
+            bag: Vec<borsh_test::Item>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Loadout>()).unwrap();
+        assert!(output.contains("  slots: b.array(ItemSchema, 4),\n"));
+        assert!(output.contains("  effects: b.array(EffectSchema, 2),\n"));
+        assert!(output.contains("  pairs: b.array(AccountIdSchema, 3),\n"));
+        assert!(output.contains("  bag: b.vec(ItemSchema),\n"));
+        assert!(output.find("ItemSchema =").unwrap() < output.find("LoadoutSchema =").unwrap());
+    }
+
+    #[test]
+    fn ignores_whitespace_in_declarations() {
+        let sequence = |elements: &str| Definition::Sequence {
+            length_width: 4,
+            length_range: Definition::DEFAULT_LENGTH_RANGE,
+            elements: elements.to_string(),
+        };
+        let definitions = BTreeMap::from([
+            ("HashMap<String,u32>".to_string(), sequence("(String,u32)")),
+            (
+                "(String,u32)".to_string(),
+                Definition::Tuple {
+                    elements: vec!["String".to_string(), " u32".to_string()],
+                },
+            ),
+            ("HashSet< Vec<u8> >".to_string(), sequence("Vec< u8 >")),
+            ("Vec<u8>".to_string(), sequence("u8")),
+            ("String".to_string(), sequence("u8")),
+            ("u8".to_string(), Definition::Primitive(1)),
+            ("u32".to_string(), Definition::Primitive(4)),
+            (
+                "Spaced".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("a".to_string(), "HashMap<String, u32>".to_string()),
+                        ("b".to_string(), "HashMap< String ,u32 >".to_string()),
+                        ("c".to_string(), "HashSet<Vec<u8>>".to_string()),
+                    ]),
+                },
+            ),
+        ]);
+        let container = BorshSchemaContainer::new("Spaced".to_string(), definitions);
+
+        let output = generate(&container).unwrap();
+        assert!(output.contains(
+            "  a: b.hashMap(b.string(), b.u32()),\n  b: b.hashMap(b.string(), b.u32()),\n  c: b.hashSet(b.bytes()),\n"
+        ));
+    }
+
+    #[test]
+    fn splits_map_types_at_the_top_level_comma() {
+        assert_eq!(
+            parse_map_types("HashMap<String,u32>"),
+            ("String".to_string(), "u32".to_string())
+        );
+        assert_eq!(
+            parse_map_types(" HashMap < (u8, u16) , Vec<HashMap<u8,u8>> > "),
+            ("(u8, u16)".to_string(), "Vec<HashMap<u8,u8>>".to_string())
+        );
+    }
+
+    #[test]
+    fn resolves_non_string_map_keys() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct PositionKey {
+            x: i32,
+            y: i32,
+        }
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Board {
+            players: HashMap<u64, borsh_test::Player>,
+            tiles: HashMap<PositionKey, String>,
+            edges: HashMap<(u8, u8), bool>,
+            buffs: HashMap<borsh_test::Effect, u32>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Board>()).unwrap();
+        assert!(output.contains("  players: b.hashMap(b.u64(), PlayerSchema),\n"));
+        assert!(output.contains("  tiles: b.hashMap(PositionKeySchema, b.string()),\n"));
+        assert!(output.contains("  edges: b.hashMap(b.tuple(b.u8(), b.u8()), b.bool()),\n"));
+        assert!(output.contains("  buffs: b.hashMap(EffectSchema, b.u32()),\n"));
+        assert!(!output.contains("TODO"));
+    }
+
+    #[test]
+    fn resolves_sets_of_user_types() {
+        // `Achievements` sorts before the types its sets refer to, so they are
+        // forward references when the struct pass reaches it.
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Achievements {
+            rewards: HashSet<borsh_test::Reward>,
+            effects: HashSet<borsh_test::Effect>,
+            ids: std::collections::BTreeSet<AccountId>,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<Achievements>()).unwrap();
+        assert!(output.contains("  rewards: b.hashSet(RewardSchema),\n"));
+        assert!(output.contains("  effects: b.hashSet(EffectSchema),\n"));
+        assert!(output.contains("  ids: b.hashSet(AccountIdSchema),\n"));
+        let achievements = output.find("AchievementsSchema =").unwrap();
+        for dependency in ["RewardSchema =", "EffectSchema =", "AccountIdSchema ="] {
+            assert!(output.find(dependency).unwrap() < achievements);
+        }
+    }
+
+    fn with_unknown_declarations() -> BorshSchemaContainer {
+        let definitions = BTreeMap::from([(
+            "Contract".to_string(),
+            Definition::Struct {
+                fields: Fields::NamedFields(vec![
+                    ("owner".to_string(), "AccountId".to_string()),
+                    ("balance".to_string(), "U128".to_string()),
+                ]),
+            },
+        )]);
+        BorshSchemaContainer::new("Contract".to_string(), definitions)
+    }
+
+    #[test]
+    fn leaves_unknown_declarations_unresolved() {
+        let output = generate(&with_unknown_declarations()).unwrap();
+        assert!(output.contains("  owner: /* TODO: Resolve AccountId */,\n"));
+    }
+
+    #[test]
+    fn returns_warnings_with_the_output() {
+        let mut container = with_unknown_declarations();
+        container.insert_definition(
+            "Never".to_string(),
+            Definition::Enum {
+                tag_width: 1,
+                variants: Vec::new(),
+            },
+        );
+        let generated = generate_with_warnings(&container, &GeneratorOptions::default()).unwrap();
+
+        assert!(generated
+            .output
+            .contains("  owner: /* TODO: Resolve AccountId */,\n"));
+        let warnings: Vec<(&str, &[String], bool)> = generated
+            .warnings
+            .iter()
+            .map(|w| {
+                (
+                    w.declaration.as_deref().unwrap(),
+                    &w.references[..],
+                    w.placeholder,
+                )
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (
+                    "AccountId",
+                    &["Contract".into(), "owner".into(), "AccountId".into()][..],
+                    true
+                ),
+                (
+                    "U128",
+                    &["Contract".into(), "balance".into(), "U128".into()][..],
+                    true
+                ),
+                ("Never", &["Never".to_string()][..], false),
+            ]
+        );
+        assert!(generated.warnings[2].message.contains("has no variants"));
+    }
+
+    #[test]
+    fn consults_custom_resolver() {
+        let options = GeneratorOptions {
+            resolver: Some(Arc::new(|declaration: &str| match declaration {
+                "AccountId" => Some("b.string()".to_string()),
+                _ => None,
+            })),
+            ..Default::default()
+        };
+        let output = generate_with(&with_unknown_declarations(), &options).unwrap();
+
+        assert!(output.contains("  owner: b.string(),\n"));
+        assert!(output.contains("  balance: /* TODO: Resolve U128 */,\n"));
+    }
+
+    #[test]
+    fn reports_progress() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let options = GeneratorOptions {
+            progress: Some(Arc::new(
+                move |done: usize, total: usize, declaration: &str| {
+                    record
+                        .lock()
+                        .unwrap()
+                        .push((done, total, declaration.to_string()));
+                },
+            )),
+            ..Default::default()
+        };
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        generate_with(&container, &options).unwrap();
+
+        let seen = seen.lock().unwrap();
+        let total = seen[0].1;
+        let done: Vec<usize> = seen.iter().map(|(done, _, _)| *done).collect();
+        assert_eq!(done, (1..=total).collect::<Vec<_>>());
+        assert!(seen
+            .iter()
+            .any(|(_, _, declaration)| declaration == "Player"));
+    }
+
+    #[test]
+    fn generates_with_custom_backend() {
+        struct Roots;
+
+        impl Backend for Roots {
+            fn generate(
+                &self,
+                schema: &ir::Schema,
+                _options: &GeneratorOptions,
+            ) -> Result<Generated, ZorshGenError> {
+                Ok(Generated {
+                    output: format!("{} of {}", schema.root, schema.types.len()),
+                    warnings: Vec::new(),
+                })
+            }
+        }
+
+        let generator = ZorshGenerator::builder().backend(Roots).build();
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let output = generator.generate_to_string(&container).unwrap();
+        assert!(output.starts_with("GameState of "));
+        let files = generator.render_container(&container, "out.txt").unwrap();
+        assert_eq!(files, vec![("out.txt".into(), output)]);
+        assert!(matches!(
+            generator.render_container_files(&container, "out"),
+            Err(ZorshGenError::Options(_))
+        ));
+    }
+
+    #[test]
+    fn overrides_types_and_fields_with_hooks() {
+        struct Overrides;
+
+        impl Hooks for Overrides {
+            fn on_type(&self, declaration: &Declaration, _ty: &ir::Type) -> Option<Override> {
+                (declaration == "Stats").then(|| {
+                    Override::Mapping(TypeMapping::Import {
+                        module: "./stats".to_string(),
+                        name: "CustomStats".to_string(),
+                    })
+                })
+            }
+
+            fn on_field(
+                &self,
+                parent: &Declaration,
+                field: &str,
+                _ty: &Declaration,
+            ) -> Option<Override> {
+                let length = ir::Length {
+                    width: 4,
+                    range: 0..=u32::MAX as u64,
+                };
+                (parent == "Item" && field == "name")
+                    .then_some(Override::Type(ir::Type::Bytes(length)))
+            }
+        }
+
+        struct Structs;
+
+        impl Hooks for Structs {
+            fn on_field(
+                &self,
+                parent: &Declaration,
+                _field: &str,
+                _ty: &Declaration,
+            ) -> Option<Override> {
+                (parent == "Item").then_some(Override::Type(ir::Type::Struct(ir::Fields::Empty)))
+            }
+        }
+
+        let container = BorshSchemaContainer::for_type::<Player>();
+        let output = ZorshGenerator::builder()
+            .hooks(Overrides)
+            .build()
+            .generate_to_string(&container)
+            .unwrap();
+        assert!(output.contains("import { CustomStats } from \"./stats\";\n"));
+        assert!(output.contains("  stats: CustomStats,\n"));
+        assert!(!output.contains("StatsSchema"));
+        assert!(output.contains("  id: b.string(),\n  name: b.bytes(),\n"));
+        assert!(output.contains("export const PlayerSchema = b.struct({\n  name: b.string(),\n"));
+
+        let error = ZorshGenerator::builder()
+            .hooks(Structs)
+            .build()
+            .generate_to_string(&container)
+            .unwrap_err();
+        assert!(matches!(error, ZorshGenError::Options(message) if message.contains("`Item.id`")));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn builds_a_generator() {
+        let generator = ZorshGenerator::builder()
+            .field_case(FieldCase::Camel)
+            .rename("GameState", "State")
+            .include("Game*")
+            .exclude("GameEvent")
+            .quotes(Quotes::Single)
+            .strict(true)
+            .build();
+        let options = generator.options();
+        assert_eq!(options.field_case, FieldCase::Camel);
+        assert_eq!(options.renames["GameState"], "State");
+        assert_eq!(options.filter.include, ["Game*"]);
+        assert_eq!(options.filter.exclude, ["GameEvent"]);
+        assert_eq!(options.format.quotes, Quotes::Single);
+        assert!(options.strict);
+
+        let dir = std::env::temp_dir().join(format!("zorsh_builder_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("schema.bin");
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        std::fs::write(&input, borsh::to_vec(&container).unwrap()).unwrap();
+        let files = generator
+            .render(&[&input.to_string_lossy()], "schemas.ts")
+            .unwrap();
+        assert_eq!(files[0].1, generate_with(&container, options).unwrap());
+        assert!(files[0]
+            .1
+            .contains("export const StateSchema = b.struct({\n  players: "));
+        assert!(files[0]
+            .1
+            .contains("  currentRound: b.u32(), // current_round\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn generates_to_a_writer() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            codec_functions: true,
+            ..Default::default()
+        };
+        let output = generate_to_string(&container, &options).unwrap();
+        assert_eq!(output, generate_with(&container, &options).unwrap());
+
+        let mut written = Vec::new();
+        let generator = ZorshGenerator::from(options);
+        generator
+            .generate_to_writer(&container, &mut written)
+            .unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), output);
+
+        #[cfg(feature = "backend-json-schema")]
+        {
+            let generator = ZorshGenerator::builder().target(Target::JsonSchema).build();
+            let output = generator.generate_to_string(&container).unwrap();
+            assert!(output.starts_with("{\n  \"$schema\": "));
+        }
+
+        let generator = ZorshGenerator::builder().source_map(true).build();
+        let error = generator.generate_to_string(&container).unwrap_err();
+        assert!(matches!(error, ZorshGenError::Options(_)), "{:?}", error);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn generates_from_a_container_in_memory() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let dir = std::env::temp_dir().join(format!("zorsh_container_{}", std::process::id()));
+        let output = dir.join("schemas.ts");
+        let output = output.to_string_lossy();
+        let options = GeneratorOptions {
+            roundtrip_spec: true,
+            ..Default::default()
+        };
+        let files = render_zorsh_schema_from_container(&container, &output, &options).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap())
+            .collect();
+        assert_eq!(names, ["schemas.ts", "schemas.test.ts"]);
+        assert_eq!(files[0].1, generate_with(&container, &options).unwrap());
+
+        let generator = ZorshGenerator::new();
+        generator
+            .generate_container_files(&container, &dir.to_string_lossy())
+            .unwrap();
+        let player = std::fs::read_to_string(dir.join("Player.ts")).unwrap();
+        assert!(player.contains("export const PlayerSchema = b.struct({\n"));
+        assert!(dir.join("index.ts").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn generates_for_a_type() {
+        let container = BorshSchemaContainer::for_type::<Player>();
+        assert_eq!(
+            generate_for::<Player>().unwrap(),
+            generate(&container).unwrap()
+        );
+        let generator = ZorshGenerator::builder()
+            .field_case(FieldCase::Camel)
+            .build();
+        let output = generator.generate_for::<Player>().unwrap();
+        assert!(output.contains("  equippedItems: b.hashMap(b.string(), b.string()),"));
+        assert!(!output.contains("GameStateSchema"));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn exports_several_types() {
+        let dir = std::env::temp_dir().join(format!("zorsh_export_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("generated.ts");
+        let output = path.to_string_lossy();
+        crate::zorsh_export!(Player, GameState, GameEvent => &output).unwrap();
+        let generated = std::fs::read_to_string(&path).unwrap();
+        for name in ["Player", "GameState", "GameEvent", "Item"] {
+            let export = format!("export const {}Schema = ", name);
+            assert_eq!(generated.matches(&export).count(), 1, "{}", name);
+        }
+
+        let options = GeneratorOptions {
+            field_case: FieldCase::Camel,
+            ..Default::default()
+        };
+        crate::zorsh_export!(Stats, Player => &output, &options).unwrap();
+        let generated = std::fs::read_to_string(&path).unwrap();
+        assert!(generated.contains("  magicAttack: b.u32(),"));
+        assert!(!generated.contains("GameStateSchema"));
+
+        // Types defined differently under the same name can't share a module
+        mod other {
+            #[allow(dead_code)]
+            #[derive(borsh::BorshSchema)]
+            pub struct Player(pub u8);
+        }
+        let error = crate::zorsh_export!(Player, other::Player => &output).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`Player` is defined differently"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "source")]
+    mod registered {
+        use crate::ZorshExport;
+
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema, ZorshExport)]
+        pub struct Ledger {
+            pub entries: Vec<Entry>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema, ZorshExport)]
+        pub enum Entry {
+            Credit(u64),
+            Debit(u64),
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "fs", feature = "source"))]
+    fn generates_registered_types() {
+        let container = registered_container().unwrap();
+        assert_eq!(container.declaration(), "Entry");
+        assert!(container.get_definition("Ledger").is_some());
+
+        let dir = std::env::temp_dir().join(format!("zorsh_registry_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("generated.ts");
+        generate_all(&path.to_string_lossy()).unwrap();
+        let generated = std::fs::read_to_string(&path).unwrap();
+        assert!(generated.contains("export const EntrySchema = b.enum({\n"));
+        assert!(generated.contains("  entries: b.vec(EntrySchema),\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn fails_with_typed_errors() {
+        let dir = std::env::temp_dir().join(format!("zorsh_errors_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let garbage = dir.join("garbage.bin");
+        std::fs::write(&garbage, [1, 2, 3]).unwrap();
+        let garbage = garbage.to_string_lossy();
+        let error = render_zorsh_schema(&[&garbage], "-", &Default::default()).unwrap_err();
+        assert!(matches!(&error, ZorshGenError::Schema { input, .. } if *input == garbage));
+
+        let missing = dir.join("missing.bin");
+        let missing = missing.to_string_lossy();
+        let error = render_zorsh_schema(&[&missing], "-", &Default::default()).unwrap_err();
+        assert!(matches!(error, ZorshGenError::Io(_)), "{:?}", error);
+
+        let definitions = BTreeMap::from([(
+            "Holder".to_string(),
+            Definition::Struct {
+                fields: Fields::UnnamedFields(vec!["Missing".to_string()]),
+            },
+        )]);
+        let container = BorshSchemaContainer::new("Holder".to_string(), definitions);
+        let options = GeneratorOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = generate_with(&container, &options).unwrap_err();
+        assert!(matches!(error, ZorshGenError::Unresolved(_)), "{:?}", error);
+        assert_eq!(
+            error.diagnostics().unwrap().0[0].declaration.as_deref(),
+            Some("Missing")
+        );
+        let error = std::io::Error::from(error);
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn traces_diagnostics_to_the_root() {
+        let definitions = BTreeMap::from([
+            (
+                "GameState".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![(
+                        "players".to_string(),
+                        "Vec<Player>".to_string(),
+                    )]),
+                },
+            ),
+            (
+                "Vec<Player>".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=u32::MAX as u64,
+                    elements: "Player".to_string(),
+                },
+            ),
+            (
+                "Player".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![(
+                        "inventory".to_string(),
+                        "Item".to_string(),
+                    )]),
+                },
+            ),
+        ]);
+        let container = BorshSchemaContainer::new("GameState".to_string(), definitions);
+        let options = GeneratorOptions {
+            strict: true,
+            prefix: "1".to_string(),
+            ..Default::default()
+        };
+        let error = generate_with(&container, &options).unwrap_err();
+        assert!(
+            matches!(error, ZorshGenError::Unsupported(_)),
+            "{:?}",
+            error
+        );
+        assert_eq!(
+            error.diagnostics().unwrap().0,
+            [
+                Diagnostic {
+                    message:
+                        "`1` can't prefix exported names, which must be TypeScript identifiers"
+                            .to_string(),
+                    declaration: None,
+                    references: Vec::new(),
+                    placeholder: false,
+                },
+                Diagnostic {
+                    message: "`Item` isn't defined in the schema container, mapped or resolved"
+                        .to_string(),
+                    declaration: Some("Item".to_string()),
+                    references: ["GameState", "players", "Player", "inventory", "Item"]
+                        .map(String::from)
+                        .to_vec(),
+                    placeholder: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fails_on_placeholders_when_strict() {
+        let options = GeneratorOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = generate_with(&with_unknown_declarations(), &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`AccountId` isn't defined in the schema container, mapped or resolved\n\
+             `U128` isn't defined in the schema container, mapped or resolved"
+        );
+        let declarations = GeneratorOptions {
+            output_mode: OutputMode::Declarations,
+            ..options.clone()
+        };
+        assert!(generate_with(&with_unknown_declarations(), &declarations).is_err());
+
+        let definitions = BTreeMap::from([
+            ("u24".to_string(), Definition::Primitive(3)),
+            (
+                "Color".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![("rgb".to_string(), "u24".to_string())]),
+                },
+            ),
+        ]);
+        let container = BorshSchemaContainer::new("Color".to_string(), definitions);
+        let output = generate(&container).unwrap();
+        assert!(output.contains("  rgb: /* TODO: Read 3-byte primitive u24 */,\n"));
+        assert_eq!(
+            generate_with(&container, &options).unwrap_err().to_string(),
+            "primitive `u24` is 3 bytes wide, which no zorsh builder reads"
+        );
+
+        let options = GeneratorOptions {
+            source: SourceInfo {
+                aliases: BTreeMap::from([("Balance".to_string(), "u128".to_string())]),
+                ..Default::default()
+            },
+            ..options
+        };
+        assert_eq!(
+            generate_with(&container, &options).unwrap_err().to_string(),
+            "type alias `Balance` was skipped, since nothing defines or maps `u128`\n\
+             primitive `u24` is 3 bytes wide, which no zorsh builder reads"
+        );
+    }
+
+    #[test]
+    fn applies_type_mappings_first() {
+        let options = GeneratorOptions {
+            type_mappings: BTreeMap::from([
+                (
+                    "AccountId".to_string(),
+                    TypeMapping::Expression("b.string()".to_string()),
+                ),
+                (
+                    "U128".to_string(),
+                    TypeMapping::Import {
+                        module: "./near".to_string(),
+                        name: "U128Schema".to_string(),
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let output = generate_with(&with_unknown_declarations(), &options).unwrap();
+        assert!(output.starts_with(
+            "import { b } from \"@zorsh/zorsh\";\nimport { U128Schema } from \"./near\";\n\n"
+        ));
+        assert!(output.contains("  owner: b.string(),\n  balance: U128Schema,\n"));
+
+        // A mapping also replaces declarations the container does define
+        let output = generate_with(&BorshSchemaContainer::for_type::<Account>(), &options).unwrap();
+        assert!(!output.contains("AccountIdSchema"));
+        assert!(output.contains("  id: b.string(),\n  friends: b.vec(b.string()),\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "source")]
+    fn exports_type_aliases() {
+        type Balance = u128;
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Wallet {
+            balance: Balance,
+            raw: u128,
+        }
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Escrow(Balance, AccountId);
+
+        let source = SourceInfo::parse(
+            "type Balance = u128;
+            type Timestamp = u64;
+            struct Wallet { balance: Balance, raw: u128 }
+            struct Escrow(Balance, AccountId);",
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            source,
+            ..Default::default()
+        };
+
+        let output = generate_with(&BorshSchemaContainer::for_type::<Wallet>(), &options).unwrap();
+        assert!(output.contains(
+            "export const BalanceSchema = b.u128();\nexport type Balance = b.infer<typeof BalanceSchema>;\n"
+        ));
+        assert!(!output.contains("TimestampSchema"));
+        assert!(output.contains("  balance: BalanceSchema,\n  raw: b.u128(),\n"));
+
+        let output = generate_with(&BorshSchemaContainer::for_type::<Escrow>(), &options).unwrap();
+        assert!(
+            output.contains("export const EscrowSchema = b.tuple(BalanceSchema, AccountIdSchema);")
+        );
+    }
+
+    #[test]
+    fn quotes_reserved_field_names() {
+        let definitions = BTreeMap::from([
+            (
+                "Keywords".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("default".to_string(), "u8".to_string()),
+                        ("function".to_string(), "u8".to_string()),
+                        ("class".to_string(), "Bounded".to_string()),
+                        ("new".to_string(), "u8".to_string()),
+                        ("plain".to_string(), "u8".to_string()),
+                    ]),
+                },
+            ),
+            (
+                "Bounded".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=2,
+                    elements: "u8".to_string(),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Keywords".to_string(), definitions);
+
+        let output = generate(&container).unwrap();
+        assert!(output.contains(
+            "  \"default\": b.u8(),\n  \"function\": b.u8(),\n  \"class\": b.bytes(),\n  \"new\": b.u8(),\n  plain: b.u8(),\n"
+        ));
+        assert!(output.contains("checkLength(value[\"class\"].length, 0, 2, \"Keywords.class\");"));
+    }
+
+    #[test]
+    fn names_module_qualified_declarations() {
+        let config = |field: &str| Definition::Struct {
+            fields: Fields::NamedFields(vec![(field.to_string(), "u8".to_string())]),
+        };
+        let definitions = BTreeMap::from([
+            (
+                "Settings".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("server".to_string(), "my_crate::server::Config".to_string()),
+                        ("client".to_string(), "my_crate::client::Config".to_string()),
+                        ("limits".to_string(), "my_crate::Limits".to_string()),
+                    ]),
+                },
+            ),
+            ("my_crate::server::Config".to_string(), config("port")),
+            ("my_crate::client::Config".to_string(), config("retries")),
+            ("my_crate::Limits".to_string(), config("max")),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Settings".to_string(), definitions);
+
+        let output = generate(&container).unwrap();
+        assert!(output.contains(
+            "export const ServerConfigSchema = b.struct({\n  port: b.u8(),\n});\nexport type ServerConfig = b.infer<typeof ServerConfigSchema>;\n"
+        ));
+        assert!(
+            output.contains("export const ClientConfigSchema = b.struct({\n  retries: b.u8(),\n")
+        );
+        assert!(output.contains("export const LimitsSchema = b.struct({\n"));
+        assert!(output.contains(
+            "  server: ServerConfigSchema,\n  client: ClientConfigSchema,\n  limits: LimitsSchema,\n"
+        ));
+        assert!(!output.contains("::"));
+    }
+
+    #[test]
+    fn strips_raw_identifiers() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct r#Match {
+            r#type: u8,
+            r#enum: Vec<u8>,
+            r#match: u8,
+        }
+
+        let output = generate(&BorshSchemaContainer::for_type::<r#Match>()).unwrap();
+        assert!(output.contains(
+            "export const MatchSchema = b.struct({\n  type: b.u8(),\n  \"enum\": b.bytes(),\n  match: b.u8(),\n});\nexport type Match = b.infer<typeof MatchSchema>;\n"
+        ));
+        assert!(!output.contains("r#"));
+    }
+
+    #[test]
+    fn renames_fields() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        enum Event {
+            Joined { player_id: u64 },
+        }
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Lobby {
+            max_players: u8,
+            _reserved: u8,
+            r#type: u8,
+            events: Vec<Event>,
+        }
+        let container = BorshSchemaContainer::for_type::<Lobby>();
+
+        let options = GeneratorOptions {
+            field_case: FieldCase::Camel,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "  maxPlayers: b.u8(), // max_players\n  _reserved: b.u8(),\n  type: b.u8(),\n  events: b.vec(EventSchema),\n"
+        ));
+        assert!(output.contains("  Joined: b.struct({ playerId: b.u64() }),\n"));
+
+        let options = GeneratorOptions {
+            field_case: FieldCase::Pascal,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("  MaxPlayers: b.u8(), // max_players\n  _Reserved: b.u8(), // _reserved\n  Type: b.u8(), // type\n"));
+        assert!(output.contains("  Joined: b.struct({ PlayerId: b.u64() }),\n"));
+    }
+
+    #[test]
+    fn names_schemas() {
+        let container = BorshSchemaContainer::for_type::<Account>();
+
+        let options = GeneratorOptions {
+            schema_naming: SchemaNaming::Affix {
+                prefix: String::new(),
+                suffix: "Codec".to_string(),
+            },
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export const AccountIdCodec = b.tuple(b.string());\nexport type AccountId = b.infer<typeof AccountIdCodec>;\n"
+        ));
+        assert!(output.contains("  id: AccountIdCodec,\n"));
+
+        let options = GeneratorOptions {
+            schema_naming: SchemaNaming::Custom(Arc::new(|name| format!("${}", name))),
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("export const $Account = b.struct({\n  id: $AccountId,\n"));
+        assert!(output.contains("export type Account = b.infer<typeof $Account>;\n"));
+        assert!(!output.contains("Schema"));
+    }
+
+    #[test]
+    fn imports_the_configured_runtime() {
+        let container = BorshSchemaContainer::for_type::<Account>();
+        let options = GeneratorOptions {
+            runtime: RuntimeImport {
+                module: "@zorsh/zorsh".to_string(),
+                binding: "builders".to_string(),
+            },
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with("import { builders as b } from \"@zorsh/zorsh\";\n\n"));
+
+        let options = GeneratorOptions {
+            runtime: RuntimeImport {
+                module: "../lib/zorsh".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with("import { b } from \"../lib/zorsh\";\n\n"));
+    }
+
+    #[test]
+    fn writes_commonjs() {
+        let definitions = BTreeMap::from([
+            (
+                "Roster".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![("names".to_string(), "Capped".to_string())]),
+                },
+            ),
+            (
+                "Capped".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=8,
+                    elements: "u8".to_string(),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Roster".to_string(), definitions);
+
+        let options = GeneratorOptions {
+            module_format: ModuleFormat::CommonJs,
+            runtime: RuntimeImport {
+                binding: "builders".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with(
+            "import zorsh = require(\"@zorsh/zorsh\");\nimport b = zorsh.builders;\nimport Schema = zorsh.Schema;\n\n"
+        ));
+        assert!(output.ends_with("}\n\nexport = exported;\n"));
+
+        // `test/rust/commonjs-schema.ts`, which CI type-checks, is this output
+        let options = GeneratorOptions {
+            module_format: ModuleFormat::CommonJs,
+            runtime: RuntimeImport {
+                module: "../../src/index".to_string(),
+                ..Default::default()
+            },
+            codec_functions: true,
+            format: FormatOptions {
+                semicolons: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let fixture: String = include_str!("../../../test/rust/commonjs-schema.ts")
+            .lines()
+            .skip_while(|line| line.starts_with("//"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert_eq!(generate_with(&container, &options).unwrap(), fixture);
+
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        assert!(files["Roster.ts"].starts_with(
+            "import index = require(\"../../src/index\")\nimport b = index.b\nimport helpers = require(\"./helpers\")\nimport checkLength = helpers.checkLength\nimport withValidation = helpers.withValidation\n\nnamespace exported {\n"
+        ));
+        assert_eq!(
+            files["index.ts"],
+            "import RosterModule = require(\"./Roster\")\n\nnamespace exported {\n  export const RosterSchema = RosterModule.RosterSchema\n  export const validateRoster = RosterModule.validateRoster\n  export const serializeRoster = RosterModule.serializeRoster\n  export const deserializeRoster = RosterModule.deserializeRoster\n  export type Roster = RosterModule.Roster\n}\n\nexport = exported\n"
+        );
+    }
+
+    #[test]
+    fn writes_type_declarations() {
+        let options = GeneratorOptions {
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let output =
+            generate_with(&BorshSchemaContainer::for_type::<GameState>(), &options).unwrap();
+        assert!(output.contains("export interface Stats {\n  health: number;\n"));
+        assert!(output.contains(
+            "export type Effect =\n  | { Damage: number }\n  | { Heal: number }\n  | { Buff: StatBuff }\n  | { Debuff: StatBuff };\n"
+        ));
+        assert!(output.contains(
+            "  inventory: Array<Item>;\n  equipped_items: Map<string, string>;\n  quest_log: Set<string>;\n  last_login: bigint | null;\n"
+        ));
+        assert!(output.contains(
+            "  | { ChatMessage: { sender: string; message: string } }\n  | { ItemUsed: { item_id: string; target: string | null } }\n"
+        ));
+        assert!(output.contains("export interface GameState {\n  players: Map<string, Player>;\n"));
+        assert!(!output.contains("zorsh"));
+        assert!(!output.contains("Schema"));
+
+        let options = GeneratorOptions {
+            newtype_mode: NewtypeMode::Branded,
+            ..options
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Account>(), &options).unwrap();
+        assert!(output
+            .contains("export type AccountId = string & { readonly __brand: \"AccountId\" };\n"));
+        assert!(output.contains("  friends: Array<AccountId>;\n"));
+    }
+
+    #[test]
+    fn documents_rust_types() {
+        let options = GeneratorOptions {
+            rust_type_docs: true,
+            ..Default::default()
+        };
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "/** Rust: GameState */\nexport const GameStateSchema = b.struct({\n  /** Rust: HashMap<String, Player> */\n  players: b.hashMap(b.string(), PlayerSchema),\n"
+        ));
+        assert!(output.contains("  /** Rust: Option<u64> */\n  last_login: b.option(b.u64()),\n"));
+
+        let options = GeneratorOptions {
+            output_mode: OutputMode::Declarations,
+            ..options
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("/** Rust: Effect */\nexport type Effect =\n"));
+        assert!(output.contains("  /** Rust: Vec<GameEvent> */\n  events: Array<GameEvent>;\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "source")]
+    fn carries_doc_comments() {
+        let source = SourceInfo::parse(
+            r#"
+            /// Key of an [`Account`].
+            struct AccountId(String);
+            /// A user account.
+            ///
+            /// Accounts are created on first login.
+            struct Account {
+                /// The account's own id.
+                id: AccountId,
+                friends: Vec<AccountId>,
+            }
+            "#,
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            source,
+            rust_type_docs: true,
+            ..Default::default()
+        };
+        let container = BorshSchemaContainer::for_type::<Account>();
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("/**\n * Key of an [`Account`].\n *\n * Rust: AccountId\n */\nexport const AccountIdSchema"));
+        assert!(output.contains(
+            "/**\n * A user account.\n *\n * Accounts are created on first login.\n *\n * Rust: Account\n */\nexport const AccountSchema = b.struct({\n  /**\n   * The account's own id.\n   *\n   * Rust: AccountId\n   */\n  id: AccountIdSchema,\n  /** Rust: Vec<AccountId> */\n  friends:"
+        ));
+
+        let source = SourceInfo::parse(
+            r#"
+            enum Effect {
+                /// Lowers health.
+                Damage(u32),
+                Heal(u32),
+                Buff(StatBuff),
+                Debuff(StatBuff),
+            }
+            "#,
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            source,
+            ..Default::default()
+        };
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export const EffectSchema = b.enum({\n  /** Lowers health. */\n  Damage: b.u32(),\n  Heal: b.u32(),\n"
+        ));
+
+        let options = GeneratorOptions {
+            output_mode: OutputMode::Declarations,
+            ..options
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export type Effect =\n  /** Lowers health. */\n  | { Damage: number }\n  | { Heal: number }\n"
+        ));
+    }
+
+    #[test]
+    fn orders_exports_by_dependency() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let output = generate(&container).unwrap();
+        let exports: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.strip_prefix("export const "))
+            .filter_map(|line| line.split("Schema = ").next())
+            .collect();
+        assert_eq!(
+            exports,
+            [
+                "StatBuff",
+                "Effect",
+                "Reward",
+                "GameEvent",
+                "Stats",
+                "Item",
+                "Player",
+                "GameState"
+            ]
+        );
+        assert_eq!(generate(&container).unwrap(), output);
+    }
+
+    #[test]
+    fn rejects_recursive_types() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Tree {
+            children: Vec<Tree>,
+        }
+
+        let error = generate(&BorshSchemaContainer::for_type::<Tree>()).unwrap_err();
+        assert!(
+            matches!(error, ZorshGenError::Unsupported(_)),
+            "{:?}",
+            error
+        );
+        assert_eq!(
+            error.to_string(),
+            "type `Tree` refers to itself (Tree -> Vec<Tree> -> Tree), which zorsh cannot represent"
+        );
+    }
+
+    #[test]
+    fn splits_files_with_an_index() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let files = Parser::new(&ir::Schema::new(&container), &GeneratorOptions::default())
+            .parse_files()
+            .unwrap();
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            [
+                "Effect.ts",
+                "GameEvent.ts",
+                "GameState.ts",
+                "Item.ts",
+                "Player.ts",
+                "Reward.ts",
+                "StatBuff.ts",
+                "Stats.ts",
+                "index.ts"
+            ]
+        );
+        assert!(files["Player.ts"].starts_with(
+            "import { b } from \"@zorsh/zorsh\";\nimport { ItemSchema } from \"./Item\";\nimport { StatsSchema } from \"./Stats\";\n\nexport const PlayerSchema = b.struct({\n"
+        ));
+        assert!(files["Stats.ts"]
+            .starts_with("import { b } from \"@zorsh/zorsh\";\n\nexport const StatsSchema"));
+        assert!(files["index.ts"].starts_with(
+            "export * from \"./StatBuff\";\nexport * from \"./Effect\";\nexport * from \"./Reward\";\n"
+        ));
+        assert!(files["index.ts"].ends_with("export * from \"./GameState\";\n"));
+    }
+
+    #[test]
+    fn splits_helpers_and_types() {
+        let definitions = BTreeMap::from([
+            (
+                "Roster".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![("names".to_string(), "Names".to_string())]),
+                },
+            ),
+            (
+                "Names".to_string(),
+                Definition::Struct {
+                    fields: Fields::UnnamedFields(vec!["Capped".to_string()]),
+                },
+            ),
+            (
+                "Capped".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=8,
+                    elements: "u8".to_string(),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Roster".to_string(), definitions);
+
+        let files = Parser::new(&ir::Schema::new(&container), &GeneratorOptions::default())
+            .parse_files()
+            .unwrap();
+        assert!(files["helpers.ts"].starts_with(
+            "import { type Schema } from \"@zorsh/zorsh\";\n\nexport function checkLength("
+        ));
+        assert!(files["helpers.ts"].contains("\nexport function withValidation<T>("));
+        assert!(files["Roster.ts"].starts_with(
+            "import { b } from \"@zorsh/zorsh\";\nimport { NamesSchema, validateNames } from \"./Names\";\nimport { withValidation } from \"./helpers\";\n\n"
+        ));
+
+        let options = GeneratorOptions {
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(names, ["Names.d.ts", "Roster.d.ts", "index.d.ts"]);
+        assert!(files["Roster.d.ts"].starts_with(
+            "import { type Names } from \"./Names\";\n\nexport interface Roster {\n  names: Names;\n}\n"
+        ));
+    }
+
+    #[test]
+    fn writes_a_banner() {
+        let container = BorshSchemaContainer::for_type::<Account>();
+        let options = GeneratorOptions {
+            banner: Some(Banner {
+                source: Some("account.bin".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        let hash = schema_hash(&container);
+        assert_eq!(hash.len(), 64);
+        assert!(output.starts_with(&format!(
+            "// Generated by zorsh-gen {}. Do not edit by hand.\n// Source: account.bin\n// Schema hash: sha256:{}\n\nimport {{ b }} from \"@zorsh/zorsh\";\n",
+            env!("CARGO_PKG_VERSION"),
+            hash
+        )));
+        assert_eq!(generate_with(&container, &options).unwrap(), output);
+        assert_ne!(
+            schema_hash(&BorshSchemaContainer::for_type::<GameState>()),
+            hash
+        );
+
+        let options = GeneratorOptions {
+            banner: Some(Banner {
+                timestamp: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("\n// Generated at: 20"));
+        assert!(!output.contains("// Source:"));
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096);
+        assert_eq!(utc_timestamp(time), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn writes_codec_functions() {
+        let options = GeneratorOptions {
+            codec_functions: true,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Account>(), &options).unwrap();
+        assert!(output.contains(
+            "export type Account = b.infer<typeof AccountSchema>;\n\nexport function serializeAccount(value: Account): Uint8Array {\n  return AccountSchema.serialize(value);\n}\n\nexport function deserializeAccount(bytes: Uint8Array): Account {\n  return AccountSchema.deserialize(bytes);\n}\n"
+        ));
+        assert!(
+            output.contains("export function serializeAccountId(value: AccountId): Uint8Array {\n")
+        );
+
+        let options = GeneratorOptions {
+            module_format: ModuleFormat::CommonJs,
+            ..options
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Account>(), &options).unwrap();
+        assert!(output
+            .contains("\n  export function deserializeAccount(bytes: Uint8Array): Account {\n"));
+        assert!(output.ends_with("}\n\nexport = exported;\n"));
+    }
+
+    #[test]
+    fn writes_type_guards() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        enum Outcome {
+            Heal(u32),
+            Miss,
+        }
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Turn {
+            state: GameState,
+            outcome: Outcome,
+        }
+
+        let options = GeneratorOptions {
+            type_guards: true,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Turn>(), &options).unwrap();
+        assert!(output.contains(
+            "export type GameEvent = b.infer<typeof GameEventSchema>;\n\nexport function isPlayerJoined(value: GameEvent): value is Extract<GameEvent, { PlayerJoined: unknown }> {\n  return \"PlayerJoined\" in value;\n}\n"
+        ));
+        assert!(output.contains("export function isBattleResult(value: GameEvent): value is Extract<GameEvent, { BattleResult: unknown }> {\n"));
+        assert!(output.contains("export function isEffectHeal(value: Effect): value is Extract<Effect, { Heal: unknown }> {\n"));
+        assert!(output.contains("export function isOutcomeHeal(value: Outcome)"));
+        assert!(output.contains("export function isMiss(value: Outcome)"));
+        assert!(!output.contains("function isTurn"));
+    }
+
+    #[test]
+    fn writes_tag_constants() {
+        let options = GeneratorOptions {
+            tag_constants: true,
+            ..Default::default()
+        };
+        let output =
+            generate_with(&BorshSchemaContainer::for_type::<GameState>(), &options).unwrap();
+        assert!(output.contains(
+            "export type GameEvent = b.infer<typeof GameEventSchema>;\n\nexport const GameEventTag = {\n  PlayerJoined: 0,\n  PlayerLeft: 1,\n  ChatMessage: 2,\n  ItemUsed: 3,\n  BattleResult: 4,\n} as const;\n"
+        ));
+        assert!(output.contains("export const EffectTag = {\n  Damage: 0,\n"));
+        assert!(!output.contains("PlayerTag"));
+    }
+
+    #[test]
+    fn writes_variant_constructors() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        enum Outcome {
+            Hit { damage: u32 },
+            Miss,
+        }
+
+        let options = GeneratorOptions {
+            variant_constructors: true,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Outcome>(), &options).unwrap();
+        assert!(output.contains(
+            "export type Outcome = b.infer<typeof OutcomeSchema>;\n\nexport const Outcome = {\n  Hit: (value: Extract<Outcome, { Hit: unknown }>[\"Hit\"]): Outcome => ({ Hit: value }),\n  Miss: (): Outcome => ({ Miss: {} }),\n} as const;\n"
+        ));
+    }
+
+    #[test]
+    fn writes_mocks() {
+        let options = GeneratorOptions {
+            mocks: true,
+            ..Default::default()
+        };
+        let output =
+            generate_with(&BorshSchemaContainer::for_type::<GameState>(), &options).unwrap();
+        assert!(output.starts_with("import { b } from \"@zorsh/zorsh\";\n\nfunction mockRandom("));
+        assert!(output.contains(
+            "export function mockStats(seed?: number | (() => number)): Stats {\n  const random = mockRandom(seed);\n  return { health: mockInt(random, 0, 4294967295), mana: mockInt(random, 0, 4294967295),"
+        ));
+        assert!(output.contains(
+            "  return { name: mockString(random), level: mockInt(random, 0, 255), stats: mockStats(random), inventory: mockArray(random, 0, 3, () => mockItem(random)), equipped_items: new Map(mockArray(random, 0, 3, () => [mockString(random), mockString(random)] as const)), quest_log: new Set(mockArray(random, 0, 3, () => mockString(random))), last_login: random() < 0.5 ? null : BigInt(mockInt(random, 0, 1000000)) };\n"
+        ));
+        assert!(output.contains(
+            "  return mockPick(random, [() => ({ Damage: mockInt(random, 0, 4294967295) }), () => ({ Heal: mockInt(random, 0, 4294967295) }), () => ({ Buff: mockStatBuff(random) }), () => ({ Debuff: mockStatBuff(random) })]);\n"
+        ));
+        assert!(output.contains(
+            "() => ({ ChatMessage: { sender: mockString(random), message: mockString(random) } })"
+        ));
+    }
+
+    #[test]
+    fn mocks_respect_length_bounds() {
+        let definitions = BTreeMap::from([
+            (
+                "Hash".to_string(),
+                Definition::Sequence {
+                    length_width: Definition::ARRAY_LENGTH_WIDTH,
+                    length_range: 32..=32,
+                    elements: "u8".to_string(),
+                },
+            ),
+            (
+                "Block".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("hash".to_string(), "Hash".to_string()),
+                        ("signers".to_string(), "Signers".to_string()),
+                    ]),
+                },
+            ),
+            (
+                "Signers".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 2..=100,
+                    elements: "Hash".to_string(),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Block".to_string(), definitions);
+        let options = GeneratorOptions {
+            mocks: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "  return { hash: mockBytes(random, 32, 32), signers: mockArray(random, 2, 5, () => mockBytes(random, 32, 32)) };\n"
+        ));
+    }
+
+    #[test]
+    fn writes_byte_sizes() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Header {
+            magic: [u8; 4],
+            version: u16,
+            flags: Option<u8>,
+            stats: Stats,
+        }
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        enum Packet {
+            Ping,
+            Header(Header),
+        }
+
+        let options = GeneratorOptions {
+            byte_sizes: true,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Packet>(), &options).unwrap();
+        assert!(output.contains(
+            "export type Stats = b.infer<typeof StatsSchema>;\n\nexport const STATS_BYTE_SIZE = 24;\n"
+        ));
+        assert!(output.contains("export const HEADER_MIN_BYTE_SIZE = 31;\n"));
+        assert!(output.contains("export const PACKET_MIN_BYTE_SIZE = 1;\n"));
+
+        let output =
+            generate_with(&BorshSchemaContainer::for_type::<GameState>(), &options).unwrap();
+        assert!(output.contains("export const STAT_BUFF_MIN_BYTE_SIZE = 8;\n"));
+        assert!(output.contains("export const GAME_STATE_MIN_BYTE_SIZE = 16;\n"));
+    }
+
+    #[test]
+    fn writes_field_offsets() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Header {
+            magic: [u8; 4],
+            version: u16,
+            stats: Stats,
+            flags: Option<u8>,
+        }
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Packed(u8, [u32; 2], bool);
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Packets(Header, Packed);
+
+        let options = GeneratorOptions {
+            field_offsets: true,
+            field_case: FieldCase::Camel,
+            ..Default::default()
+        };
+        let output = generate_with(&BorshSchemaContainer::for_type::<Packets>(), &options).unwrap();
+        assert!(output.contains(
+            "export const StatsOffsets = {\n  health: { offset: 0, size: 4 },\n  mana: { offset: 4, size: 4 },\n  attack: { offset: 8, size: 4 },\n  defense: { offset: 12, size: 4 },\n  magicAttack: { offset: 16, size: 4 },\n  magicDefense: { offset: 20, size: 4 },\n} as const;\n"
+        ));
+        assert!(output.contains(
+            "export const PackedOffsets = {\n  \"0\": { offset: 0, size: 1 },\n  \"1\": { offset: 1, size: 8 },\n  \"2\": { offset: 9, size: 1 },\n} as const;\n"
+        ));
+        assert!(!output.contains("HeaderOffsets"));
+        assert!(!output.contains("PacketsOffsets"));
+    }
+
+    #[test]
+    fn filters_types() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            filter: TypeFilter {
+                include: vec!["Item".to_string()],
+                exclude: vec!["Stat*".to_string()],
+            },
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("export const ItemSchema = "));
+        // Dependencies are exported whether or not they match
+        assert!(output.contains("export const EffectSchema = "));
+        assert!(output.contains("export const StatBuffSchema = "));
+        assert!(!output.contains("PlayerSchema"));
+        assert!(!output.contains("StatsSchema"));
+        assert!(!output.contains("GameStateSchema"));
+
+        let options = GeneratorOptions {
+            filter: TypeFilter {
+                include: Vec::new(),
+                exclude: vec!["Game*".to_string(), "?ffect".to_string()],
+            },
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("export const PlayerSchema = "));
+        assert!(output.contains("export const EffectSchema = "));
+        assert!(!output.contains("GameEventSchema"));
+        assert!(!output.contains("GameStateSchema"));
+
+        assert!(glob_matches(
+            "my_crate::*::Player",
+            "my_crate::a::b::Player"
+        ));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("Player?", "Player"));
+    }
+
+    #[test]
+    fn reports_what_was_generated() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            filter: TypeFilter {
+                include: vec!["Item".to_string()],
+                exclude: Vec::new(),
+            },
+            ..Default::default()
+        };
+        let (files, report) = render_schema(&container, "-", &options).unwrap();
+        assert_eq!(report.declarations, container.definitions().count());
+        assert_eq!(report.exports, ["StatBuff", "Effect", "Item"]);
+        assert_eq!(
+            report.skipped,
+            ["GameEvent", "GameState", "Player", "Reward", "Stats"]
+        );
+        assert!(report.unresolved.is_empty());
+        assert_eq!(
+            report.files,
+            BTreeMap::from([("-".into(), files[0].1.len())])
+        );
+
+        let (_, report) = render_schema(
+            &with_unknown_declarations(),
+            "-",
+            &GeneratorOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(report.exports, ["Contract"]);
+        assert_eq!(report.unresolved, ["AccountId", "U128"]);
+        assert_eq!(
+            report.to_string(),
+            "declarations: 1, exported: 1, skipped by filters: 0, unresolved: 2 (AccountId, U128)\n-: 212 bytes"
+        );
+    }
+
+    #[test]
+    fn renames_types() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            renames: BTreeMap::from([
+                ("Player".to_string(), "Hero".to_string()),
+                ("GameState".to_string(), "World".to_string()),
+            ]),
+            codec_functions: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("export const HeroSchema = b.struct({\n"));
+        assert!(output.contains("export type Hero = b.infer<typeof HeroSchema>;\n"));
+        assert!(output.contains("  players: b.hashMap(b.string(), HeroSchema),\n"));
+        assert!(output.contains("export function serializeWorld(value: World): Uint8Array {\n"));
+        assert!(!output.contains("PlayerSchema"));
+        assert!(!output.contains("GameStateSchema"));
+
+        let options = GeneratorOptions {
+            renames: BTreeMap::from([
+                ("Player".to_string(), "Item".to_string()),
+                ("Stats".to_string(), "class".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let error = generate_with(&container, &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`Stats` can't be renamed to `class`, which isn't a TypeScript identifier\n`Item` and `Player` would both be exported as `Item`"
+        );
+    }
+
+    #[test]
+    fn prefixes_exports() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            prefix: "Ft".to_string(),
+            type_guards: true,
+            byte_sizes: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("export const FtStatsSchema = b.struct({\n"));
+        assert!(output.contains("export type FtStats = b.infer<typeof FtStatsSchema>;\n"));
+        assert!(output.contains("  stats: FtStatsSchema,\n"));
+        assert!(output.contains("export const FT_STATS_BYTE_SIZE = 24;\n"));
+        assert!(output.contains("export function isFtDamage(value: FtEffect)"));
+        assert!(!output.contains(" StatsSchema"));
+
+        let options = GeneratorOptions {
+            prefix: "1".to_string(),
+            ..Default::default()
+        };
+        assert!(generate_with(&container, &options).is_err());
+    }
+
+    #[test]
+    fn wraps_exports_in_a_namespace() {
+        let container = BorshSchemaContainer::for_type::<Stats>();
+        let options = GeneratorOptions {
+            namespace: Some("Market".to_string()),
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "import { b } from \"@zorsh/zorsh\";\n\nexport namespace Market {\n  export const StatsSchema = b.struct({\n    health: b.u32(),\n"
+        ));
+        assert!(output.ends_with("  export type Stats = b.infer<typeof StatsSchema>;\n}\n"));
+
+        let options = GeneratorOptions {
+            namespace: Some("Market".to_string()),
+            module_format: ModuleFormat::CommonJs,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "\nnamespace exported {\n  export namespace Market {\n    export const StatsSchema = "
+        ));
+        assert!(output.ends_with("  }\n}\n\nexport = exported;\n"));
+
+        let options = GeneratorOptions {
+            namespace: Some("Market".to_string()),
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with("export namespace Market {\n  export interface Stats {\n"));
+    }
+
+    #[test]
+    fn exports_a_schema_object() {
+        let container = BorshSchemaContainer::for_type::<Player>();
+        let options = GeneratorOptions {
+            export_style: ExportStyle::Object,
+            codec_functions: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("\nconst StatsSchema = b.struct({\n"));
+        assert!(output.contains("\ntype Stats = b.infer<typeof StatsSchema>;\n"));
+        assert!(!output.contains("\nexport type "));
+        assert!(output.contains("\nexport function serializeStats(value: Stats): Uint8Array {\n"));
+        assert!(!output.contains("export const StatsSchema"));
+        assert!(output.ends_with(
+            "export const schemas = {\n  StatBuff: StatBuffSchema,\n  Effect: EffectSchema,\n  Item: ItemSchema,\n  Stats: StatsSchema,\n  Player: PlayerSchema,\n} as const;\n\nexport namespace types {\n  export type StatBuff = b.infer<typeof StatBuffSchema>;\n  export type Effect = b.infer<typeof EffectSchema>;\n  export type Item = b.infer<typeof ItemSchema>;\n  export type Stats = b.infer<typeof StatsSchema>;\n  export type Player = b.infer<typeof PlayerSchema>;\n}\n"
+        ));
+
+        let options = GeneratorOptions {
+            export_style: ExportStyle::Object,
+            readonly_types: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("\ninterface Stats {\n"));
+        assert!(output.contains("\n  export interface Stats {\n"));
+        assert!(!output.contains("\nexport interface "));
+
+        let options = GeneratorOptions {
+            export_style: ExportStyle::Object,
+            module_format: ModuleFormat::CommonJs,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("\n  export const schemas = {\n"));
+        assert!(output.ends_with("}\n\nexport = exported;\n"));
+    }
+
+    #[test]
+    fn writes_a_schema_registry() {
+        let container = BorshSchemaContainer::for_type::<Player>();
+        let options = GeneratorOptions {
+            schema_registry: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with("import { b, type Schema } from \"@zorsh/zorsh\";\n"));
+        assert!(output.ends_with(
+            "export const schemaRegistry: Record<string, Schema<unknown>> = {\n  \"StatBuff\": StatBuffSchema,\n  \"Effect\": EffectSchema,\n  \"Item\": ItemSchema,\n  \"Stats\": StatsSchema,\n  \"Player\": PlayerSchema,\n};\n"
+        ));
+
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        assert!(files["index.ts"].starts_with(
+            "import { type Schema } from \"@zorsh/zorsh\";\nimport { StatBuffSchema } from \"./StatBuff\";\n"
+        ));
+        assert!(files["index.ts"].ends_with(
+            "export * from \"./Player\";\n\nexport const schemaRegistry: Record<string, Schema<unknown>> = {\n  \"StatBuff\": StatBuffSchema,\n  \"Effect\": EffectSchema,\n  \"Item\": ItemSchema,\n  \"Stats\": StatsSchema,\n  \"Player\": PlayerSchema,\n};\n"
+        ));
+    }
+
+    #[test]
+    fn writes_a_hash_constant() {
+        let container = BorshSchemaContainer::for_type::<Stats>();
+        let hash = schema_hash(&container);
+        let options = GeneratorOptions {
+            hash_constant: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with(&format!(
+            "import {{ b }} from \"@zorsh/zorsh\";\n\nexport const SCHEMA_HASH = \"sha256:{}\";\n\nexport const StatsSchema",
+            hash
+        )));
+
+        let options = GeneratorOptions {
+            hash_constant: true,
+            prefix: "Ft".to_string(),
+            module_format: ModuleFormat::CommonJs,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(&format!(
+            "\nnamespace exported {{\n  export const FT_SCHEMA_HASH = \"sha256:{}\";\n",
+            hash
+        )));
+
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        assert!(files["index.ts"].ends_with(&format!(
+            "  export type FtStats = FtStatsModule.FtStats;\n\n  export const FT_SCHEMA_HASH = \"sha256:{}\";\n}}\n\nexport = exported;\n",
+            hash
+        )));
+    }
+
+    #[test]
+    fn writes_a_source_map() {
+        let container = BorshSchemaContainer::for_type::<Player>();
+        let options = GeneratorOptions {
+            source_map: true,
+            ..Default::default()
+        };
+        let schema = ir::Schema::new(&container);
+        let mut parser = Parser::new(&schema, &options);
+        let output = parser.parse().unwrap();
+        let line = output
+            .lines()
+            .position(|line| line.starts_with("export const PlayerSchema"))
+            .unwrap();
+        let map = parser.source_map(&BTreeMap::from([("game.ts".to_string(), output)]));
+        assert!(map.starts_with(&format!(
+            "{{\n  \"version\": 1,\n  \"schemaHash\": \"sha256:{}\",\n  \"entries\": [\n    {{\n      \"rust\": \"StatBuff\",\n      \"name\": \"StatBuff\",\n      \"schema\": \"StatBuffSchema\",\n      \"file\": \"game.ts\",\n      \"line\": 3\n    }},\n",
+            schema_hash(&container)
+        )));
+        assert!(map.contains(&format!(
+            "      \"rust\": \"Player\",\n      \"name\": \"Player\",\n      \"schema\": \"PlayerSchema\",\n      \"file\": \"game.ts\",\n      \"line\": {}\n",
+            line + 1
+        )));
+
+        let options = GeneratorOptions {
+            source_map: true,
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        assert!(files["zorsh-map.json"].contains(
+            "      \"rust\": \"Stats\",\n      \"name\": \"Stats\",\n      \"file\": \"Stats.d.ts\",\n      \"line\": 1\n"
+        ));
+    }
+
+    #[test]
+    fn writes_union_types() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        enum Shape {
+            Empty,
+            Circle(u32),
+            Rect { width: u32, height: u32 },
+        }
+
+        let container = BorshSchemaContainer::for_type::<Shape>();
+        let options = GeneratorOptions {
+            union_types: Some(UnionStyle::Keyed),
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export const ShapeSchema = b.enum({\n  Empty: b.unit(),\n  Circle: b.u32(),\n  Rect: b.struct({ width: b.u32(), height: b.u32() }),\n});\nexport type Shape =\n  | { Empty: Record<string, never> }\n  | { Circle: number }\n  | { Rect: { width: number; height: number } };\n"
+        ));
+        assert!(!output.contains("Tagged"));
+
+        let options = GeneratorOptions {
+            union_types: Some(UnionStyle::Tagged {
+                field: "kind".to_string(),
+            }),
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export type ShapeTagged =\n  | { kind: \"Empty\" }\n  | { kind: \"Circle\"; value: number }\n  | { kind: \"Rect\"; width: number; height: number };\n"
+        ));
+        assert!(output.contains(
+            "export function toShapeTagged(value: Shape): ShapeTagged {\n  if (\"Empty\" in value) {\n    return { kind: \"Empty\" };\n  }\n  if (\"Circle\" in value) {\n    return { kind: \"Circle\", value: value.Circle };\n  }\n  if (\"Rect\" in value) {\n    return { kind: \"Rect\", ...value.Rect };\n  }\n  return value;\n}\n"
+        ));
+        assert!(output.contains(
+            "export function fromShapeTagged(value: ShapeTagged): Shape {\n  switch (value.kind) {\n    case \"Empty\": {\n      return { Empty: {} };\n    }\n    case \"Circle\": {\n      return { Circle: value.value };\n    }\n    case \"Rect\": {\n      const { kind: _, ...payload } = value;\n      return { Rect: payload };\n    }\n  }\n}\n"
+        ));
+
+        let options = GeneratorOptions {
+            union_types: Some(UnionStyle::Tagged {
+                field: "width".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            generate_with(&container, &options).unwrap_err().to_string(),
+            "variant `Shape::Rect` has a field named `width`, which is also the tag of its tagged union"
+        );
+    }
+
+    #[test]
+    fn inlines_or_references_definitions() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            inlining: Inlining::Inline,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert_eq!(output.matches("export const").count(), 1);
+        assert!(output.contains("    stats: b.struct({\n      health: b.u32(),\n"));
+        assert!(output.contains("      effects: b.vec(b.enum({\n        Damage: b.u32(),\n"));
+
+        let options = GeneratorOptions {
+            inlining: Inlining::Inline,
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with(
+            "export interface GameState {\n  players: Map<string, { name: string; level: number; stats: { health: number; "
+        ));
+
+        let options = GeneratorOptions {
+            inlining: Inlining::Reference,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export const GameEventChatMessageSchema = b.struct({\n  sender: b.string(),\n  message: b.string(),\n});\n"
+        ));
+        assert!(output.contains("  ChatMessage: GameEventChatMessageSchema,\n"));
+        // Single-field payloads are still written as their field
+        assert!(output.contains("  Damage: b.u32(),\n"));
+    }
+
+    #[test]
+    fn hoists_duplicate_payloads() {
+        let container = BorshSchemaContainer::for_type::<GameEvent>();
+        let options = GeneratorOptions {
+            hoist_duplicates: true,
+            mocks: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "/** The payload of `GameEvent::PlayerJoined` and `GameEvent::PlayerLeft`. */\nexport const GameEventPlayerJoinedSchema = b.struct({ player_id: b.string() });\nexport type GameEventPlayerJoined = b.infer<typeof GameEventPlayerJoinedSchema>;\n"
+        ));
+        assert!(output.contains(
+            "  PlayerJoined: GameEventPlayerJoinedSchema,\n  PlayerLeft: GameEventPlayerJoinedSchema,\n  ChatMessage: b.struct({ sender: b.string(), message: b.string() }),\n"
+        ));
+        assert!(output.contains("() => ({ PlayerLeft: mockGameEventPlayerJoined(random) })"));
+
+        let options = GeneratorOptions {
+            hoist_duplicates: true,
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("  | { PlayerLeft: GameEventPlayerJoined }\n"));
+    }
+
+    #[test]
+    fn decodes_64_bit_integers_as_numbers() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Balance {
+            amount: u64,
+            delta: i64,
+            total: u128,
+            updated_at: Option<u64>,
+        }
+
+        let container = BorshSchemaContainer::for_type::<Balance>();
+        let options = GeneratorOptions {
+            large_integers: LargeIntegers::Number,
+            mocks: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.starts_with(
+            "import { b, type Schema } from \"@zorsh/zorsh\";\n\nfunction safeNumber("
+        ));
+        // Laying out the output mustn't re-indent the helper's second callback
+        assert!(output.contains(SAFE_NUMBER_HELPER));
+        assert!(!output.contains(".registry"));
+        assert!(output.contains(
+            "  }, (value) => {\n    if (!Number.isSafeInteger(value)) {\n      throw new RangeError("
+        ));
+        assert!(output.contains(
+            "export const BalanceSchema = b.struct({\n  amount: safeNumber(b.u64()),\n  delta: safeNumber(b.i64()),\n  total: b.u128(),\n  updated_at: b.option(safeNumber(b.u64())),\n});\n"
+        ));
+
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        assert!(files["helpers.ts"].starts_with(
+            "import { b, type Schema } from \"@zorsh/zorsh\";\n\nexport function safeNumber("
+        ));
+
+        let options = GeneratorOptions {
+            large_integers: LargeIntegers::Number,
+            output_mode: OutputMode::Declarations,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "  amount: number;\n  delta: number;\n  total: bigint;\n  updated_at: number | null;\n"
+        ));
+    }
+
+    #[test]
+    fn decodes_large_integers_as_strings() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Balance {
+            amount: u64,
+            total: i128,
+            count: u32,
+        }
+
+        let container = BorshSchemaContainer::for_type::<Balance>();
+        let options = GeneratorOptions {
+            large_integers: LargeIntegers::String,
+            mocks: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(DECIMAL_STRING_HELPER));
+        assert!(!output.contains("mapSchema"));
+        assert!(output.contains(
+            "export const BalanceSchema = b.struct({\n  amount: decimalString(b.u64()),\n  total: decimalString(b.i128()),\n  count: b.u32(),\n});\n"
+        ));
+        assert!(output.contains("amount: String(mockInt(random, 0, 1000000))"));
+    }
+
+    #[test]
+    #[cfg(feature = "backend-json-schema")]
+    fn writes_json_schema() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            target: Target::JsonSchema,
+            ..Default::default()
+        };
+        let output = Parser::new(&ir::Schema::new(&container), &options)
+            .json_schema()
+            .unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(schema["title"], "GameState");
+        assert_eq!(schema["$ref"], "#/$defs/GameState");
+        let defs = &schema["$defs"];
+        assert_eq!(
+            defs["GameState"]["properties"]["players"]["additionalProperties"],
+            serde_json::json!({ "$ref": "#/$defs/Player" })
+        );
+        assert_eq!(
+            defs["Player"]["properties"]["last_login"],
+            serde_json::json!({
+                "anyOf": [{ "type": "string", "pattern": "^[0-9]+$" }, { "type": "null" }]
+            })
+        );
+        assert_eq!(
+            defs["Stats"]["properties"]["health"],
+            serde_json::json!({ "type": "integer", "minimum": 0, "maximum": 4294967295u32 })
+        );
+        assert_eq!(
+            defs["Effect"]["oneOf"][0]["properties"]["Damage"],
+            serde_json::json!({ "type": "integer", "minimum": 0, "maximum": 4294967295u32 })
+        );
+        assert_eq!(
+            defs["Player"]["properties"]["quest_log"]["uniqueItems"],
+            true
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "backend-zod")]
+    fn writes_zod_schemas() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let options = GeneratorOptions {
+            target: Target::Zod,
+            ..Default::default()
+        };
+        let output = Parser::new(&ir::Schema::new(&container), &options)
+            .zod_schema()
+            .unwrap();
+
+        assert!(output.starts_with("import { z } from \"zod\";\n\nexport const StatsSchema"));
+        assert!(output.contains(
+            "export const StatBuffSchema = z.strictObject({\n  stat: z.string(),\n  amount: z.number().int().min(-2147483648).max(2147483647),\n});\nexport type StatBuff = z.infer<typeof StatBuffSchema>;\n"
+        ));
+        assert!(output.contains(
+            "export const EffectSchema = z.union([\n  z.strictObject({ Damage: z.number().int().min(0).max(4294967295) }),\n"
+        ));
+        assert!(output.contains("  quest_log: z.set(z.string()),\n"));
+        assert!(output
+            .contains("  last_login: z.bigint().min(0n).max(18446744073709551615n).nullable(),\n"));
+        assert!(output.ends_with("export type GameState = z.infer<typeof GameStateSchema>;\n"));
+
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Tree {
+            children: Vec<Tree>,
+            hash: [u8; 4],
+        }
+
+        let container = BorshSchemaContainer::for_type::<Tree>();
+        let options = GeneratorOptions {
+            target: Target::Zod,
+            large_integers: LargeIntegers::String,
+            ..Default::default()
+        };
+        let output = Parser::new(&ir::Schema::new(&container), &options)
+            .zod_schema()
+            .unwrap();
+        assert!(output.contains(
+            "export interface Tree {\n  children: Array<Tree>;\n  hash: Uint8Array;\n}\nexport const TreeSchema: z.ZodType<Tree> = z.strictObject({\n  children: z.array(z.lazy(() => TreeSchema)),\n  hash: z.instanceof(Uint8Array).refine((value) => value.length === 4),\n});\n"
+        ));
+    }
+
+    #[test]
+    fn writes_readonly_types() {
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        struct Inventory {
+            items: Vec<String>,
+            counts: HashMap<String, u32>,
+            slot: (u8, u8),
+            owner: Option<Owner>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(borsh::BorshSchema)]
+        enum Owner {
+            Player { id: u32 },
+            World,
+        }
+
+        let container = BorshSchemaContainer::for_type::<Inventory>();
+        let options = GeneratorOptions {
+            readonly_types: true,
+            codec_functions: true,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains(
+            "export type Owner =\n  | { readonly Player: { readonly id: number } }\n  | { readonly World: Record<string, never> };\n"
+        ));
+        assert!(output.contains(
+            "export interface Inventory {\n  readonly items: ReadonlyArray<string>;\n  readonly counts: ReadonlyMap<string, number>;\n  readonly slot: readonly [number, number];\n  readonly owner: Owner | null;\n}\n"
+        ));
+        assert!(output.contains(
+            "export function serializeInventory(value: Inventory): Uint8Array {\n  return InventorySchema.serialize(value as b.infer<typeof InventorySchema>);\n}\n"
+        ));
+        assert!(!output.contains("export type Inventory = b.infer"));
+    }
+
+    #[test]
+    #[cfg(feature = "source")]
+    fn splits_files_by_module() {
+        let source = SourceInfo::parse(
+            r#"
+            mod game {
+                struct GameState;
+                mod players {
+                    struct Player;
+                    struct Stats;
+                }
+            }
+            mod items {
+                struct Item;
+                enum Effect {}
+                struct StatBuff;
+            }
+            "#,
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            source,
+            module_files: true,
+            ..Default::default()
+        };
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            [
+                "game.ts",
+                "game/players.ts",
+                "index.ts",
+                "items.ts",
+                "lib.ts"
+            ]
+        );
+        assert!(files["game/players.ts"].starts_with(
+            "import { b } from \"@zorsh/zorsh\";\nimport { ItemSchema } from \"../items\";\n\nexport const StatsSchema"
+        ));
+        assert!(files["items.ts"].contains("export type Effect = "));
+        assert!(files["items.ts"].contains("export type Item = "));
+        assert!(files["index.ts"].contains("export * from \"./game/players\";\n"));
+
+        let source = SourceInfo::parse("mod game { struct GameState; struct Stats; }").unwrap();
+        let options = GeneratorOptions {
+            source,
+            module_files: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Parser::new(&ir::Schema::new(&container), &options).parse_files().unwrap_err().to_string(),
+            "generated files would import each other's schemas in a cycle (game -> lib -> game), which modules can't initialize; group these types differently"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn carries_over_custom_regions() {
+        let existing = "// zorsh:begin-custom\nimport { log } from \"./log\";\n// zorsh:end-custom\n\nexport const ASchema = b.u8();\nexport type A = b.infer<typeof ASchema>;\n\n// zorsh:begin-custom\nexport const isSmall = (a: A) => a < 10;\n// zorsh:end-custom\n\nexport const BSchema = b.u16();\n";
+        let generated = "export const ASchema = b.u32();\nexport type A = b.infer<typeof ASchema>;\n\nexport const CSchema = b.u8();\n";
+        let merged = custom::carry_over(existing, generated).unwrap();
+        assert_eq!(
+            merged,
+            "// zorsh:begin-custom\nimport { log } from \"./log\";\n// zorsh:end-custom\n\nexport const ASchema = b.u32();\nexport type A = b.infer<typeof ASchema>;\n\n// zorsh:begin-custom\nexport const isSmall = (a: A) => a < 10;\n// zorsh:end-custom\n\nexport const CSchema = b.u8();\n"
+        );
+        assert_eq!(custom::carry_over(&merged, generated).unwrap(), merged);
+
+        // Regions whose generated line is gone end up at the end
+        let generated = "export const ASchema = b.u32();\n";
+        assert!(custom::carry_over(existing, generated).unwrap().ends_with(
+            "export const ASchema = b.u32();\n\n// zorsh:begin-custom\nexport const isSmall = (a: A) => a < 10;\n// zorsh:end-custom\n"
+        ));
+
+        assert_eq!(
+            custom::carry_over("// zorsh:begin-custom\nconst a = 1;\n", generated).unwrap_err(),
+            "line 1: `// zorsh:begin-custom` is never closed by `// zorsh:end-custom`"
+        );
+    }
+
+    #[test]
+    fn writes_a_roundtrip_spec() {
+        let container = BorshSchemaContainer::for_type::<Player>();
+        let options = GeneratorOptions {
+            roundtrip_spec: true,
+            ..Default::default()
+        };
+        let schema = ir::Schema::new(&container);
+        let mut parser = Parser::new(&schema, &options);
+        let output = parser.parse().unwrap();
+        assert!(output.contains("export function mockPlayer("));
+        assert!(output.contains("weight: Math.fround(random() * 100)"));
+        let spec = parser.roundtrip_spec("./schema");
+        assert!(spec.starts_with(
+            "import { describe, expect, it } from \"vitest\";\nimport { StatBuffSchema, mockStatBuff, EffectSchema, mockEffect, "
+        ));
+        assert!(spec.contains(
+            "\ndescribe(\"zorsh schemas\", () => {\n  it(\"round-trips StatBuff\", () => {\n    const value = mockStatBuff(1);\n    expect(StatBuffSchema.deserialize(StatBuffSchema.serialize(value))).toEqual(value);\n  });\n\n  it(\"round-trips Effect\", () => {\n"
+        ));
+        assert!(spec.ends_with("  });\n});\n"));
+
+        let options = GeneratorOptions {
+            roundtrip_spec: true,
+            type_mappings: BTreeMap::from([(
+                "Stats".to_string(),
+                TypeMapping::Expression("customStats".to_string()),
+            )]),
+            ..Default::default()
+        };
+        let schema = ir::Schema::new(&container);
+        let mut parser = Parser::new(&schema, &options);
+        parser.parse().unwrap();
+        let spec = parser.roundtrip_spec("./schema");
+        assert!(spec.contains("  it.todo(\"round-trips Player once it can be mocked\");\n"));
+        assert!(spec.contains("  it(\"round-trips Item\", () => {\n"));
+
+        let files = Parser::new(&ir::Schema::new(&container), &options)
+            .parse_files()
+            .unwrap();
+        assert!(files["schemas.test.ts"].contains(" } from \"./index\";\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn merges_schema_containers() {
+        mod other {
+            #[allow(dead_code)]
+            #[derive(borsh::BorshSchema)]
+            pub struct Item {
+                pub id: u8,
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("zorsh_merge_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, container: BorshSchemaContainer| {
+            let path = dir.join(name);
+            std::fs::write(&path, borsh::to_vec(&container).unwrap()).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let game = write("game.bin", BorshSchemaContainer::for_type::<GameState>());
+        let account = write("account.bin", BorshSchemaContainer::for_type::<Account>());
+        let other = write("other.bin", BorshSchemaContainer::for_type::<other::Item>());
+
+        let output = dir.join("merged.ts").to_string_lossy().into_owned();
+        let options = GeneratorOptions {
+            banner: Some(Banner::default()),
+            ..Default::default()
+        };
+        generate_merged_zorsh_schema(&[&game, &account], &output, &options).unwrap();
+        let merged = std::fs::read_to_string(&output).unwrap();
+        assert!(merged.contains("// Source: game.bin, account.bin\n"));
+        assert!(merged.contains("export const GameStateSchema = "));
+        assert!(merged.contains("export const AccountSchema = "));
+
+        let error = generate_merged_zorsh_schema(&[&game, &other], &output, &options).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "`Item` is defined differently in `{}` and `{}`",
+                game, other
+            )
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn orders_variants_by_discriminant() {
+        let output = generate(&BorshSchemaContainer::for_type::<Result<u8, String>>()).unwrap();
+        assert!(output.contains("b.enum({ Err: b.string(), Ok: b.u8() })"));
+    }
+
+    #[test]
+    fn emits_length_range_validation() {
+        let definitions = BTreeMap::from([
+            (
+                "BoundedVec<String>".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 1..=10,
+                    elements: "Option<ShortString>".to_string(),
+                },
+            ),
+            (
+                "Option<ShortString>".to_string(),
+                Definition::Enum {
+                    tag_width: 1,
+                    variants: vec![
+                        (0, "None".to_string(), "()".to_string()),
+                        (1, "Some".to_string(), "ShortString".to_string()),
+                    ],
+                },
+            ),
+            (
+                "ShortString".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=32,
+                    elements: "u8".to_string(),
+                },
+            ),
+            (
+                "Inner".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![(
+                        "tags".to_string(),
+                        "BoundedVec<String>".to_string(),
+                    )]),
+                },
+            ),
+            (
+                "Outer".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("inner".to_string(), "Inner".to_string()),
+                        ("items".to_string(), "Vec<Inner>".to_string()),
+                    ]),
+                },
+            ),
+            (
+                "Vec<Inner>".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: Definition::DEFAULT_LENGTH_RANGE,
+                    elements: "Inner".to_string(),
+                },
+            ),
+            ("()".to_string(), Definition::Primitive(0)),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Outer".to_string(), definitions);
+        let output = generate(&container).unwrap();
+
+        assert!(output.starts_with("import { b, type Schema } from \"@zorsh/zorsh\";\n"));
+        assert!(output.contains("function checkLength("));
+        assert!(output.contains(
+            "export const InnerSchema = withValidation(b.struct({\n  tags: b.vec(b.option(b.bytes())),\n}), validateInner);"
+        ));
+        assert!(output.contains(
+            "export function validateInner(value: Inner): void {\n  checkLength(value.tags.length, 1, 10, \"Inner.tags\");\n  for (const item0 of value.tags) {\n    if (item0 !== null) {\n      checkLength(item0.length, 0, 32, \"Inner.tags[]\");\n    }\n  }\n}\n"
+        ));
+        assert!(output.contains(
+            "export function validateOuter(value: Outer): void {\n  validateInner(value.inner);\n  for (const item0 of value.items) {\n    validateInner(item0);\n  }\n}\n"
+        ));
+    }
+
+    #[test]
+    fn validates_newtypes_without_changing_the_inner_schema() {
+        let definitions = BTreeMap::from([
+            (
+                "Capped".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=8,
+                    elements: "u8".to_string(),
+                },
+            ),
+            (
+                "Inner".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![("names".to_string(), "Capped".to_string())]),
+                },
+            ),
+            (
+                "Wrapper".to_string(),
+                Definition::Struct {
+                    fields: Fields::UnnamedFields(vec!["Inner".to_string()]),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Wrapper".to_string(), definitions);
+        let options = GeneratorOptions {
+            newtype_mode: NewtypeMode::Flatten,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+
+        assert!(output.contains(
+            "export const WrapperSchema = withValidation(InnerSchema, validateWrapper);\n"
+        ));
+        // Wrapping `InnerSchema` mustn't make it run `validateWrapper` too
+        assert!(output.contains("  const validated = Object.create(schema) as Schema<T>;\n"));
+        assert!(!output.contains("schema.serialize ="));
+    }
+
+    #[test]
+    fn names_tuple_struct_validation_paths_like_named_ones() {
+        let definitions = BTreeMap::from([
+            (
+                "ShortString".to_string(),
+                Definition::Sequence {
+                    length_width: 4,
+                    length_range: 0..=32,
+                    elements: "u8".to_string(),
+                },
+            ),
+            (
+                "game::Pair".to_string(),
+                Definition::Struct {
+                    fields: Fields::UnnamedFields(vec![
+                        "u8".to_string(),
+                        "ShortString".to_string(),
+                    ]),
+                },
+            ),
+            (
+                "game::Label".to_string(),
+                Definition::Struct {
+                    fields: Fields::UnnamedFields(vec!["ShortString".to_string()]),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("game::Pair".to_string(), definitions.clone());
+        let output = generate(&container).unwrap();
+        assert!(output.contains("  checkLength(value[1].length, 0, 32, \"Pair[1]\");\n"));
+        assert!(!output.contains("game::"));
+
+        let container = BorshSchemaContainer::new("game::Label".to_string(), definitions);
+        let options = GeneratorOptions {
+            newtype_mode: NewtypeMode::Flatten,
+            ..Default::default()
+        };
+        let output = generate_with(&container, &options).unwrap();
+        assert!(output.contains("  checkLength(value.length, 0, 32, \"Label[0]\");\n"));
+    }
+
+    #[test]
+    fn omits_validation_for_unbounded_schemas() {
+        let output = generate(&BorshSchemaContainer::for_type::<GameState>()).unwrap();
+        assert!(!output.contains("withValidation"));
+        assert!(!output.contains("checkLength"));
+    }
+
+    #[test]
+    fn rejects_wide_enum_tags() {
+        let definitions = BTreeMap::from([
+            (
+                "Wide".to_string(),
+                Definition::Enum {
+                    tag_width: 2,
+                    variants: vec![(0, "A".to_string(), "()".to_string())],
+                },
+            ),
+            ("()".to_string(), Definition::Primitive(0)),
+        ]);
+        let container = BorshSchemaContainer::new("Wide".to_string(), definitions);
+
+        let error = generate(&container).unwrap_err();
+        assert!(
+            matches!(error, ZorshGenError::Unsupported(_)),
+            "{:?}",
+            error
+        );
+        assert!(error
+            .to_string()
+            .contains("enum `Wide` uses a 2-byte tag, but zorsh enums are encoded with a u8 tag"));
+    }
+
+    #[test]
+    fn passes_length_prefix_widths_to_builders() {
+        let sequence = |width, elements: &str| Definition::Sequence {
+            length_width: width,
+            length_range: Definition::DEFAULT_LENGTH_RANGE,
+            elements: elements.to_string(),
+        };
+        let definitions = BTreeMap::from([
+            ("PascalString".to_string(), sequence(1, "u8")),
+            ("ShortVec<u32>".to_string(), sequence(2, "u32")),
+            ("LongVec<u32>".to_string(), sequence(8, "u32")),
+            ("Vec<u32>".to_string(), sequence(4, "u32")),
+            (
+                "Packet".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("name".to_string(), "PascalString".to_string()),
+                        ("short".to_string(), "ShortVec<u32>".to_string()),
+                        ("long".to_string(), "LongVec<u32>".to_string()),
+                        ("usual".to_string(), "Vec<u32>".to_string()),
+                    ]),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+            ("u32".to_string(), Definition::Primitive(4)),
+        ]);
+        let container = BorshSchemaContainer::new("Packet".to_string(), definitions);
+
+        let output = generate(&container).unwrap();
+        assert!(output.contains("  name: b.bytes({ lengthWidth: 1 }),\n"));
+        assert!(output.contains("  short: b.vec(b.u32(), { lengthWidth: 2 }),\n"));
+        assert!(output.contains("  long: b.vec(b.u32(), { lengthWidth: 8 }),\n"));
+        assert!(output.contains("  usual: b.vec(b.u32()),\n"));
+    }
+
+    #[test]
+    fn rejects_length_prefixes_zorsh_cannot_encode() {
+        let definitions = BTreeMap::from([
+            (
+                "Triple".to_string(),
+                Definition::Sequence {
+                    length_width: 3,
+                    length_range: 0..=255,
+                    elements: "u8".to_string(),
+                },
+            ),
+            (
+                "VarInt".to_string(),
+                Definition::Sequence {
+                    length_width: 0,
+                    length_range: 1..=5,
+                    elements: "u8".to_string(),
+                },
+            ),
+            (
+                "Packet".to_string(),
+                Definition::Struct {
+                    fields: Fields::NamedFields(vec![
+                        ("name".to_string(), "Triple".to_string()),
+                        ("size".to_string(), "VarInt".to_string()),
+                    ]),
+                },
+            ),
+            ("u8".to_string(), Definition::Primitive(1)),
+        ]);
+        let container = BorshSchemaContainer::new("Packet".to_string(), definitions);
+
+        let error = generate(&container).unwrap_err().to_string();
+        assert!(error.contains(
+            "sequence `Triple` uses a 3-byte length prefix, but zorsh length prefixes are 1, 2, 4 or 8 bytes"
+        ));
+        assert!(error.contains(
+            "sequence `VarInt` has no length prefix and a variable length, which zorsh cannot represent"
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+// src/mock.rs
+//! `mock{Type}(seed?)` functions producing random values that fit each schema.
+use super::{ir, normalize, property_key, NewtypeMode, Parser};
+
+/// Runtime helpers used by the generated mock functions: a seeded PRNG (mulberry32)
+/// and generators built on it.
+pub(super) const MOCK_HELPERS: &str = r#"function mockRandom(seed: number | (() => number) = 1): () => number {
+  if (typeof seed === "function") {
+    return seed;
+  }
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+function mockInt(random: () => number, min: number, max: number): number {
+  return min + Math.floor(random() * (max - min + 1));
+}
+
+function mockArray<T>(random: () => number, min: number, max: number, item: () => T): T[] {
+  return Array.from({ length: mockInt(random, min, max) }, item);
+}
+
+function mockString(random: () => number): string {
+  return mockArray(random, 1, 12, () => String.fromCharCode(mockInt(random, 97, 122))).join("");
+}
+
+function mockBytes(random: () => number, min: number, max: number): Uint8Array {
+  return Uint8Array.from(mockArray(random, min, max, () => mockInt(random, 0, 255)));
+}
+
+function mockPick<T>(random: () => number, choices: (() => T)[]): T {
+  return choices[mockInt(random, 0, choices.length - 1)]();
+}
+
+"#;
+
+/// The most elements a mocked collection gets beyond its minimum length.
+const MOCK_EXTRA_ELEMENTS: u64 = 3;
+
+impl Parser<'_> {
+    /// Returns the `mock{Type}` function for an exported declaration or type alias.
+    pub(super) fn mock_function(&self, declaration: &str) -> String {
+        let name = self.type_name(declaration);
+        let value = match self.options.source.aliases.get(declaration) {
+            Some(target) => self.mock_value(target),
+            None => self.mock_definition(declaration),
+        };
+        format!(
+            "export function mock{0}(seed?: number | (() => number)): {0} {{\n  const random = mockRandom(seed);\n  return {1};\n}}\n\n",
+            name,
+            value
+        )
+    }
+
+    /// Returns an expression for a random value of `declaration`, calling the mock
+    /// functions of exported types.
+    fn mock_value(&self, declaration: &str) -> String {
+        if self.mappings.contains_key(&normalize(declaration)) {
+            return format!("undefined as never /* TODO: Mock {} */", declaration);
+        }
+        let Some((declaration, _)) = self.resolve(declaration) else {
+            return format!("undefined as never /* TODO: Mock {} */", declaration);
+        };
+        if self.generated.contains(declaration) {
+            return format!("mock{}(random)", self.type_name(declaration));
+        }
+        self.mock_definition(declaration)
+    }
+
+    /// Returns an expression for a random value of a definition, even if it is exported.
+    fn mock_definition(&self, declaration: &str) -> String {
+        let Some(ty) = self.ty(declaration) else {
+            return self.mock_value(declaration);
+        };
+        let lengths = |length: &ir::Length| {
+            let min = *length.range.start();
+            (min, (*length.range.end()).min(min + MOCK_EXTRA_ELEMENTS))
+        };
+        match ty {
+            ir::Type::Primitive(size) => {
+                mock_primitive(declaration, self.primitive_ts(declaration, *size))
+            }
+            ir::Type::String(_) => "mockString(random)".to_string(),
+            ir::Type::Bytes(length) => {
+                let (min, max) = lengths(length);
+                format!("mockBytes(random, {}, {})", min, max)
+            }
+            ir::Type::Sequence { element, length } => {
+                let (min, max) = lengths(length);
+                format!(
+                    "mockArray(random, {}, {}, () => {})",
+                    min,
+                    max,
+                    self.mock_value(element)
+                )
+            }
+            ir::Type::Map { key, value, length } => {
+                let (min, max) = lengths(length);
+                format!(
+                    "new Map(mockArray(random, {}, {}, () => [{}, {}] as const))",
+                    min,
+                    max,
+                    self.mock_value(key),
+                    self.mock_value(value)
+                )
+            }
+            ir::Type::Set { element, length } => {
+                let (min, max) = lengths(length);
+                format!(
+                    "new Set(mockArray(random, {}, {}, () => {}))",
+                    min,
+                    max,
+                    self.mock_value(element)
+                )
+            }
+            ir::Type::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| self.mock_value(e)).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            ir::Type::Option(value) => {
+                format!("random() < 0.5 ? null : {}", self.mock_option_value(value))
+            }
+            ir::Type::Enum { variants, .. } => {
+                if variants.is_empty() {
+                    return "undefined as never".to_string();
+                }
+                let choices: Vec<String> = variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "() => ({{ {}: {} }})",
+                            property_key(&variant.name),
+                            self.mock_variant(&variant.payload)
+                        )
+                    })
+                    .collect();
+                format!("mockPick(random, [{}])", choices.join(", "))
+            }
+            ir::Type::Struct(fields) => match fields {
+                ir::Fields::Named(fields) => self.mock_object(fields),
+                ir::Fields::Unnamed(elements)
+                    if elements.len() == 1 && self.options.newtype_mode != NewtypeMode::Tuple =>
+                {
+                    let inner = self.mock_value(&elements[0]);
+                    match self.options.newtype_mode {
+                        NewtypeMode::Branded => {
+                            format!("({}) as {}", inner, self.type_name(declaration))
+                        }
+                        _ => inner,
+                    }
+                }
+                ir::Fields::Unnamed(elements) => self.mock_tuple(elements),
+                ir::Fields::Empty => "{}".to_string(),
+            },
+        }
+    }
+
+    /// Returns a random `Option` payload, matching `parse_option_value`.
+    fn mock_option_value(&self, declaration: &str) -> String {
+        if let Some(ir::Type::Option(value)) = self.ty(declaration) {
+            return format!(
+                "mockPick(random, [() => ({{ None: {{}} }}), () => ({{ Some: {} }})])",
+                self.mock_option_value(value)
+            );
+        }
+        self.mock_value(declaration)
+    }
+
+    /// Returns a random enum variant payload, matching `parse_variant`.
+    fn mock_variant(&self, declaration: &str) -> String {
+        let declaration = self.resolve(declaration).map_or(declaration, |(d, _)| d);
+        if !self.variant_structs.contains(declaration) {
+            return self.mock_value(declaration);
+        }
+        if let Some(shared) = self.hoisted.get(declaration) {
+            if self.generated.contains(shared) {
+                return format!("mock{}(random)", self.type_name(shared));
+            }
+        }
+        match self.ty(declaration) {
+            Some(ir::Type::Struct(ir::Fields::Unnamed(elements))) if elements.len() == 1 => {
+                self.mock_value(&elements[0])
+            }
+            Some(ir::Type::Struct(ir::Fields::Named(fields))) => self.mock_object(fields),
+            Some(ir::Type::Struct(ir::Fields::Unnamed(elements))) => self.mock_tuple(elements),
+            _ => "{}".to_string(),
+        }
+    }
+
+    fn mock_object(&self, fields: &[ir::Field]) -> String {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                let name = self.field_name(&field.name);
+                format!("{}: {}", property_key(&name), self.mock_value(&field.ty))
+            })
+            .collect();
+        format!("{{ {} }}", fields.join(", "))
+    }
+
+    fn mock_tuple(&self, elements: &[String]) -> String {
+        let elements: Vec<String> = elements.iter().map(|e| self.mock_value(e)).collect();
+        format!("[{}]", elements.join(", "))
+    }
+}
+
+/// Returns a random value of a primitive, within the range of its Rust type.
+fn mock_primitive(declaration: &str, ty: &str) -> String {
+    let range = match declaration {
+        "u8" => Some((0, u8::MAX as i64)),
+        "u16" => Some((0, u16::MAX as i64)),
+        "u32" => Some((0, u32::MAX as i64)),
+        "i8" => Some((i8::MIN as i64, i8::MAX as i64)),
+        "i16" => Some((i16::MIN as i64, i16::MAX as i64)),
+        "i32" => Some((i32::MIN as i64, i32::MAX as i64)),
+        _ => None,
+    };
+    if let Some((min, max)) = range {
+        return format!("mockInt(random, {}, {})", min, max);
+    }
+    match (declaration, ty) {
+        // Rounded to what an `f32` can hold, so values survive encoding
+        ("f32", _) => "Math.fround(random() * 100)".to_string(),
+        ("f64", _) => "random() * 100".to_string(),
+        (_, "boolean") => "random() < 0.5".to_string(),
+        (_, "bigint") => "BigInt(mockInt(random, 0, 1000000))".to_string(),
+        (_, "number") => "mockInt(random, 0, 255)".to_string(),
+        (_, "string") => "String(mockInt(random, 0, 1000000))".to_string(),
+        _ => "{}".to_string(),
+    }
+}
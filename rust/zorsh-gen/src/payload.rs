@@ -0,0 +1,627 @@
+// src/payload.rs
+//! Decodes borsh payloads by a `BorshSchemaContainer` at runtime, keeping the bytes each
+//! value was read from, for looking into serialized data without the Rust types.
+use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+use std::fmt;
+use std::ops::Range;
+
+/// How deeply values may nest, so that types referring to themselves through zero-sized
+/// values can't recurse forever.
+const MAX_DEPTH: usize = 256;
+
+/// A decoded value of a declaration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub declaration: String,
+    /// The bytes the value was read from.
+    pub span: Range<usize>,
+    /// The length prefix or enum tag at the start of `span`, if it has one.
+    pub header: Option<Range<usize>>,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    Unsigned(u128),
+    Signed(i128),
+    Float(f64),
+    String(String),
+    /// Bytes of a `Vec<u8>` or `[u8; N]`.
+    Bytes(Vec<u8>),
+    /// Elements of a vector, array or set.
+    Sequence(Vec<Node>),
+    /// Entries of a map, each a `(key, value)` tuple.
+    Map(Vec<Node>),
+    Tuple(Vec<Node>),
+    /// Fields of a struct; those of tuple structs are named by their index.
+    Struct(Vec<(String, Node)>),
+    Enum {
+        discriminant: i64,
+        variant: String,
+        payload: Box<Node>,
+    },
+}
+
+/// A run of bytes and what they encode: a length prefix, an enum tag, or a primitive,
+/// string or byte sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub span: Range<usize>,
+    /// Where in the value the bytes are, e.g. `players[1].inventory`.
+    pub path: String,
+    /// What the bytes are, e.g. `length prefix: 3` or `u32 = 100`.
+    pub label: String,
+}
+
+/// How many characters of a value a segment label shows.
+const LABEL_WIDTH: usize = 40;
+
+/// Why a payload doesn't decode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The offset of the first byte that couldn't be read.
+    pub offset: usize,
+    /// Where in the value decoding stopped, e.g. `players[1].inventory`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "at byte {}: {}", self.offset, self.message),
+            false => write!(
+                f,
+                "at byte {}, in `{}`: {}",
+                self.offset, self.path, self.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes `bytes` as one value of `declaration`, which must take up all of them.
+pub fn decode(
+    container: &BorshSchemaContainer,
+    declaration: &str,
+    bytes: &[u8],
+) -> Result<Node, DecodeError> {
+    let mut decoder = Decoder {
+        container,
+        bytes,
+        offset: 0,
+        path: Vec::new(),
+    };
+    let node = decoder.node(declaration)?;
+    if decoder.offset < bytes.len() {
+        return Err(decoder.error(format!(
+            "{} bytes are left over after the `{}`",
+            bytes.len() - decoder.offset,
+            declaration
+        )));
+    }
+    Ok(node)
+}
+
+struct Decoder<'a> {
+    container: &'a BorshSchemaContainer,
+    bytes: &'a [u8],
+    offset: usize,
+    /// The fields, indices and variants leading to the value being read.
+    path: Vec<String>,
+}
+
+/// Joins the fields, indices and variants leading to a value into e.g. `players[1].name`.
+fn join(path: &[String]) -> String {
+    let mut joined = String::new();
+    for segment in path {
+        if !joined.is_empty() && !segment.starts_with('[') {
+            joined.push('.');
+        }
+        joined.push_str(segment);
+    }
+    joined
+}
+
+impl Decoder<'_> {
+    fn error(&self, message: String) -> DecodeError {
+        DecodeError {
+            offset: self.offset,
+            path: join(&self.path),
+            message,
+        }
+    }
+
+    fn read(&mut self, count: usize, what: &str) -> Result<&[u8], DecodeError> {
+        let Some(bytes) = self.bytes.get(self.offset..self.offset + count) else {
+            return Err(self.error(format!(
+                "{} takes {} bytes, but only {} are left",
+                what,
+                count,
+                self.bytes.len() - self.offset
+            )));
+        };
+        self.offset += count;
+        Ok(bytes)
+    }
+
+    /// Reads a little-endian unsigned integer of up to 16 bytes.
+    fn unsigned(&mut self, width: usize, what: &str) -> Result<u128, DecodeError> {
+        let bytes = self.read(width, what)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | u128::from(byte)))
+    }
+
+    fn nested<T>(
+        &mut self,
+        segment: String,
+        read: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        self.path.push(segment);
+        let value = read(self)?;
+        self.path.pop();
+        Ok(value)
+    }
+
+    fn node(&mut self, declaration: &str) -> Result<Node, DecodeError> {
+        if self.path.len() > MAX_DEPTH {
+            return Err(self.error(format!("values nest more than {} deep", MAX_DEPTH)));
+        }
+        let Some(definition) = self.container.get_definition(declaration) else {
+            return Err(self.error(format!(
+                "`{}` isn't defined in the schema container",
+                declaration
+            )));
+        };
+        let start = self.offset;
+        let mut header = None;
+        let value = match definition {
+            Definition::Primitive(size) => self.primitive(declaration, usize::from(*size))?,
+            Definition::Sequence {
+                length_width,
+                length_range,
+                elements,
+            } => {
+                let length = match *length_width {
+                    Definition::ARRAY_LENGTH_WIDTH => *length_range.start(),
+                    width => {
+                        let length = self.unsigned(usize::from(width), "length prefix")?;
+                        header = Some(start..self.offset);
+                        let length = u64::try_from(length).unwrap_or(u64::MAX);
+                        if !length_range.contains(&length) {
+                            self.offset = start;
+                            return Err(self.error(format!(
+                                "a length of {} is outside the {}..={} `{}` allows",
+                                length,
+                                length_range.start(),
+                                length_range.end(),
+                                declaration
+                            )));
+                        }
+                        length
+                    }
+                };
+                self.sequence(declaration, elements, length)?
+            }
+            Definition::Tuple { elements } => {
+                let mut nodes = Vec::new();
+                for (index, element) in elements.iter().enumerate() {
+                    nodes.push(self.nested(index.to_string(), |d| d.node(element))?);
+                }
+                Value::Tuple(nodes)
+            }
+            Definition::Enum {
+                tag_width,
+                variants,
+            } => {
+                let tag = self.unsigned(usize::from(*tag_width), "enum tag")?;
+                header = Some(start..self.offset);
+                let Some((discriminant, variant, payload)) = variants
+                    .iter()
+                    .find(|(discriminant, _, _)| *discriminant as u128 == tag)
+                else {
+                    self.offset = start;
+                    return Err(self.error(format!(
+                        "{} isn't the discriminant of any `{}` variant",
+                        tag, declaration
+                    )));
+                };
+                let payload = self.nested(format!("<{}>", variant), |d| d.node(payload))?;
+                Value::Enum {
+                    discriminant: *discriminant,
+                    variant: variant.clone(),
+                    payload: Box::new(payload),
+                }
+            }
+            Definition::Struct { fields } => match fields {
+                Fields::NamedFields(fields) => {
+                    let mut values = Vec::new();
+                    for (name, field) in fields {
+                        let node = self.nested(name.clone(), |d| d.node(field))?;
+                        values.push((name.clone(), node));
+                    }
+                    Value::Struct(values)
+                }
+                Fields::UnnamedFields(fields) => {
+                    let mut values = Vec::new();
+                    for (index, field) in fields.iter().enumerate() {
+                        let node = self.nested(index.to_string(), |d| d.node(field))?;
+                        values.push((index.to_string(), node));
+                    }
+                    Value::Struct(values)
+                }
+                Fields::Empty => Value::Struct(Vec::new()),
+            },
+        };
+        Ok(Node {
+            declaration: declaration.to_string(),
+            span: start..self.offset,
+            header,
+            value,
+        })
+    }
+
+    fn primitive(&mut self, declaration: &str, size: usize) -> Result<Value, DecodeError> {
+        let what = format!("`{}`", declaration);
+        Ok(match declaration {
+            "()" => Value::Unit,
+            "bool" => match self.unsigned(1, &what)? {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                byte => {
+                    self.offset -= 1;
+                    return Err(self.error(format!("{} isn't a `bool`, which is 0 or 1", byte)));
+                }
+            },
+            "f32" => Value::Float(f64::from(f32::from_bits(self.unsigned(4, &what)? as u32))),
+            "f64" => Value::Float(f64::from_bits(self.unsigned(8, &what)? as u64)),
+            "i8" | "i16" | "i32" | "i64" | "i128" => {
+                let value = self.unsigned(size, &what)?;
+                // Sign-extend from the width read
+                let shift = 128 - 8 * size as u32;
+                Value::Signed(((value << shift) as i128) >> shift)
+            }
+            _ if size <= 16 => Value::Unsigned(self.unsigned(size, &what)?),
+            _ => {
+                return Err(self.error(format!(
+                    "`{}` is {} bytes wide, more than any integer decoded",
+                    declaration, size
+                )))
+            }
+        })
+    }
+
+    fn sequence(
+        &mut self,
+        declaration: &str,
+        elements: &str,
+        length: u64,
+    ) -> Result<Value, DecodeError> {
+        if elements == "u8" && self.container.get_definition("u8").is_some() {
+            let length = usize::try_from(length).unwrap_or(usize::MAX);
+            let bytes = self.read(length, &format!("`{}`", declaration))?.to_vec();
+            if collection_name(declaration) != "String" {
+                return Ok(Value::Bytes(bytes));
+            }
+            return match String::from_utf8(bytes) {
+                Ok(string) => Ok(Value::String(string)),
+                Err(error) => {
+                    self.offset -= length - error.utf8_error().valid_up_to();
+                    Err(self.error("the string isn't valid UTF-8".to_string()))
+                }
+            };
+        }
+        let mut nodes = Vec::new();
+        for index in 0..length {
+            nodes.push(self.nested(format!("[{}]", index), |d| d.node(elements))?);
+        }
+        match collection_name(declaration) {
+            "HashMap" | "BTreeMap" => Ok(Value::Map(nodes)),
+            _ => Ok(Value::Sequence(nodes)),
+        }
+    }
+}
+
+/// Returns the name of a generic declaration without its parameters and path, e.g.
+/// `HashMap` for `std::collections::HashMap<String, u32>`.
+fn collection_name(declaration: &str) -> &str {
+    let name = declaration.split('<').next().unwrap_or(declaration);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+impl fmt::Display for Node {
+    /// Writes the value in Rust syntax, one field or element per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl Node {
+    /// Returns the runs of bytes the value was read from, in order, each labelled with
+    /// where in the value it is.
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        self.collect_segments(&mut Vec::new(), &mut segments);
+        segments
+    }
+
+    fn collect_segments(&self, path: &mut Vec<String>, segments: &mut Vec<Segment>) {
+        let mut push = |span: Range<usize>, label: String| {
+            if !span.is_empty() {
+                segments.push(Segment {
+                    span,
+                    path: join(path),
+                    label,
+                });
+            }
+        };
+        if let Some(header) = &self.header {
+            let label = match &self.value {
+                Value::Enum { variant, .. } => format!("`{}` tag", variant),
+                Value::Sequence(nodes) | Value::Map(nodes) => {
+                    format!("length prefix: {}", nodes.len())
+                }
+                Value::String(string) => format!("length prefix: {}", string.len()),
+                Value::Bytes(bytes) => format!("length prefix: {}", bytes.len()),
+                _ => "header".to_string(),
+            };
+            push(header.clone(), label);
+        }
+        let children: Vec<(String, &Node)> = match &self.value {
+            Value::Sequence(nodes) | Value::Map(nodes) => nodes
+                .iter()
+                .enumerate()
+                .map(|(index, node)| (format!("[{}]", index), node))
+                .collect(),
+            Value::Tuple(nodes) => nodes
+                .iter()
+                .enumerate()
+                .map(|(index, node)| (index.to_string(), node))
+                .collect(),
+            Value::Struct(fields) => fields
+                .iter()
+                .map(|(name, node)| (name.clone(), node))
+                .collect(),
+            Value::Enum {
+                variant, payload, ..
+            } => vec![(format!("<{}>", variant), &**payload)],
+            _ => {
+                let mut value = self.to_string();
+                if value.chars().count() > LABEL_WIDTH {
+                    value = value.chars().take(LABEL_WIDTH - 3).collect::<String>() + "...";
+                }
+                let start = self
+                    .header
+                    .as_ref()
+                    .map_or(self.span.start, |header| header.end);
+                push(
+                    start..self.span.end,
+                    format!("{} = {}", self.declaration, value),
+                );
+                return;
+            }
+        };
+        for (segment, node) in children {
+            path.push(segment);
+            node.collect_segments(path, segments);
+            path.pop();
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "    ".repeat(depth + 1);
+        let close = "    ".repeat(depth);
+        let list = |f: &mut fmt::Formatter<'_>, open: &str, nodes: &[&Node], end: &str| {
+            if nodes.is_empty() {
+                return write!(f, "{}{}", open, end);
+            }
+            writeln!(f, "{}", open)?;
+            for node in nodes {
+                write!(f, "{}", indent)?;
+                node.write(f, depth + 1)?;
+                writeln!(f, ",")?;
+            }
+            write!(f, "{}{}", close, end)
+        };
+        match &self.value {
+            Value::Unit => write!(f, "()"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Unsigned(value) => write!(f, "{}", value),
+            Value::Signed(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::String(value) => write!(f, "{:?}", value),
+            Value::Bytes(bytes) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            Value::Sequence(nodes) => list(f, "[", &nodes.iter().collect::<Vec<_>>(), "]"),
+            Value::Tuple(nodes) => list(f, "(", &nodes.iter().collect::<Vec<_>>(), ")"),
+            Value::Map(entries) => {
+                if entries.is_empty() {
+                    return write!(f, "{{}}");
+                }
+                writeln!(f, "{{")?;
+                for entry in entries {
+                    match &entry.value {
+                        Value::Tuple(pair) if pair.len() == 2 => {
+                            write!(f, "{}", indent)?;
+                            pair[0].write(f, depth + 1)?;
+                            write!(f, ": ")?;
+                            pair[1].write(f, depth + 1)?;
+                        }
+                        _ => {
+                            write!(f, "{}", indent)?;
+                            entry.write(f, depth + 1)?;
+                        }
+                    }
+                    writeln!(f, ",")?;
+                }
+                write!(f, "{}}}", close)
+            }
+            Value::Struct(fields) => {
+                let name = self.declaration.as_str();
+                self.write_fields(f, name, fields, depth)
+            }
+            Value::Enum {
+                variant, payload, ..
+            } => {
+                let name = format!("{}::{}", self.declaration, variant);
+                let option = self.declaration.starts_with("Option<");
+                match &payload.value {
+                    // Options read best as they're written
+                    _ if option && variant == "None" => write!(f, "None"),
+                    _ if option => {
+                        write!(f, "Some(")?;
+                        payload.write(f, depth)?;
+                        write!(f, ")")
+                    }
+                    Value::Struct(fields) => payload.write_fields(f, &name, fields, depth),
+                    _ => {
+                        write!(f, "{}(", name)?;
+                        payload.write(f, depth)?;
+                        write!(f, ")")
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes a struct, or an enum variant carrying fields.
+    fn write_fields(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: &str,
+        fields: &[(String, Node)],
+        depth: usize,
+    ) -> fmt::Result {
+        let indent = "    ".repeat(depth + 1);
+        let close = "    ".repeat(depth);
+        if fields.is_empty() {
+            return write!(f, "{}", name);
+        }
+        let unnamed = fields
+            .iter()
+            .all(|(field, _)| field.parse::<usize>().is_ok());
+        if unnamed {
+            write!(f, "{}(", name)?;
+            for (index, (_, node)) in fields.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                node.write(f, depth)?;
+            }
+            return write!(f, ")");
+        }
+        writeln!(f, "{} {{", name)?;
+        for (field, node) in fields {
+            write!(f, "{}{}: ", indent, field)?;
+            node.write(f, depth + 1)?;
+            writeln!(f, ",")?;
+        }
+        write!(f, "{}}}", close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh_test::Stats;
+
+    fn game_state() -> (BorshSchemaContainer, Vec<u8>) {
+        let schema = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../borsh_test/test_data/complex_schema.bin"
+        ))
+        .unwrap();
+        let payload = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../borsh_test/test_data/complex_game_state.bin"
+        ))
+        .unwrap();
+        (borsh::from_slice(&schema).unwrap(), payload)
+    }
+
+    #[test]
+    fn decodes_payloads() {
+        let (container, payload) = game_state();
+        let node = decode(&container, "GameState", &payload).unwrap();
+        assert_eq!(node.span, 0..payload.len());
+        let text = node.to_string();
+        assert!(text.starts_with("GameState {\n    players: {\n        \"alice_id\": Player {\n"));
+        assert!(text.contains("            last_login: Some(1678886400),\n"));
+        assert!(text.contains("            last_login: None,\n"));
+        assert!(text.contains("effects: [\n                        Effect::Damage(15),\n"));
+        assert!(text
+            .contains("        GameEvent::PlayerJoined {\n            player_id: \"alice_id\",\n"));
+        assert!(text.ends_with("    game_version: \"1.2.3\",\n}"));
+
+        let stats = Stats {
+            health: 1,
+            mana: 2,
+            attack: 3,
+            defense: 4,
+            magic_attack: 5,
+            magic_defense: 6,
+        };
+        let container = BorshSchemaContainer::for_type::<Stats>();
+        let node = decode(&container, "Stats", &borsh::to_vec(&stats).unwrap()).unwrap();
+        let Value::Struct(fields) = &node.value else {
+            panic!("{:?}", node.value);
+        };
+        assert_eq!(fields[5].0, "magic_defense");
+        assert_eq!(fields[5].1.span, 20..24);
+        assert_eq!(fields[5].1.value, Value::Unsigned(6));
+    }
+
+    #[test]
+    fn reports_where_decoding_stops() {
+        let (container, payload) = game_state();
+        let error = decode(&container, "GameState", &payload[..40]).unwrap_err();
+        assert_eq!(error.offset, 38);
+        assert_eq!(error.path, "players[0].1.stats.defense");
+        assert_eq!(
+            error.to_string(),
+            "at byte 38, in `players[0].1.stats.defense`: `u32` takes 4 bytes, but only 2 are left"
+        );
+
+        let mut trailing = payload.clone();
+        trailing.push(0);
+        let error = decode(&container, "GameState", &trailing).unwrap_err();
+        assert_eq!(error.offset, payload.len());
+        assert_eq!(error.message, "1 bytes are left over after the `GameState`");
+
+        let error = decode(&container, "Missing", &payload).unwrap_err();
+        assert_eq!(
+            error.message,
+            "`Missing` isn't defined in the schema container"
+        );
+    }
+
+    #[test]
+    fn labels_segments() {
+        let (container, payload) = game_state();
+        let segments = decode(&container, "GameState", &payload)
+            .unwrap()
+            .segments();
+        let covered: usize = segments.iter().map(|segment| segment.span.len()).sum();
+        assert_eq!(covered, payload.len());
+        assert!(segments
+            .windows(2)
+            .all(|pair| pair[0].span.end == pair[1].span.start));
+
+        let labels: Vec<(&str, &str)> = segments
+            .iter()
+            .take(3)
+            .map(|segment| (segment.path.as_str(), segment.label.as_str()))
+            .collect();
+        assert_eq!(labels[0], ("players", "length prefix: 2"));
+        assert_eq!(labels[1].0, "players[0].0");
+        assert!(labels[1].1.starts_with("length prefix: "));
+        assert!(labels[2].1.starts_with("String = \""));
+    }
+}
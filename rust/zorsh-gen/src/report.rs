@@ -0,0 +1,95 @@
+// src/report.rs
+//! [`GenerationReport`], what a run of the generator did, for summaries and regression
+//! checks in build pipelines.
+use super::{normalize, Parser};
+use borsh::schema::Declaration;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// What generating schemas did, returned by the functions that write them:
+///
+/// ```no_run
+/// # #[cfg(feature = "fs")]
+/// # {
+/// let report = zorsh_gen::generate_zorsh_schema("schema.bin", "src/schemas.ts")?;
+/// println!("{}", report);
+/// assert!(report.exports.len() >= 12, "types are no longer exported");
+/// # }
+/// # Ok::<(), zorsh_gen::ZorshGenError>(())
+/// ```
+///
+/// A custom backend is only reported as far as the generator can tell without it: the
+/// declarations and files, but not what the backend exported or left unresolved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GenerationReport {
+    /// How many declarations the schema containers define, once merged.
+    pub declarations: usize,
+    /// The types exported as their own schema, in output order.
+    pub exports: Vec<Declaration>,
+    /// Types that would have been exported, but that `include` and `exclude` left out and
+    /// nothing exported refers to, sorted.
+    pub skipped: Vec<Declaration>,
+    /// Declarations referred to but not defined, mapped or resolved, which the output
+    /// holds a placeholder for, sorted.
+    pub unresolved: Vec<Declaration>,
+    /// The size in bytes of each file, including those that already held their contents
+    /// and weren't rewritten. Standard output is `-`.
+    pub files: BTreeMap<PathBuf, usize>,
+}
+
+impl GenerationReport {
+    /// Returns `files` with the report, recording their sizes.
+    pub(super) fn with_files(
+        mut self,
+        files: Vec<(PathBuf, String)>,
+    ) -> (Vec<(PathBuf, String)>, Self) {
+        self.files = files
+            .iter()
+            .map(|(path, contents)| (path.clone(), contents.len()))
+            .collect();
+        (files, self)
+    }
+}
+
+impl fmt::Display for GenerationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "declarations: {}, exported: {}, skipped by filters: {}, unresolved: {}",
+            self.declarations,
+            self.exports.len(),
+            self.skipped.len(),
+            self.unresolved.len()
+        )?;
+        if !self.unresolved.is_empty() {
+            write!(f, " ({})", self.unresolved.join(", "))?;
+        }
+        for (path, bytes) in &self.files {
+            write!(f, "\n{}: {} bytes", path.display(), bytes)?;
+        }
+        Ok(())
+    }
+}
+
+impl Parser<'_> {
+    /// Returns what has been generated so far, without any files.
+    pub(super) fn report(&self) -> GenerationReport {
+        let skipped = self.ir.types.iter().filter(|(declaration, ty)| {
+            self.is_exported(declaration, ty)
+                && !self.mappings.contains_key(&normalize(declaration))
+                && !self.options.filter.matches(declaration)
+                && !self.exported.contains(declaration)
+        });
+        GenerationReport {
+            declarations: self.ir.types.len(),
+            exports: self.exported.clone(),
+            skipped: skipped
+                .map(|(declaration, _)| declaration.clone())
+                .collect(),
+            unresolved: self.unresolved.iter().cloned().collect(),
+            files: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,430 @@
+// src/settings.rs
+//! [`Settings`], the generator options as plain values named after the `zorsh-gen` flags.
+//! The flags, `zorsh.toml` and the serialized form of [`GeneratorOptions`] are all this
+//! one struct, so an option is added in one place and spelled the same everywhere:
+//!
+//! ```
+//! use zorsh_gen::{FieldCase, GeneratorOptions};
+//!
+//! let options: GeneratorOptions = toml::from_str(
+//!     r#"
+//!     field-case = "camel"
+//!     codec-functions = true
+//!
+//!     [rename]
+//!     State = "ContractState"
+//!     "#,
+//! )?;
+//! assert_eq!(options.field_case, FieldCase::Camel);
+//! assert_eq!(options.renames["State"], "ContractState");
+//! # Ok::<(), toml::de::Error>(())
+//! ```
+use super::{
+    Banner, ExportStyle, FieldCase, FormatOptions, GeneratorOptions, Indent, Inlining,
+    LargeIntegers, ModuleFormat, NewtypeMode, OutputMode, Quotes, RuntimeImport, SchemaNaming,
+    Target, TypeFilter, TypeMapping, UnionStyle, ZorshGenError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Every option of [`GeneratorOptions`] that can be written down: all but the source
+/// info, which is read from a file, custom schema naming, and the hooks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Settings {
+    /// What to convert the schema container to: zorsh TypeScript schemas, Zod schemas
+    /// validating their values, or a JSON Schema of the JSON form of their values,
+    /// written to `.schema.json` by default.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = Target::Zorsh))]
+    pub backend: Target,
+    /// What the output contains.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = OutputMode::Schemas))]
+    pub mode: OutputMode,
+    /// The module syntax of imports and exports.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = ModuleFormat::Esm))]
+    pub module_format: ModuleFormat,
+    /// How single-field tuple structs are emitted.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = NewtypeMode::Tuple))]
+    pub newtypes: NewtypeMode,
+    /// How struct field names are spelled.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = FieldCase::Keep))]
+    pub field_case: FieldCase,
+    /// How 64- and 128-bit integers appear in TypeScript.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = LargeIntegers::BigInt))]
+    pub large_integers: LargeIntegers,
+    /// Which types get their own schema, and which are written out where used.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = Inlining::Auto))]
+    pub inlining: Inlining,
+    /// Export one constant per schema, or a single `schemas` object.
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value_t = ExportStyle::Separate))]
+    pub export_style: ExportStyle,
+    /// Write enum types out as unions.
+    #[cfg_attr(feature = "cli", arg(long, value_enum))]
+    pub union_types: Option<UnionTypes>,
+    /// The field holding the variant name of tagged unions.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "kind"))]
+    pub tag_field: String,
+
+    /// Put before each type name to name its schema.
+    #[cfg_attr(feature = "cli", arg(long, default_value = ""))]
+    pub schema_prefix: String,
+    /// Put after each type name to name its schema.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "Schema"))]
+    pub schema_suffix: String,
+    /// The module to import the zorsh runtime from.
+    #[cfg_attr(feature = "cli", arg(long, default_value = super::RUNTIME_PACKAGE))]
+    pub runtime_module: String,
+    /// The binding of the runtime module holding the builders.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "b"))]
+    pub runtime_binding: String,
+    /// Prepended to every exported type name.
+    #[cfg_attr(feature = "cli", arg(long, default_value = ""))]
+    pub prefix: String,
+    /// Wrap every export in a namespace.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub namespace: Option<String>,
+    /// Export a declaration under another name, e.g. `State=ContractState`.
+    #[cfg_attr(feature = "cli", arg(long, value_name = "DECL=NAME", value_parser = pair))]
+    #[serde(with = "pairs")]
+    pub rename: Vec<(String, String)>,
+    /// Use a zorsh expression for a declaration, e.g. `AccountId=b.string()`.
+    #[cfg_attr(feature = "cli", arg(long, value_name = "DECL=EXPR", value_parser = pair))]
+    #[serde(with = "pairs")]
+    pub map: Vec<(String, String)>,
+    /// Use a schema imported from a module for a declaration, e.g.
+    /// `U128=U128Schema@near-zorsh`.
+    #[cfg_attr(feature = "cli", arg(long, value_name = "DECL=NAME@MODULE", value_parser = pair))]
+    #[serde(with = "pairs")]
+    pub map_import: Vec<(String, String)>,
+    /// Export only types matching this glob, and the types they refer to.
+    #[cfg_attr(feature = "cli", arg(long, value_name = "GLOB"))]
+    pub include: Vec<String>,
+    /// Don't export types matching this glob unless another type refers to them.
+    #[cfg_attr(feature = "cli", arg(long, value_name = "GLOB"))]
+    pub exclude: Vec<String>,
+    /// With `--split`, write one file per Rust module instead, as given by `--source`.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub module_files: bool,
+
+    /// Fail instead of writing `TODO` placeholders for declarations that can't be resolved
+    /// or represented.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub strict: bool,
+    /// Export identical enum variant payloads once, as a shared schema.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub hoist_duplicates: bool,
+    /// Write exported types with readonly properties and collections.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub readonly: bool,
+    /// Annotate schemas and fields with their Rust declarations.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub rust_type_docs: bool,
+    /// Emit `serialize{Type}` and `deserialize{Type}` functions.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub codec_functions: bool,
+    /// Emit an `is{Variant}` type guard per enum variant.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub type_guards: bool,
+    /// Emit a `{Enum}Tag` object mapping variant names to discriminants.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub tag_constants: bool,
+    /// Emit a `{Enum}` object with a constructor per variant.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub variant_constructors: bool,
+    /// Emit a seeded `mock{Type}` function per type.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub mocks: bool,
+    /// Also write a vitest spec round-tripping every type.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub roundtrip_spec: bool,
+    /// Emit the fixed or minimum encoded size of each type.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub byte_sizes: bool,
+    /// Emit the byte offset and size of each field of fixed-size structs.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub field_offsets: bool,
+    /// Emit `schemaRegistry`, mapping Rust declarations to schemas.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub schema_registry: bool,
+    /// Emit `SCHEMA_HASH`, the SHA-256 of the schema container.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub hash_constant: bool,
+    /// Also write a JSON map from Rust declarations to generated lines.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub source_map: bool,
+
+    /// Start the output with a comment naming the generator and the input.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub banner: bool,
+    /// Include the time of generation in the banner.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub timestamp: bool,
+    /// Spaces per indent level, or `tab`.
+    #[cfg_attr(feature = "cli", arg(long, default_value = "2"))]
+    pub indent: Indent,
+    /// Quote strings with `'`.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub single_quotes: bool,
+    /// Leave out semicolons.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub no_semicolons: bool,
+    /// Leave out trailing commas.
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub no_trailing_commas: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        GeneratorOptions::default().into()
+    }
+}
+
+/// How enums are typed, for `union-types`: a [`UnionStyle`], with the field of tagged
+/// unions given by `tag-field`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum UnionTypes {
+    Keyed,
+    Tagged,
+}
+
+/// Parses a `KEY=VALUE` argument.
+#[cfg(feature = "cli")]
+fn pair(argument: &str) -> Result<(String, String), String> {
+    match argument.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected `KEY=VALUE`, got `{}`", argument)),
+    }
+}
+
+/// `KEY=VALUE` pairs as a table of keys, e.g. `[rename]` in `zorsh.toml`.
+mod pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(
+        pairs: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let table: BTreeMap<&str, &str> = pairs
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        table.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        let table = BTreeMap::<String, String>::deserialize(deserializer)?;
+        Ok(table.into_iter().collect())
+    }
+}
+
+impl TryFrom<Settings> for GeneratorOptions {
+    type Error = ZorshGenError;
+
+    /// Fails for `map-import` values other than `NAME@MODULE`.
+    fn try_from(settings: Settings) -> Result<Self, Self::Error> {
+        let mut type_mappings = std::collections::BTreeMap::new();
+        for (declaration, expression) in settings.map {
+            type_mappings.insert(declaration, TypeMapping::Expression(expression));
+        }
+        for (declaration, import) in settings.map_import {
+            let Some((name, module)) = import.split_once('@') else {
+                return Err(ZorshGenError::options(format!(
+                    "expected `NAME@MODULE` to import `{}` from, got `{}`",
+                    declaration, import
+                )));
+            };
+            let mapping = TypeMapping::Import {
+                module: module.to_string(),
+                name: name.to_string(),
+            };
+            type_mappings.insert(declaration, mapping);
+        }
+
+        Ok(GeneratorOptions {
+            target: settings.backend,
+            output_mode: settings.mode,
+            module_format: settings.module_format,
+            newtype_mode: settings.newtypes,
+            field_case: settings.field_case,
+            large_integers: settings.large_integers,
+            inlining: settings.inlining,
+            export_style: settings.export_style,
+            union_types: settings.union_types.map(|style| match style {
+                UnionTypes::Keyed => UnionStyle::Keyed,
+                UnionTypes::Tagged => UnionStyle::Tagged {
+                    field: settings.tag_field,
+                },
+            }),
+            schema_naming: SchemaNaming::Affix {
+                prefix: settings.schema_prefix,
+                suffix: settings.schema_suffix,
+            },
+            runtime: RuntimeImport {
+                module: settings.runtime_module,
+                binding: settings.runtime_binding,
+            },
+            prefix: settings.prefix,
+            namespace: settings.namespace,
+            renames: settings.rename.into_iter().collect(),
+            type_mappings,
+            filter: TypeFilter {
+                include: settings.include,
+                exclude: settings.exclude,
+            },
+            module_files: settings.module_files,
+            strict: settings.strict,
+            hoist_duplicates: settings.hoist_duplicates,
+            readonly_types: settings.readonly,
+            rust_type_docs: settings.rust_type_docs,
+            codec_functions: settings.codec_functions,
+            type_guards: settings.type_guards,
+            tag_constants: settings.tag_constants,
+            variant_constructors: settings.variant_constructors,
+            mocks: settings.mocks,
+            roundtrip_spec: settings.roundtrip_spec,
+            byte_sizes: settings.byte_sizes,
+            field_offsets: settings.field_offsets,
+            schema_registry: settings.schema_registry,
+            hash_constant: settings.hash_constant,
+            source_map: settings.source_map,
+            banner: settings.banner.then_some(Banner {
+                source: None,
+                timestamp: settings.timestamp,
+            }),
+            format: FormatOptions {
+                indent: settings.indent,
+                quotes: match settings.single_quotes {
+                    true => Quotes::Single,
+                    false => Quotes::Double,
+                },
+                semicolons: !settings.no_semicolons,
+                trailing_commas: !settings.no_trailing_commas,
+            },
+            ..GeneratorOptions::default()
+        })
+    }
+}
+
+/// Leaves out what settings can't hold: the source info, hooks, the banner's source,
+/// and custom schema naming, which becomes the default.
+impl From<GeneratorOptions> for Settings {
+    fn from(options: GeneratorOptions) -> Self {
+        let (union_types, tag_field) = match options.union_types {
+            None => (None, "kind".to_string()),
+            Some(UnionStyle::Keyed) => (Some(UnionTypes::Keyed), "kind".to_string()),
+            Some(UnionStyle::Tagged { field }) => (Some(UnionTypes::Tagged), field),
+        };
+        let (schema_prefix, schema_suffix) = match options.schema_naming {
+            SchemaNaming::Affix { prefix, suffix } => (prefix, suffix),
+            SchemaNaming::Custom(_) => (String::new(), "Schema".to_string()),
+        };
+        let mut map = Vec::new();
+        let mut map_import = Vec::new();
+        for (declaration, mapping) in options.type_mappings {
+            match mapping {
+                TypeMapping::Expression(expression) => map.push((declaration, expression)),
+                TypeMapping::Import { module, name } => {
+                    map_import.push((declaration, format!("{}@{}", name, module)))
+                }
+            }
+        }
+
+        Settings {
+            backend: options.target,
+            mode: options.output_mode,
+            module_format: options.module_format,
+            newtypes: options.newtype_mode,
+            field_case: options.field_case,
+            large_integers: options.large_integers,
+            inlining: options.inlining,
+            export_style: options.export_style,
+            union_types,
+            tag_field,
+            schema_prefix,
+            schema_suffix,
+            runtime_module: options.runtime.module,
+            runtime_binding: options.runtime.binding,
+            prefix: options.prefix,
+            namespace: options.namespace,
+            rename: options.renames.into_iter().collect(),
+            map,
+            map_import,
+            include: options.filter.include,
+            exclude: options.filter.exclude,
+            module_files: options.module_files,
+            strict: options.strict,
+            hoist_duplicates: options.hoist_duplicates,
+            readonly: options.readonly_types,
+            rust_type_docs: options.rust_type_docs,
+            codec_functions: options.codec_functions,
+            type_guards: options.type_guards,
+            tag_constants: options.tag_constants,
+            variant_constructors: options.variant_constructors,
+            mocks: options.mocks,
+            roundtrip_spec: options.roundtrip_spec,
+            byte_sizes: options.byte_sizes,
+            field_offsets: options.field_offsets,
+            schema_registry: options.schema_registry,
+            hash_constant: options.hash_constant,
+            source_map: options.source_map,
+            banner: options.banner.is_some(),
+            timestamp: options.banner.is_some_and(|banner| banner.timestamp),
+            indent: options.format.indent,
+            single_quotes: options.format.quotes == Quotes::Single,
+            no_semicolons: !options.format.semicolons,
+            no_trailing_commas: !options.format.trailing_commas,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn defaults_match_flags() {
+        use clap::{Args, FromArgMatches};
+
+        let command = Settings::augment_args(clap::Command::new("zorsh-gen"));
+        let matches = command.try_get_matches_from(["zorsh-gen"]).unwrap();
+        assert_eq!(
+            Settings::from_arg_matches(&matches).unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn round_trips_options() {
+        let options = GeneratorOptions::try_from(Settings {
+            union_types: Some(UnionTypes::Tagged),
+            tag_field: "type".to_string(),
+            map_import: vec![("U128".to_string(), "U128Schema@@near/zorsh".to_string())],
+            indent: Indent::Tabs,
+            banner: true,
+            ..Settings::default()
+        })
+        .unwrap();
+        assert_eq!(
+            options.type_mappings["U128"],
+            TypeMapping::Import {
+                module: "@near/zorsh".to_string(),
+                name: "U128Schema".to_string()
+            }
+        );
+
+        let text = toml::to_string(&options).unwrap();
+        assert!(text.contains("indent = \"tab\"\n"), "{}", text);
+        let read: GeneratorOptions = toml::from_str(&text).unwrap();
+        assert_eq!(Settings::from(read), Settings::from(options));
+
+        let error = toml::from_str::<GeneratorOptions>("map-import = { U128 = \"U128Schema\" }");
+        assert!(error.unwrap_err().to_string().contains("NAME@MODULE"));
+        assert!(toml::from_str::<GeneratorOptions>("no-such-flag = true").is_err());
+    }
+}
@@ -0,0 +1,320 @@
+// src/source.rs
+//! Metadata recovered from Rust source that a `BorshSchemaContainer` doesn't carry.
+use borsh::schema::Declaration;
+#[cfg(feature = "source")]
+use quote::ToTokens;
+use std::collections::BTreeMap;
+
+/// Information about the Rust types behind a schema, supplied by hand or parsed
+/// from source with [`SourceInfo::parse`].
+#[derive(Clone, Debug, Default)]
+pub struct SourceInfo {
+    /// Type aliases by name, e.g. `Balance` → `u128`.
+    pub aliases: BTreeMap<String, Declaration>,
+    /// Aliases used directly as a field's type, keyed by the containing type and the
+    /// field name (or index, for tuple structs).
+    pub field_aliases: BTreeMap<(Declaration, String), String>,
+    /// `///` doc comments of structs, enums and type aliases, one entry per line.
+    pub docs: BTreeMap<String, Vec<String>>,
+    /// Doc comments of struct fields and enum variants, keyed by the containing type and
+    /// the field name (or index), or the variant name.
+    pub member_docs: BTreeMap<(Declaration, String), Vec<String>>,
+    /// The inline `mod` blocks that structs, enums and type aliases are declared in,
+    /// outermost first, e.g. `Player` → `["game", "players"]`. Types at the top of the
+    /// file have no entry.
+    pub modules: BTreeMap<String, Vec<String>>,
+}
+
+#[cfg(feature = "source")]
+impl SourceInfo {
+    /// Collects type aliases, the struct fields declared with them, and doc comments
+    /// from a Rust file.
+    pub fn parse(source: &str) -> syn::Result<Self> {
+        let file = syn::parse_file(source)?;
+        let mut info = Self::default();
+        info.collect_aliases(&file.items);
+        info.collect_field_aliases(&file.items);
+        info.collect_docs(&file.items);
+        info.collect_modules(&file.items, &mut Vec::new());
+        Ok(info)
+    }
+
+    fn collect_docs(&mut self, items: &[syn::Item]) {
+        for item in items {
+            match item {
+                syn::Item::Struct(item) => {
+                    self.insert_docs(item.ident.to_string(), &item.attrs);
+                    for (index, field) in item.fields.iter().enumerate() {
+                        let name = field
+                            .ident
+                            .as_ref()
+                            .map_or_else(|| index.to_string(), ToString::to_string);
+                        self.insert_member_docs(item.ident.to_string(), name, &field.attrs);
+                    }
+                }
+                syn::Item::Enum(item) => {
+                    self.insert_docs(item.ident.to_string(), &item.attrs);
+                    for variant in &item.variants {
+                        let name = variant.ident.to_string();
+                        self.insert_member_docs(item.ident.to_string(), name, &variant.attrs);
+                    }
+                }
+                syn::Item::Type(item) => self.insert_docs(item.ident.to_string(), &item.attrs),
+                syn::Item::Mod(module) => {
+                    if let Some((_, items)) = &module.content {
+                        self.collect_docs(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn collect_modules(&mut self, items: &[syn::Item], path: &mut Vec<String>) {
+        for item in items {
+            let name = match item {
+                syn::Item::Struct(item) => &item.ident,
+                syn::Item::Enum(item) => &item.ident,
+                syn::Item::Type(item) => &item.ident,
+                syn::Item::Mod(module) => {
+                    if let Some((_, items)) = &module.content {
+                        path.push(module.ident.to_string());
+                        self.collect_modules(items, path);
+                        path.pop();
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if !path.is_empty() {
+                self.modules.insert(name.to_string(), path.clone());
+            }
+        }
+    }
+
+    fn insert_docs(&mut self, name: String, attrs: &[syn::Attribute]) {
+        let docs = doc_lines(attrs);
+        if !docs.is_empty() {
+            self.docs.insert(name, docs);
+        }
+    }
+
+    fn insert_member_docs(&mut self, parent: String, name: String, attrs: &[syn::Attribute]) {
+        let docs = doc_lines(attrs);
+        if !docs.is_empty() {
+            self.member_docs.insert((parent, name), docs);
+        }
+    }
+
+    fn collect_aliases(&mut self, items: &[syn::Item]) {
+        for item in items {
+            match item {
+                syn::Item::Type(alias) => {
+                    self.aliases
+                        .insert(alias.ident.to_string(), declaration(&alias.ty));
+                }
+                syn::Item::Mod(module) => {
+                    if let Some((_, items)) = &module.content {
+                        self.collect_aliases(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn collect_field_aliases(&mut self, items: &[syn::Item]) {
+        for item in items {
+            match item {
+                syn::Item::Struct(item) => {
+                    for (index, field) in item.fields.iter().enumerate() {
+                        let Some(alias) = self.alias_name(&field.ty) else {
+                            continue;
+                        };
+                        let name = field
+                            .ident
+                            .as_ref()
+                            .map_or_else(|| index.to_string(), ToString::to_string);
+                        self.field_aliases
+                            .insert((item.ident.to_string(), name), alias);
+                    }
+                }
+                syn::Item::Mod(module) => {
+                    if let Some((_, items)) = &module.content {
+                        self.collect_field_aliases(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the alias a type refers to, if it is a known alias without generics.
+    fn alias_name(&self, ty: &syn::Type) -> Option<String> {
+        let syn::Type::Path(path) = ty else {
+            return None;
+        };
+        let segment = path.path.segments.last()?;
+        let name = segment.ident.to_string();
+        (segment.arguments.is_none() && self.aliases.contains_key(&name)).then_some(name)
+    }
+}
+
+/// Returns the lines of the `///` comments (`#[doc = "..."]` attributes) in `attrs`.
+#[cfg(feature = "source")]
+fn doc_lines(attrs: &[syn::Attribute]) -> Vec<String> {
+    let mut lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value()),
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.split('\n')
+                .map(|line| {
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    while lines.first().is_some_and(String::is_empty) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Spells a Rust type the way borsh declares it: without module paths, with
+/// generic arguments converted recursively.
+#[cfg(feature = "source")]
+fn declaration(ty: &syn::Type) -> Declaration {
+    match ty {
+        syn::Type::Path(path) => {
+            let Some(segment) = path.path.segments.last() else {
+                return ty.to_token_stream().to_string();
+            };
+            let mut name = segment.ident.to_string();
+            if let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments {
+                let arguments: Vec<Declaration> = arguments
+                    .args
+                    .iter()
+                    .map(|argument| match argument {
+                        syn::GenericArgument::Type(ty) => declaration(ty),
+                        other => other.to_token_stream().to_string(),
+                    })
+                    .collect();
+                name.push_str(&format!("<{}>", arguments.join(", ")));
+            }
+            name
+        }
+        syn::Type::Array(array) => format!(
+            "[{}; {}]",
+            declaration(&array.elem),
+            array.len.to_token_stream()
+        ),
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => "()".to_string(),
+        syn::Type::Tuple(tuple) => {
+            let elements: Vec<Declaration> = tuple.elems.iter().map(declaration).collect();
+            format!("({})", elements.join(", "))
+        }
+        syn::Type::Reference(reference) => declaration(&reference.elem),
+        syn::Type::Paren(paren) => declaration(&paren.elem),
+        syn::Type::Group(group) => declaration(&group.elem),
+        _ => ty.to_token_stream().to_string(),
+    }
+}
+
+#[cfg(all(test, feature = "source"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_aliases_and_their_fields() {
+        let info = SourceInfo::parse(
+            r#"
+            type Balance = u128;
+            mod ids {
+                pub type Ids = std::collections::HashSet<AccountId>;
+                pub type Hash = [u8; 32];
+            }
+            struct Wallet {
+                balance: Balance,
+                ids: ids::Ids,
+                raw: u128,
+                history: Vec<Balance>,
+            }
+            struct Pair(Balance, u8);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(info.aliases["Balance"], "u128");
+        assert_eq!(info.aliases["Ids"], "HashSet<AccountId>");
+        assert_eq!(info.aliases["Hash"], "[u8; 32]");
+
+        let field = |ty: &str, field: &str| {
+            info.field_aliases
+                .get(&(ty.to_string(), field.to_string()))
+                .cloned()
+        };
+        assert_eq!(field("Wallet", "balance").as_deref(), Some("Balance"));
+        assert_eq!(field("Wallet", "ids").as_deref(), Some("Ids"));
+        assert_eq!(field("Wallet", "raw"), None);
+        assert_eq!(field("Wallet", "history"), None);
+        assert_eq!(field("Pair", "0").as_deref(), Some("Balance"));
+
+        assert_eq!(info.modules["Ids"], ["ids"]);
+        assert!(!info.modules.contains_key("Wallet"));
+    }
+
+    #[test]
+    fn collects_doc_comments() {
+        let info = SourceInfo::parse(
+            r#"
+            /// A player in the lobby.
+            ///
+            /// Players are keyed by account.
+            struct Player {
+                /// Display name.
+                name: String,
+                level: u8,
+            }
+            enum Event {
+                /** Someone joined. */
+                Joined(Player),
+                Left,
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            info.docs["Player"],
+            [
+                "A player in the lobby.",
+                "",
+                "Players are keyed by account."
+            ]
+        );
+        assert!(!info.docs.contains_key("Event"));
+        let member =
+            |ty: &str, member: &str| info.member_docs.get(&(ty.to_string(), member.to_string()));
+        assert_eq!(member("Player", "name").unwrap(), &["Display name."]);
+        assert_eq!(member("Player", "level"), None);
+        assert_eq!(member("Event", "Joined").unwrap(), &["Someone joined."]);
+        assert_eq!(member("Event", "Left"), None);
+    }
+}
@@ -0,0 +1,72 @@
+// src/source_map.rs
+//! A JSON file mapping each Rust declaration to where it was generated, for tools that
+//! navigate or rewrite generated code.
+use super::{schema_hash, OutputMode, Parser};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceMap<'a> {
+    version: u32,
+    schema_hash: String,
+    entries: Vec<Entry<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry<'a> {
+    /// The Rust declaration or type alias.
+    rust: &'a str,
+    /// The exported TypeScript type.
+    name: &'a str,
+    /// The exported schema constant; absent for declaration-only output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    file: &'a str,
+    /// The 1-based line the schema, or the type for declaration-only output, starts on.
+    line: usize,
+}
+
+impl Parser<'_> {
+    /// Returns the source map for generated `files`, keyed by file name.
+    pub(super) fn source_map(&self, files: &BTreeMap<String, String>) -> String {
+        let declarations = self.options.output_mode == OutputMode::Declarations;
+        let mut entries = Vec::new();
+        for chunk in &self.chunks {
+            let schema = self.options.schema_naming.apply(&chunk.name);
+            let starts = |line: &str| {
+                let line = line.trim_start();
+                let line = line.strip_prefix("export ").unwrap_or(line);
+                match declarations {
+                    true => [
+                        format!("interface {} ", chunk.name),
+                        format!("type {} ", chunk.name),
+                    ]
+                    .iter()
+                    .any(|start| line.starts_with(start.as_str())),
+                    false => line.starts_with(&format!("const {} ", schema)),
+                }
+            };
+            let location = files.iter().find_map(|(file, contents)| {
+                let line = contents.lines().position(starts)?;
+                Some((file.as_str(), line + 1))
+            });
+            if let Some((file, line)) = location {
+                entries.push(Entry {
+                    rust: &chunk.declaration,
+                    name: &chunk.name,
+                    schema: (!declarations).then_some(schema),
+                    file,
+                    line,
+                });
+            }
+        }
+        let map = SourceMap {
+            version: 1,
+            schema_hash: format!("sha256:{}", schema_hash(self.container)),
+            entries,
+        };
+        serde_json::to_string_pretty(&map).expect("a source map always serializes") + "\n"
+    }
+}
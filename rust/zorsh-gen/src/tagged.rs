@@ -0,0 +1,139 @@
+// src/tagged.rs
+//! Tag-field unions for enums: `{ kind: "PlayerJoined"; player_id: string }` instead of
+//! zorsh's `{ PlayerJoined: { player_id: string } }`, with functions converting between
+//! the two, since schemas only encode and decode the latter.
+use super::{ir, property_access, property_key, unraw, Parser};
+
+/// How an enum variant's payload appears in its tagged form.
+enum Payload {
+    /// Nothing besides the tag.
+    Unit,
+    /// Named fields, spread next to the tag.
+    Fields(Vec<(String, String)>),
+    /// Anything else, under a `value` property.
+    Value(String),
+}
+
+impl Parser<'_> {
+    /// Returns the `{Enum}Tagged` type and, unless only types are generated, the
+    /// `to{Enum}Tagged` and `from{Enum}Tagged` conversions for an exported enum.
+    pub(super) fn tagged_union(&mut self, declaration: &str, tag: &str, functions: bool) -> String {
+        let Some(ir::Type::Enum { variants, .. }) = self.ty(declaration) else {
+            return String::new();
+        };
+        if variants.is_empty() {
+            return String::new();
+        }
+        let variants: Vec<(String, Payload)> = variants
+            .iter()
+            .map(|variant| (variant.name.clone(), self.tagged_payload(&variant.payload)))
+            .collect();
+        for (variant, payload) in &variants {
+            let clashes = match payload {
+                Payload::Fields(fields) => fields.iter().any(|(name, _)| unraw(name) == tag),
+                Payload::Value(_) => tag == "value",
+                Payload::Unit => false,
+            };
+            if clashes {
+                self.error(
+                    declaration,
+                    format!(
+                        "variant `{}::{}` has a field named `{}`, which is also the tag of its tagged union",
+                        declaration, variant, tag
+                    ),
+                );
+            }
+        }
+
+        let name = self.type_name(declaration);
+        let tagged = format!("{}Tagged", name);
+        let key = property_key(tag);
+        let readonly = self.readonly();
+        let mut output = format!("export type {} =", tagged);
+        self.types.push(tagged.clone());
+        for (variant, payload) in &variants {
+            let mut members = vec![format!("{}{}: \"{}\"", readonly, key, unraw(variant))];
+            match payload {
+                Payload::Unit => {}
+                Payload::Fields(fields) => members.extend(
+                    fields
+                        .iter()
+                        .map(|(field, ty)| format!("{}{}: {}", readonly, property_key(field), ty)),
+                ),
+                Payload::Value(ty) => members.push(format!("{}value: {}", readonly, ty)),
+            }
+            output.push_str(&format!("\n  | {{ {} }}", members.join("; ")));
+        }
+        output.push_str(";\n\n");
+        if !functions {
+            return output;
+        }
+
+        output.push_str(&format!(
+            "export function to{0}(value: {1}): {0} {{\n",
+            tagged, name
+        ));
+        for (variant, payload) in &variants {
+            let kind = format!("{}: \"{}\"", key, unraw(variant));
+            let access = property_access("value", variant);
+            let tagged = match payload {
+                Payload::Unit => format!("{{ {} }}", kind),
+                Payload::Fields(_) => format!("{{ {}, ...{} }}", kind, access),
+                Payload::Value(_) => format!("{{ {}, value: {} }}", kind, access),
+            };
+            output.push_str(&format!(
+                "  if (\"{}\" in value) {{\n    return {};\n  }}\n",
+                unraw(variant),
+                tagged
+            ));
+        }
+        // Every variant is handled above, so `value` is `never` here
+        output.push_str("  return value;\n}\n\n");
+
+        output.push_str(&format!(
+            "export function from{0}(value: {0}): {1} {{\n  switch ({2}) {{\n",
+            tagged,
+            name,
+            property_access("value", tag)
+        ));
+        for (variant, payload) in &variants {
+            let variant_key = property_key(variant);
+            let body = match payload {
+                Payload::Unit => format!("return {{ {}: {{}} }};", variant_key),
+                Payload::Fields(_) => format!(
+                    "const {{ {}: _, ...payload }} = value;\n      return {{ {}: payload }};",
+                    key, variant_key
+                ),
+                Payload::Value(_) => format!("return {{ {}: value.value }};", variant_key),
+            };
+            output.push_str(&format!(
+                "    case \"{}\": {{\n      {}\n    }}\n",
+                unraw(variant),
+                body
+            ));
+        }
+        output.push_str("  }\n}\n\n");
+        self.exports.push(format!("to{}", tagged));
+        self.exports.push(format!("from{}", tagged));
+        output
+    }
+
+    /// Returns the shape of an enum variant's payload, matching `parse_ts_variant`.
+    fn tagged_payload(&self, declaration: &str) -> Payload {
+        if self.is_unit(declaration) {
+            return Payload::Unit;
+        }
+        let resolved = self.resolve(declaration).map_or(declaration, |(d, _)| d);
+        if self.variant_structs.contains(resolved) {
+            if let Some(ir::Type::Struct(ir::Fields::Named(fields))) = self.ty(resolved) {
+                return Payload::Fields(
+                    fields
+                        .iter()
+                        .map(|field| (self.field_name(&field.name), self.parse_ts_type(&field.ty)))
+                        .collect(),
+                );
+            }
+        }
+        Payload::Value(self.parse_ts_variant(declaration))
+    }
+}
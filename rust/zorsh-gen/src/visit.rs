@@ -0,0 +1,164 @@
+// src/visit.rs
+//! [`SchemaVisitor`], for analyses of a schema such as size audits or naming lints, and
+//! [`walk`], which drives one over every definition.
+use super::ir::{self, Fields, Length, Type, Variant};
+use borsh::schema::Declaration;
+use std::collections::HashSet;
+
+/// Callbacks for each kind of definition, all doing nothing by default.
+///
+/// [`walk`] calls [`SchemaVisitor::visit_type`] for a definition after visiting the
+/// definitions it refers to, so a visitor can work out something about a type from what
+/// it found for its fields, as in this count of the fields each struct holds directly or
+/// through the structs it contains:
+///
+/// ```
+/// use borsh::schema::{BorshSchemaContainer, Declaration};
+/// use zorsh_gen::{ir, walk, SchemaVisitor};
+/// use std::collections::HashMap;
+///
+/// #[derive(Default)]
+/// struct FieldCount(HashMap<Declaration, usize>);
+///
+/// impl SchemaVisitor for FieldCount {
+///     fn visit_struct(&mut self, declaration: &Declaration, fields: &ir::Fields) {
+///         let count = fields
+///             .types()
+///             .iter()
+///             .map(|ty| self.0.get(*ty).copied().unwrap_or(1))
+///             .sum();
+///         self.0.insert(declaration.clone(), count);
+///     }
+/// }
+///
+/// let container = BorshSchemaContainer::for_type::<borsh_test::Player>();
+/// let mut count = FieldCount::default();
+/// walk(&ir::Schema::new(&container), &mut count);
+/// assert!(count.0["Player"] > 0);
+/// ```
+///
+/// Types that refer to themselves are visited before the definitions on the way back to
+/// them, as those aren't finished yet.
+#[allow(unused_variables)]
+pub trait SchemaVisitor {
+    /// Calls the method for the kind of `ty`; overriding it sees every definition.
+    fn visit_type(&mut self, declaration: &Declaration, ty: &Type) {
+        match ty {
+            Type::Primitive(size) => self.visit_primitive(declaration, *size),
+            Type::String(length) => self.visit_string(declaration, length),
+            Type::Bytes(length) => self.visit_bytes(declaration, length),
+            Type::Sequence { element, length } => self.visit_sequence(declaration, element, length),
+            Type::Map { key, value, length } => self.visit_map(declaration, key, value, length),
+            Type::Set { element, length } => self.visit_set(declaration, element, length),
+            Type::Tuple(elements) => self.visit_tuple(declaration, elements),
+            Type::Option(value) => self.visit_option(declaration, value),
+            Type::Struct(fields) => self.visit_struct(declaration, fields),
+            Type::Enum {
+                tag_width,
+                variants,
+            } => self.visit_enum(declaration, *tag_width, variants),
+        }
+    }
+
+    fn visit_primitive(&mut self, declaration: &Declaration, size: u8) {}
+
+    fn visit_string(&mut self, declaration: &Declaration, length: &Length) {}
+
+    fn visit_bytes(&mut self, declaration: &Declaration, length: &Length) {}
+
+    fn visit_sequence(
+        &mut self,
+        declaration: &Declaration,
+        element: &Declaration,
+        length: &Length,
+    ) {
+    }
+
+    fn visit_map(
+        &mut self,
+        declaration: &Declaration,
+        key: &Declaration,
+        value: &Declaration,
+        length: &Length,
+    ) {
+    }
+
+    fn visit_set(&mut self, declaration: &Declaration, element: &Declaration, length: &Length) {}
+
+    fn visit_tuple(&mut self, declaration: &Declaration, elements: &[Declaration]) {}
+
+    fn visit_option(&mut self, declaration: &Declaration, value: &Declaration) {}
+
+    fn visit_struct(&mut self, declaration: &Declaration, fields: &Fields) {}
+
+    fn visit_enum(&mut self, declaration: &Declaration, tag_width: u8, variants: &[Variant]) {}
+
+    /// A declaration referred to that the schema doesn't define, as written where it is
+    /// referred to.
+    fn visit_missing(&mut self, declaration: &Declaration) {}
+}
+
+/// Visits every definition of `schema` once, depth-first: the root type and what it
+/// refers to, then any definitions it doesn't reach, in declaration order.
+pub fn walk(schema: &ir::Schema, visitor: &mut (impl SchemaVisitor + ?Sized)) {
+    let mut visited = HashSet::new();
+    visit(schema, &schema.root, visitor, &mut visited);
+    for declaration in schema.types.keys() {
+        visit(schema, declaration, visitor, &mut visited);
+    }
+}
+
+fn visit<'a>(
+    schema: &'a ir::Schema,
+    declaration: &'a Declaration,
+    visitor: &mut (impl SchemaVisitor + ?Sized),
+    visited: &mut HashSet<&'a Declaration>,
+) {
+    let Some((declaration, ty)) = schema.get(declaration) else {
+        if visited.insert(declaration) {
+            visitor.visit_missing(declaration);
+        }
+        return;
+    };
+    if !visited.insert(declaration) {
+        return;
+    }
+    for reference in ty.references() {
+        visit(schema, reference, visitor, visited);
+    }
+    visitor.visit_type(declaration, ty);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::schema::BorshSchemaContainer;
+    use borsh_test::GameState;
+
+    #[derive(Default)]
+    struct Order(Vec<String>);
+
+    impl SchemaVisitor for Order {
+        fn visit_type(&mut self, declaration: &Declaration, _ty: &Type) {
+            self.0.push(declaration.clone());
+        }
+
+        fn visit_missing(&mut self, declaration: &Declaration) {
+            self.0.push(format!("missing {}", declaration));
+        }
+    }
+
+    #[test]
+    fn visits_references_first() {
+        let container = BorshSchemaContainer::for_type::<GameState>();
+        let schema = ir::Schema::new(&container);
+        let mut order = Order::default();
+        walk(&schema, &mut order);
+
+        let position = |declaration: &str| order.0.iter().position(|d| d == declaration);
+        assert_eq!(order.0.len(), schema.types.len());
+        assert!(position("Player") < position("HashMap<String, Player>"));
+        assert!(position("HashMap<String, Player>") < position("GameState"));
+        assert!(position("GameEventPlayerJoined") < position("GameEvent"));
+    }
+}
@@ -0,0 +1,341 @@
+// src/zod.rs
+//! The Zod backend: TypeScript Zod schemas validating values as zorsh decodes them, for
+//! checking data such as form input or API payloads before it is encoded.
+//!
+//! Values have the shapes of the generated TypeScript types, so each exported type is
+//! the `z.infer` of its schema: 64- and 128-bit integers are `bigint`s unless
+//! `LargeIntegers` says otherwise, byte sequences `Uint8Array`s, maps `Map`s, sets
+//! `Set`s and `None` is `null`. Integers are checked against the range of their Rust
+//! type and collections against the lengths borsh allows them. String lengths aren't
+//! checked, as borsh bounds UTF-8 bytes and JavaScript counts UTF-16 code units.
+//!
+//! Types that refer back to themselves declare their type, as Zod can't infer it, and
+//! refer to their schema with `z.lazy`.
+use super::{
+    ir, jsdoc, large_integer, normalize, primitive_to_ts, property_key, Declaration, LargeIntegers,
+    NewtypeMode, Parser, ZorshGenError,
+};
+use std::collections::HashSet;
+
+/// The module Zod is imported from.
+const ZOD_MODULE: &str = "zod";
+
+/// The exported schemas written so far.
+#[derive(Default)]
+struct Definitions {
+    /// The code of each, in an order that defines schemas before those using them.
+    code: Vec<String>,
+    /// The declarations whose schema has been started.
+    started: HashSet<Declaration>,
+    /// The declarations whose schema isn't finished, so that others refer to it lazily.
+    pending: HashSet<Declaration>,
+    /// The declarations referred to while pending, whose type is declared.
+    recursive: HashSet<Declaration>,
+}
+
+impl Parser<'_> {
+    /// Generates a TypeScript module exporting a Zod schema and its inferred type for the
+    /// root type and each struct and enum that would be exported as its own schema.
+    pub(super) fn zod_schema(&mut self) -> Result<String, ZorshGenError> {
+        let mut defs = Definitions::default();
+        let root = self.container.declaration().clone();
+        match self.ir.get(&root) {
+            // The root is exported even if it wouldn't be elsewhere, such as a `Vec`
+            Some((declaration, ty)) => {
+                self.zod_export(declaration, ty, &mut defs);
+            }
+            None => {
+                self.zod_type(&root, &mut defs);
+            }
+        }
+        if !self.errors.is_empty() {
+            return Err(self.failure());
+        }
+
+        let mut output = self.banner();
+        output.push_str(&format!("import {{ z }} from \"{}\";\n\n", ZOD_MODULE));
+        output.push_str(&defs.code.join("\n"));
+        Ok(super::format::format(&output, &self.options.format))
+    }
+
+    /// Returns the schema of a declaration, referring to its exported schema, written if
+    /// needed, for exported structs and enums.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, defs))
+    )]
+    fn zod_type(&mut self, declaration: &str, defs: &mut Definitions) -> String {
+        if self.mappings.contains_key(&normalize(declaration)) {
+            return format!(
+                "z.unknown() /* Rust: {}, mapped to a custom schema */",
+                declaration
+            );
+        }
+        let Some((declaration, ty)) = self.ir.get(declaration) else {
+            self.unresolved.insert(declaration.to_string());
+            self.strict_error(
+                declaration,
+                format!(
+                    "`{}` isn't defined in the schema container, mapped or resolved",
+                    declaration
+                ),
+            );
+            return format!("z.unknown() /* TODO: Resolve {} */", declaration);
+        };
+        if !self.is_exported(declaration, ty) {
+            return self.zod_definition(declaration, ty, defs);
+        }
+        self.zod_export(declaration, ty, defs)
+    }
+
+    /// Writes the exported schema and type of a declaration unless it already is, and
+    /// returns a reference to the schema.
+    fn zod_export(
+        &mut self,
+        declaration: &Declaration,
+        ty: &ir::Type,
+        defs: &mut Definitions,
+    ) -> String {
+        let schema_name = self.schema_name(declaration);
+        if defs.pending.contains(declaration) {
+            defs.recursive.insert(declaration.clone());
+            return format!("z.lazy(() => {})", schema_name);
+        }
+        if !defs.started.insert(declaration.clone()) {
+            return schema_name;
+        }
+        defs.pending.insert(declaration.clone());
+        self.exported.push(declaration.clone());
+        let schema = self.zod_definition(declaration, ty, defs);
+        defs.pending.remove(declaration);
+        // Later type declarations refer to it by name
+        self.generated.insert(declaration.clone());
+
+        let name = self.type_name(declaration);
+        let docs = jsdoc(&self.docs(declaration), "");
+        let code = match defs.recursive.contains(declaration) {
+            true => format!(
+                "{}{}\nexport const {}: z.ZodType<{}> = {};\n",
+                docs,
+                self.type_declaration(declaration).trim_end(),
+                schema_name,
+                name,
+                schema
+            ),
+            false => format!(
+                "{}export const {} = {};\nexport type {} = z.infer<typeof {}>;\n",
+                docs, schema_name, schema, name, schema_name
+            ),
+        };
+        defs.code.push(code);
+        schema_name
+    }
+
+    /// Returns the schema of a type, even if it is exported.
+    fn zod_definition(
+        &mut self,
+        declaration: &str,
+        ty: &ir::Type,
+        defs: &mut Definitions,
+    ) -> String {
+        match ty {
+            ir::Type::Primitive(size) => self.zod_primitive(declaration, *size),
+            ir::Type::String(_) => "z.string()".to_string(),
+            ir::Type::Map { key, value, length } => {
+                let key = self.zod_type(key, defs);
+                let value = self.zod_type(value, defs);
+                format!("z.map({}, {}){}", key, value, refine_length(length, "size"))
+            }
+            ir::Type::Set { element, length } => {
+                let element = self.zod_type(element, defs);
+                format!("z.set({}){}", element, length_checks(length, "size"))
+            }
+            ir::Type::Bytes(length) => {
+                format!(
+                    "z.instanceof(Uint8Array){}",
+                    refine_length(length, "length")
+                )
+            }
+            ir::Type::Sequence { element, length } => {
+                let element = self.zod_type(element, defs);
+                format!("z.array({}){}", element, length_checks(length, "length"))
+            }
+            ir::Type::Tuple(elements) => self.zod_tuple(elements, defs),
+            ir::Type::Option(value) => {
+                format!("{}.nullable()", self.zod_option_value(value, defs))
+            }
+            ir::Type::Enum { variants, .. } => {
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|variant| {
+                        let payload = self.zod_variant(&variant.payload, defs);
+                        keyed(&variant.name, &payload)
+                    })
+                    .collect();
+                match variants.as_slice() {
+                    [] => "z.never()".to_string(),
+                    [variant] => variant.clone(),
+                    _ => format!("z.union([\n{},\n])", variants.join(",\n")),
+                }
+            }
+            ir::Type::Struct(fields) => match fields {
+                ir::Fields::Empty => "z.strictObject({})".to_string(),
+                ir::Fields::Named(fields) => self.zod_object(fields, defs),
+                ir::Fields::Unnamed(elements)
+                    if elements.len() == 1 && self.options.newtype_mode != NewtypeMode::Tuple =>
+                {
+                    self.zod_type(&elements[0], defs)
+                }
+                ir::Fields::Unnamed(elements) => self.zod_tuple(elements, defs),
+            },
+        }
+    }
+
+    /// Returns the schema of a primitive, matching `primitive_ts`.
+    fn zod_primitive(&mut self, declaration: &str, size: u8) -> String {
+        match (
+            self.options.large_integers,
+            large_integer(declaration, size),
+        ) {
+            (LargeIntegers::Number, Some("u64")) => {
+                return "z.number().int().min(0).max(Number.MAX_SAFE_INTEGER)".to_string();
+            }
+            (LargeIntegers::Number, Some("i64")) => {
+                return "z.number().int().min(Number.MIN_SAFE_INTEGER).max(Number.MAX_SAFE_INTEGER)"
+                    .to_string();
+            }
+            (LargeIntegers::String, Some(large)) => {
+                let pattern = if large.starts_with('u') {
+                    "/^[0-9]+$/"
+                } else {
+                    "/^-?[0-9]+$/"
+                };
+                return format!("z.string().regex({})", pattern);
+            }
+            (_, Some(large)) => {
+                let (minimum, maximum) = match large {
+                    "u64" => (0, i128::from(u64::MAX)),
+                    "i64" => (i128::from(i64::MIN), i128::from(i64::MAX)),
+                    "i128" => (i128::MIN, i128::MAX),
+                    _ => return format!("z.bigint().min(0n).max({}n)", u128::MAX),
+                };
+                return format!("z.bigint().min({}n).max({}n)", minimum, maximum);
+            }
+            _ => {}
+        }
+        match (declaration, primitive_to_ts(declaration, size)) {
+            ("f32" | "f64", _) => "z.number()".to_string(),
+            ("bool", _) => "z.boolean()".to_string(),
+            ("()", _) => "z.strictObject({})".to_string(),
+            ("i8", _) => integer(i8::MIN.into(), i8::MAX.into()),
+            ("i16", _) => integer(i16::MIN.into(), i16::MAX.into()),
+            ("i32", _) => integer(i32::MIN.into(), i32::MAX.into()),
+            (_, "number") => integer(0, (1i64 << (8 * u32::from(size))) - 1),
+            _ => {
+                self.strict_error(
+                    declaration,
+                    format!(
+                        "primitive `{}` is {} bytes wide, which no zorsh builder reads",
+                        declaration, size
+                    ),
+                );
+                format!(
+                    "z.unknown() /* TODO: Read {}-byte primitive {} */",
+                    size, declaration
+                )
+            }
+        }
+    }
+
+    /// Returns the schema of an `Option` payload, matching `parse_option_value`: nested
+    /// options are keyed `None`/`Some` objects, so that `Some(None)` isn't `null`.
+    fn zod_option_value(&mut self, declaration: &str, defs: &mut Definitions) -> String {
+        if let Some(ir::Type::Option(value)) = self.ty(declaration) {
+            let value = self.zod_option_value(value, defs);
+            return format!(
+                "z.union([{}, {}])",
+                keyed("None", "z.strictObject({})"),
+                keyed("Some", &value)
+            );
+        }
+        self.zod_type(declaration, defs)
+    }
+
+    /// Returns the schema of an enum variant payload, matching `parse_ts_variant`.
+    fn zod_variant(&mut self, declaration: &str, defs: &mut Definitions) -> String {
+        let declaration = self.resolve(declaration).map_or(declaration, |(d, _)| d);
+        if !self.variant_structs.contains(declaration) {
+            return self.zod_type(declaration, defs);
+        }
+        match self.ir.get(declaration) {
+            Some((_, ir::Type::Struct(fields))) => match fields {
+                ir::Fields::Named(fields) => self.zod_object(fields, defs),
+                ir::Fields::Unnamed(elements) if elements.len() == 1 => {
+                    self.zod_type(&elements[0], defs)
+                }
+                ir::Fields::Unnamed(elements) => self.zod_tuple(elements, defs),
+                ir::Fields::Empty => "z.strictObject({})".to_string(),
+            },
+            _ => self.zod_type(declaration, defs),
+        }
+    }
+
+    fn zod_object(&mut self, fields: &[ir::Field], defs: &mut Definitions) -> String {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}: {},\n",
+                    property_key(&self.field_name(&field.name)),
+                    self.zod_type(&field.ty, defs)
+                )
+            })
+            .collect();
+        format!("z.strictObject({{\n{}}})", fields.concat())
+    }
+
+    fn zod_tuple(&mut self, elements: &[String], defs: &mut Definitions) -> String {
+        let elements: Vec<String> = elements.iter().map(|e| self.zod_type(e, defs)).collect();
+        format!("z.tuple([{}])", elements.join(", "))
+    }
+}
+
+/// A number schema accepting the integers from `minimum` to `maximum`.
+fn integer(minimum: i64, maximum: i64) -> String {
+    format!("z.number().int().min({}).max({})", minimum, maximum)
+}
+
+/// An object with a single property `name`, as enum values are.
+fn keyed(name: &str, payload: &str) -> String {
+    format!("z.strictObject({{ {}: {} }})", property_key(name), payload)
+}
+
+/// The `min`, `max` or `length`/`size` checks of a collection with the lengths a
+/// sequence may have, or none if any `u32` length is allowed.
+fn length_checks(length: &ir::Length, exact: &str) -> String {
+    if let Some(fixed) = length.fixed() {
+        return format!(".{}({})", exact, fixed);
+    }
+    let mut checks = String::new();
+    if *length.range.start() > 0 {
+        checks.push_str(&format!(".min({})", length.range.start()));
+    }
+    if *length.range.end() < u64::from(u32::MAX) {
+        checks.push_str(&format!(".max({})", length.range.end()));
+    }
+    checks
+}
+
+/// The lengths of a collection as a `refine` of its `property`, for schemas without
+/// length checks of their own.
+fn refine_length(length: &ir::Length, property: &str) -> String {
+    let (start, end) = (*length.range.start(), *length.range.end());
+    let check = match (start > 0, end < u64::from(u32::MAX)) {
+        _ if start == end => format!("value.{} === {}", property, start),
+        (true, true) => format!("value.{0} >= {1} && value.{0} <= {2}", property, start, end),
+        (true, false) => format!("value.{} >= {}", property, start),
+        (false, true) => format!("value.{} <= {}", property, end),
+        (false, false) => return String::new(),
+    };
+    format!(".refine((value) => {})", check)
+}
@@ -1,5 +1,8 @@
 // Core binary reading/writing utilities
 
+// How many bytes a length prefix is: 4 in Borsh by default
+export type LengthWidth = 1 | 2 | 4 | 8
+
 export class BinaryWriter {
   private buffer: Uint8Array
   private view: DataView
@@ -111,9 +114,27 @@ export class BinaryWriter {
     this.writeUint8(value ? 1 : 0)
   }
 
-  writeString(value: string): void {
+  writeLength(length: number, width: LengthWidth = 4): void {
+    if (width < 8 && length >= 2 ** (8 * width)) {
+      throw new Error(`Length ${length} does not fit in a ${width}-byte length prefix`)
+    }
+    switch (width) {
+      case 1:
+        return this.writeUint8(length)
+      case 2:
+        return this.writeUint16(length)
+      case 4:
+        return this.writeUint32(length)
+      case 8:
+        return this.writeUint64(BigInt(length))
+      default:
+        throw new Error(`Unsupported length prefix width: ${width}`)
+    }
+  }
+
+  writeString(value: string, lengthWidth: LengthWidth = 4): void {
     const bytes = new TextEncoder().encode(value)
-    this.writeUint32(bytes.length)
+    this.writeLength(bytes.length, lengthWidth)
     this.ensureCapacity(bytes.length)
     this.buffer.set(bytes, this.offset)
     this.offset += bytes.length
@@ -211,8 +232,28 @@ export class BinaryReader {
     return Boolean(this.readUint8())
   }
 
-  readString(): string {
-    const length = this.readUint32()
+  readLength(width: LengthWidth = 4): number {
+    switch (width) {
+      case 1:
+        return this.readUint8()
+      case 2:
+        return this.readUint16()
+      case 4:
+        return this.readUint32()
+      case 8: {
+        const length = this.readUint64()
+        if (length > BigInt(Number.MAX_SAFE_INTEGER)) {
+          throw new Error(`Length ${length} is too large to read`)
+        }
+        return Number(length)
+      }
+      default:
+        throw new Error(`Unsupported length prefix width: ${width}`)
+    }
+  }
+
+  readString(lengthWidth: LengthWidth = 4): string {
+    const length = this.readLength(lengthWidth)
     const bytes = this.buffer.slice(this.offset, this.offset + length)
     this.offset += length
     return new TextDecoder().decode(bytes)
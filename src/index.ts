@@ -1,2 +1,4 @@
+export type { LengthWidth } from "./binary-io"
+export type { LengthOptions } from "./registry"
 export { b, Schema } from "./schema"
 export type { EnumLike } from "./types"
@@ -1,4 +1,4 @@
-import type { BinaryReader, BinaryWriter } from "./binary-io"
+import type { BinaryReader, BinaryWriter, LengthWidth } from "./binary-io"
 
 export interface TypeHandler<TValue, TOptions = unknown> {
   write: (writer: BinaryWriter, value: TValue, options?: TOptions) => void
@@ -24,6 +24,12 @@ export class TypeRegistry {
 // Create and initialize the global registry with primitive types
 export const registry = new TypeRegistry()
 
+// Options of the types with a length prefix, which Rust schemas may declare narrower or
+// wider than Borsh's default u32
+export interface LengthOptions {
+  lengthWidth?: LengthWidth
+}
+
 // Register all primitive type handlers
 
 // Unsigned integers
@@ -153,9 +159,9 @@ registry.register<boolean>("bool", {
   read: (reader) => reader.readBool(),
 })
 
-registry.register<string>("string", {
-  write: (writer, value) => writer.writeString(value),
-  read: (reader) => reader.readString(),
+registry.register<string, LengthOptions | null>("string", {
+  write: (writer, value, options) => writer.writeString(value, options?.lengthWidth),
+  read: (reader, options) => reader.readString(options?.lengthWidth),
 })
 
 registry.register<Record<string, never>>("unit", {
@@ -191,7 +197,7 @@ registry.register<Record<string, unknown>, StructFields>("struct", {
 })
 
 // Add Vec handler - handles dynamic-length arrays
-interface VecOptions<T> {
+interface VecOptions<T> extends LengthOptions {
   elementType: string
   elementOptions: T
 }
@@ -199,10 +205,10 @@ interface VecOptions<T> {
 registry.register<unknown[], VecOptions<unknown>>("vec", {
   write: (writer, value, options) => {
     if (!options) return
-    const { elementType, elementOptions } = options
+    const { elementType, elementOptions, lengthWidth } = options
 
     const length = value.length
-    writer.writeUint32(length)
+    writer.writeLength(length, lengthWidth)
 
     const handler = registry.getHandler(elementType)
     for (const item of value) {
@@ -211,8 +217,8 @@ registry.register<unknown[], VecOptions<unknown>>("vec", {
   },
   read: (reader, options) => {
     if (!options) return []
-    const { elementType, elementOptions } = options
-    const length = reader.readUint32()
+    const { elementType, elementOptions, lengthWidth } = options
+    const length = reader.readLength(lengthWidth)
     const handler = registry.getHandler(elementType)
 
     return Array.from({ length }, () => handler.read(reader, elementOptions))
@@ -220,7 +226,7 @@ registry.register<unknown[], VecOptions<unknown>>("vec", {
 })
 
 // Add HashSet handler
-interface SetOptions<T> {
+interface SetOptions<T> extends LengthOptions {
   elementType: string
   elementOptions: T
 }
@@ -228,9 +234,9 @@ interface SetOptions<T> {
 registry.register<Set<unknown>, SetOptions<unknown>>("set", {
   write: (writer, value, options) => {
     if (!options) return
-    const { elementType, elementOptions } = options
+    const { elementType, elementOptions, lengthWidth } = options
     const array = Array.from(value).sort()
-    writer.writeUint32(array.length)
+    writer.writeLength(array.length, lengthWidth)
     const handler = registry.getHandler<unknown>(elementType)
     for (const item of array) {
       handler.write(writer, item, elementOptions)
@@ -238,8 +244,8 @@ registry.register<Set<unknown>, SetOptions<unknown>>("set", {
   },
   read: (reader, options) => {
     if (!options) return new Set()
-    const { elementType, elementOptions } = options
-    const length = reader.readUint32()
+    const { elementType, elementOptions, lengthWidth } = options
+    const length = reader.readLength(lengthWidth)
     const handler = registry.getHandler<unknown>(elementType)
     const items = Array.from({ length }, () => handler.read(reader, elementOptions))
     return new Set(items)
@@ -247,7 +253,7 @@ registry.register<Set<unknown>, SetOptions<unknown>>("set", {
 })
 
 // Add HashMap handler
-interface MapOptions<K, V> {
+interface MapOptions<K, V> extends LengthOptions {
   keyType: string
   keyOptions: K
   valueType: string
@@ -257,9 +263,9 @@ interface MapOptions<K, V> {
 registry.register<Map<unknown, unknown>, MapOptions<unknown, unknown>>("map", {
   write: (writer, value, options) => {
     if (!options) return
-    const { keyType, keyOptions, valueType, valueOptions } = options
+    const { keyType, keyOptions, valueType, valueOptions, lengthWidth } = options
     const entries = Array.from(value.entries()).sort()
-    writer.writeUint32(entries.length)
+    writer.writeLength(entries.length, lengthWidth)
     const keyHandler = registry.getHandler<unknown>(keyType)
     const valueHandler = registry.getHandler<unknown>(valueType)
     for (const [key, val] of entries) {
@@ -269,8 +275,8 @@ registry.register<Map<unknown, unknown>, MapOptions<unknown, unknown>>("map", {
   },
   read: (reader, options) => {
     if (!options) return new Map()
-    const { keyType, keyOptions, valueType, valueOptions } = options
-    const length = reader.readUint32()
+    const { keyType, keyOptions, valueType, valueOptions, lengthWidth } = options
+    const length = reader.readLength(lengthWidth)
     const keyHandler = registry.getHandler<unknown>(keyType)
     const valueHandler = registry.getHandler<unknown>(valueType)
     const entries = Array.from({ length }, () => {
@@ -432,7 +438,7 @@ registry.register<unknown[], ArrayOptions<unknown>>("array", {
 })
 
 // Add bytes handler - handles raw byte sequences as Uint8Array
-export interface BytesOptions {
+export interface BytesOptions extends LengthOptions {
   length?: number
 }
 
@@ -448,14 +454,15 @@ registry.register<Uint8Array, BytesOptions>("bytes", {
         writer.writeUint8(value[i] as number)
       }
     } else {
-      writer.writeUint32(length)
+      writer.writeLength(length, options?.lengthWidth)
       for (let i = 0; i < length; i++) {
         writer.writeUint8(value[i] as number)
       }
     }
   },
   read: (reader, options) => {
-    const length = options?.length != null ? options.length : reader.readUint32()
+    const length =
+      options?.length != null ? options.length : reader.readLength(options?.lengthWidth)
     const bytes = new Uint8Array(length)
     for (let i = 0; i < length; i++) {
       bytes[i] = reader.readUint8()
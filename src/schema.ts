@@ -1,5 +1,5 @@
 import { BinaryReader, BinaryWriter } from "./binary-io"
-import { registry, type TypeRegistry } from "./registry"
+import { type LengthOptions, registry, type TypeRegistry } from "./registry"
 import type { EnumLike, VecType } from "./types"
 
 export class Schema<T, Type extends string = string> {
@@ -23,6 +23,12 @@ export class Schema<T, Type extends string = string> {
   }
 }
 
+// Only a length prefix other than Borsh's default u32 is kept in a schema's options
+function lengthOptions(options?: LengthOptions): LengthOptions | undefined {
+  if (options?.lengthWidth == null || options.lengthWidth === 4) return undefined
+  return { lengthWidth: options.lengthWidth }
+}
+
 // First declare the namespace type (for type-level stuff)
 export namespace b {
   export type infer<T extends Schema<unknown>> = T extends Schema<infer U> ? U : never
@@ -52,33 +58,43 @@ export const b = {
 
   // Other primitives
   bool: () => new Schema<boolean, "bool">("bool", null, registry),
-  string: () => new Schema<string, "string">("string", null, registry),
+  string: (options?: LengthOptions) =>
+    new Schema<string, "string">("string", lengthOptions(options) ?? null, registry),
   unit: () => new Schema<Record<string, never>, "unit">("unit", null, registry),
 
   // Complex container types
-  vec: <T extends Schema<unknown>>(elementSchema: T): Schema<VecType<T>> => {
+  vec: <T extends Schema<unknown>>(
+    elementSchema: T,
+    options?: LengthOptions,
+  ): Schema<VecType<T>> => {
     return new Schema(
       "vec",
       {
         elementType: elementSchema.type,
         elementOptions: elementSchema.options,
+        ...lengthOptions(options),
       },
       registry,
     )
   },
 
-  hashSet: <T>(elementSchema: Schema<T>): Schema<Set<T>> => {
+  hashSet: <T>(elementSchema: Schema<T>, options?: LengthOptions): Schema<Set<T>> => {
     return new Schema(
       "set",
       {
         elementType: elementSchema.type,
         elementOptions: elementSchema.options,
+        ...lengthOptions(options),
       },
       registry,
     )
   },
 
-  hashMap: <K, V>(keySchema: Schema<K>, valueSchema: Schema<V>): Schema<Map<K, V>> => {
+  hashMap: <K, V>(
+    keySchema: Schema<K>,
+    valueSchema: Schema<V>,
+    options?: LengthOptions,
+  ): Schema<Map<K, V>> => {
     return new Schema(
       "map",
       {
@@ -86,6 +102,7 @@ export const b = {
         keyOptions: keySchema.options,
         valueType: valueSchema.type,
         valueOptions: valueSchema.options,
+        ...lengthOptions(options),
       },
       registry,
     )
@@ -113,8 +130,12 @@ export const b = {
       registry,
     )
   },
-  bytes: (length?: number): Schema<Uint8Array, "bytes"> => {
-    return new Schema("bytes", length != null ? { length } : {}, registry)
+  // A fixed length, or the options of the length prefix of a variable one
+  bytes: (length?: number | LengthOptions): Schema<Uint8Array, "bytes"> => {
+    if (typeof length === "number") {
+      return new Schema("bytes", { length }, registry)
+    }
+    return new Schema("bytes", lengthOptions(length) ?? {}, registry)
   },
 
   // Enum type
@@ -162,6 +183,24 @@ export const b = {
     return new Schema("tuple", types, registry)
   },
 
+  // Values of another schema, converted with `decode` when read and `encode` when written.
+  // Schemas nested in others are encoded by the handler registered for their type, so the
+  // conversion is registered under `type`, which must name it uniquely: registering the
+  // same type again replaces the conversion of every schema of that type
+  transform: <T, U>(
+    schema: Schema<T>,
+    type: string,
+    decode: (value: T) => U,
+    encode: (value: U) => T,
+  ): Schema<U> => {
+    const handler = registry.getHandler<T>(schema.type)
+    registry.register<U>(type, {
+      write: (writer, value, options) => handler.write(writer, encode(value), options),
+      read: (reader, options) => decode(handler.read(reader, options)),
+    })
+    return new Schema(type, schema.options, registry)
+  },
+
   // Native TypeScript enum
   nativeEnum: <T extends EnumLike>(enumObj: T): Schema<T[keyof T], "nativeEnum"> => {
     // First, filter out numeric keys that TypeScript adds to enums
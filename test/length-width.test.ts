@@ -0,0 +1,48 @@
+import { describe, expect, test } from "vitest"
+import { b } from "../src/schema"
+
+describe("length prefix widths", () => {
+  test("collections write a prefix of the given width", () => {
+    expect(b.vec(b.u8(), { lengthWidth: 1 }).serialize([7, 8])).toEqual(new Uint8Array([2, 7, 8]))
+    expect(b.hashSet(b.u8(), { lengthWidth: 2 }).serialize(new Set([7]))).toEqual(
+      new Uint8Array([1, 0, 7]),
+    )
+    expect(b.hashMap(b.u8(), b.u8(), { lengthWidth: 8 }).serialize(new Map([[1, 2]]))).toEqual(
+      new Uint8Array([1, 0, 0, 0, 0, 0, 0, 0, 1, 2]),
+    )
+    expect(b.bytes({ lengthWidth: 1 }).serialize(new Uint8Array([9]))).toEqual(
+      new Uint8Array([1, 9]),
+    )
+    expect(b.string({ lengthWidth: 2 }).serialize("hi")).toEqual(new Uint8Array([2, 0, 104, 105]))
+  })
+
+  test("values round-trip", () => {
+    const schema = b.struct({
+      name: b.string({ lengthWidth: 1 }),
+      scores: b.vec(b.u32(), { lengthWidth: 2 }),
+      tags: b.hashSet(b.string(), { lengthWidth: 8 }),
+      data: b.bytes({ lengthWidth: 2 }),
+    })
+    const value = {
+      name: "player",
+      scores: [1, 2, 3],
+      tags: new Set(["a", "b"]),
+      data: new Uint8Array([1, 2]),
+    }
+    expect(schema.deserialize(schema.serialize(value))).toEqual(value)
+  })
+
+  test("a width of 4 is the default", () => {
+    expect(b.vec(b.u8(), { lengthWidth: 4 }).options).toEqual(b.vec(b.u8()).options)
+    expect(b.bytes({ lengthWidth: 4 }).serialize(new Uint8Array([1]))).toEqual(
+      b.bytes().serialize(new Uint8Array([1])),
+    )
+  })
+
+  test("lengths that don't fit in the prefix throw", () => {
+    const schema = b.vec(b.u8(), { lengthWidth: 1 })
+    expect(() => schema.serialize(Array(256).fill(0))).toThrow(
+      "Length 256 does not fit in a 1-byte length prefix",
+    )
+  })
+})
@@ -0,0 +1,43 @@
+// Generated by zorsh-gen with `--module-format commonjs --runtime-module ../../src/index
+// --codec-functions --no-semicolons` for `struct Roster { names: Capped }`, where `Capped`
+// is a list of at most 8 bytes, so that type-checking the tests checks CommonJS output
+import index = require("../../src/index")
+import b = index.b
+import Schema = index.Schema
+
+function checkLength(length: number, min: number, max: number, path: string): void {
+  if (length < min || length > max) {
+    throw new Error(`${path} must have between ${min} and ${max} elements, got ${length}`)
+  }
+}
+
+function withValidation<T>(schema: Schema<T>, validate: (value: T) => void): Schema<T> {
+  // A wrapper of its own, since `schema` may be another exported schema
+  const validated = Object.create(schema) as Schema<T>
+  validated.serialize = (value: T) => {
+    validate(value)
+    return schema.serialize(value)
+  }
+  return validated
+}
+
+namespace exported {
+  export const RosterSchema = withValidation(b.struct({
+    names: b.bytes(),
+  }), validateRoster)
+  export type Roster = b.infer<typeof RosterSchema>
+
+  export function validateRoster(value: Roster): void {
+    checkLength(value.names.length, 0, 8, "Roster.names")
+  }
+
+  export function serializeRoster(value: Roster): Uint8Array {
+    return RosterSchema.serialize(value)
+  }
+
+  export function deserializeRoster(bytes: Uint8Array): Roster {
+    return RosterSchema.deserialize(bytes)
+  }
+}
+
+export = exported
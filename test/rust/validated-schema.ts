@@ -0,0 +1,35 @@
+// Generated by zorsh-gen with `--newtypes flatten --no-semicolons` for
+// `struct Wrapper(Inner)`, where `Inner` has a field of at most 8 bytes
+import { b, type Schema } from "../../src/schema"
+
+function checkLength(length: number, min: number, max: number, path: string): void {
+  if (length < min || length > max) {
+    throw new Error(`${path} must have between ${min} and ${max} elements, got ${length}`)
+  }
+}
+
+function withValidation<T>(schema: Schema<T>, validate: (value: T) => void): Schema<T> {
+  // A wrapper of its own, since `schema` may be another exported schema
+  const validated = Object.create(schema) as Schema<T>
+  validated.serialize = (value: T) => {
+    validate(value)
+    return schema.serialize(value)
+  }
+  return validated
+}
+
+export const InnerSchema = withValidation(b.struct({
+  names: b.bytes(),
+}), validateInner)
+export type Inner = b.infer<typeof InnerSchema>
+
+export function validateInner(value: Inner): void {
+  checkLength(value.names.length, 0, 8, "Inner.names")
+}
+
+export const WrapperSchema = withValidation(InnerSchema, validateWrapper)
+export type Wrapper = b.infer<typeof WrapperSchema>
+
+export function validateWrapper(value: Wrapper): void {
+  validateInner(value)
+}
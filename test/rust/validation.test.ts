@@ -0,0 +1,20 @@
+import { describe, expect, test } from "vitest"
+import { InnerSchema, WrapperSchema } from "./validated-schema"
+
+describe("Generated validation", () => {
+  test("validating a flattened newtype leaves the inner schema unchanged", () => {
+    // The newtype's schema wraps InnerSchema rather than patching it
+    expect(WrapperSchema).not.toBe(InnerSchema)
+    expect(Object.hasOwn(WrapperSchema, "serialize")).toBe(true)
+    expect(InnerSchema.serialize).not.toBe(WrapperSchema.serialize)
+
+    const value = { names: new Uint8Array([1, 2, 3]) }
+    expect(WrapperSchema.deserialize(WrapperSchema.serialize(value))).toEqual(value)
+    expect(InnerSchema.serialize(value)).toEqual(WrapperSchema.serialize(value))
+
+    const tooLong = { names: new Uint8Array(9) }
+    const error = "Inner.names must have between 0 and 8 elements, got 9"
+    expect(() => WrapperSchema.serialize(tooLong)).toThrow(error)
+    expect(() => InnerSchema.serialize(tooLong)).toThrow(error)
+  })
+})
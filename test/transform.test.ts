@@ -0,0 +1,49 @@
+import { describe, expect, test } from "vitest"
+import { b } from "../src/schema"
+
+describe("transformed schemas", () => {
+  const cents = b.transform(
+    b.u64(),
+    "u64AsCents",
+    (value) => Number(value) / 100,
+    (value) => BigInt(Math.round(value * 100)),
+  )
+
+  test("values are converted when written and read", () => {
+    expect(cents.serialize(1.5)).toEqual(b.u64().serialize(150n))
+    expect(cents.deserialize(b.u64().serialize(150n))).toBe(1.5)
+  })
+
+  test("nested schemas use the conversion", () => {
+    const schema = b.struct({
+      prices: b.vec(cents),
+      discount: b.option(cents),
+    })
+    const value = { prices: [0.25, 10], discount: 1 }
+    expect(schema.deserialize(schema.serialize(value))).toEqual(value)
+  })
+
+  test("the wrapped schema's options are kept", () => {
+    const name = b.transform(
+      b.string({ lengthWidth: 1 }),
+      "stringUpperCase",
+      (value) => value.toUpperCase(),
+      (value) => value.toLowerCase(),
+    )
+    expect(name.serialize("HI")).toEqual(new Uint8Array([2, 104, 105]))
+    expect(name.deserialize(new Uint8Array([2, 104, 105]))).toBe("HI")
+  })
+
+  test("errors thrown by a conversion propagate", () => {
+    const positive = b.transform(
+      b.i32(),
+      "i32Positive",
+      (value) => value,
+      (value) => {
+        if (value <= 0) throw new RangeError(`${value} isn't positive`)
+        return value
+      },
+    )
+    expect(() => b.vec(positive).serialize([1, -1])).toThrow("-1 isn't positive")
+  })
+})